@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CcmError;
+use crate::git;
+use crate::naming;
+use crate::session::Session;
+use crate::worktree;
+
+/// Plans of a session, relative to its worktree.
+pub const PLANS_DIR: &str = ".ccm/plans";
+
+/// Closed sessions, one directory each, next to state.json.
+pub fn dir_for(state_path: &Path) -> PathBuf {
+    state_path.with_file_name("archive")
+}
+
+/// A closed session: `entry.json` and a copy of its plans in `plans/`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// The session as it was when closed (its final `claude_status` included).
+    pub session: Session,
+    pub closed_at: DateTime<Utc>,
+    pub merged: bool,
+    /// Branch checked out in the worktree at close; None for detached HEAD or
+    /// jj workspaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// Commit the worktree was on at close. A close without merge deletes the
+    /// branch; reopen recreates it here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head: Option<String>,
+    /// File names of the archived plans.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub plans: Vec<String>,
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+impl Entry {
+    pub fn duration(&self) -> chrono::Duration {
+        self.closed_at - self.session.created_at
+    }
+
+    /// Path of an archived plan.
+    pub fn plan_path(&self, plan: &str) -> PathBuf {
+        self.dir.join("plans").join(plan)
+    }
+}
+
+/// What has to be read from a session's worktree before close removes it.
+pub struct Capture {
+    branch: Option<String>,
+    head: Option<String>,
+    plans: Vec<(String, Vec<u8>)>,
+}
+
+impl Capture {
+    /// Read the branch and plans of `session`. Best-effort: a worktree that
+    /// is already gone yields an empty capture.
+    pub fn of(session: &Session) -> Self {
+        let plans = fs::read_dir(Path::new(&session.cwd).join(PLANS_DIR))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|e| e.file_type().is_ok_and(|t| t.is_file()))
+            .filter_map(|e| {
+                let content = fs::read(e.path()).ok()?;
+                Some((e.file_name().to_string_lossy().into_owned(), content))
+            })
+            .collect();
+        Self {
+            branch: git::current_branch(&session.cwd).ok(),
+            head: git::run(&session.cwd, &["rev-parse", "HEAD"]).ok(),
+            plans,
+        }
+    }
+
+    /// Archive `session`, closed now, under `archive`.
+    pub fn save(self, archive: &Path, session: Session, merged: bool) -> Result<Entry, CcmError> {
+        let closed_at = Utc::now();
+        let slug = naming::clean(&session.name).replace('/', "-");
+        let name = format!("{}-{slug}", closed_at.format("%Y%m%dT%H%M%S"));
+        let dir = archive.join(naming::unique(&name, |n| archive.join(n).exists()));
+        let plans_dir = dir.join("plans");
+        fs::create_dir_all(&plans_dir)?;
+        let mut plans = Vec::new();
+        for (name, content) in self.plans {
+            fs::write(plans_dir.join(&name), content)?;
+            plans.push(name);
+        }
+        plans.sort();
+
+        let entry = Entry {
+            session,
+            closed_at,
+            merged,
+            branch: self.branch,
+            head: self.head,
+            plans,
+            dir,
+        };
+        fs::write(
+            entry.dir.join("entry.json"),
+            serde_json::to_string_pretty(&entry)?,
+        )?;
+        Ok(entry)
+    }
+}
+
+/// All entries under `archive`, oldest first. Directories without a readable
+/// `entry.json` are skipped.
+pub fn list(archive: &Path) -> Result<Vec<Entry>, CcmError> {
+    let dirs = match fs::read_dir(archive) {
+        Ok(d) => d,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries: Vec<Entry> = dirs
+        .flatten()
+        .filter_map(|d| {
+            let data = fs::read_to_string(d.path().join("entry.json")).ok()?;
+            let mut entry: Entry = serde_json::from_str(&data).ok()?;
+            entry.dir = d.path();
+            Some(entry)
+        })
+        .collect();
+    entries.sort_by(|a, b| (a.closed_at, &a.dir).cmp(&(b.closed_at, &b.dir)));
+    Ok(entries)
+}
+
+/// The most recently closed entry named `name`, or whose branch a provider
+/// with `branch_prefix` made from it (`feat` for `gj/main-feat`).
+pub fn find(archive: &Path, name: &str, branch_prefix: Option<&str>) -> Result<Entry, CcmError> {
+    list(archive)?
+        .into_iter()
+        .rev()
+        .find(|e| {
+            let base = e.session.base_ref.as_deref();
+            worktree::is_named(&e.session.name, name, branch_prefix, base)
+        })
+        .ok_or_else(|| CcmError::Archive(format!("no archived session '{name}'")))
+}
+
+/// Delete `entry` from the archive (after it has been reopened).
+pub fn remove(entry: &Entry) -> Result<(), CcmError> {
+    fs::remove_dir_all(&entry.dir)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, cwd: &Path) -> Session {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "tab_id": 1,
+            "claude_pane_id": 2,
+            "cwd": cwd,
+            "base_ref": "main",
+            "created_at": "2025-01-01T00:00:00Z",
+            "claude_status": "✳ Done",
+            "tags": ["auth"],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn save_and_list_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir_for(&dir.path().join("state.json"));
+        let wt = dir.path().join("wt");
+        fs::create_dir_all(wt.join(PLANS_DIR)).unwrap();
+        fs::write(wt.join(PLANS_DIR).join("b.md"), "# B\n").unwrap();
+        fs::write(wt.join(PLANS_DIR).join("a.md"), "# A\n").unwrap();
+        assert!(list(&archive).unwrap().is_empty());
+
+        let s = session("gj/main-feat", &wt);
+        let saved = Capture::of(&s).save(&archive, s, true).unwrap();
+        assert_eq!(saved.plans, ["a.md", "b.md"]);
+        assert_eq!(saved.branch, None);
+        assert_eq!(
+            fs::read_to_string(saved.plan_path("a.md")).unwrap(),
+            "# A\n"
+        );
+
+        let entries = list(&archive).unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.dir, saved.dir);
+        assert_eq!(entry.session.name, "gj/main-feat");
+        assert_eq!(entry.session.claude_status.as_deref(), Some("✳ Done"));
+        assert_eq!(entry.session.tags, ["auth"]);
+        assert!(entry.merged);
+        assert!(entry.duration() > chrono::Duration::zero());
+    }
+
+    #[test]
+    fn capture_of_missing_worktree_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let s = session("gone", &dir.path().join("gone"));
+        let capture = Capture::of(&s);
+        assert!(capture.plans.is_empty());
+        assert_eq!(capture.branch, None);
+        assert_eq!(capture.head, None);
+    }
+
+    #[test]
+    fn find_returns_latest_and_remove_deletes() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        let wt = dir.path().join("wt");
+        for merged in [false, true] {
+            let s = session("gj/main-feat", &wt);
+            Capture::of(&s).save(&archive, s, merged).unwrap();
+        }
+        let s = session("gj/main-fix-other", &wt);
+        Capture::of(&s).save(&archive, s, false).unwrap();
+        // Not an entry
+        fs::create_dir_all(archive.join("stray")).unwrap();
+
+        let gj = Some("gj/");
+        assert_eq!(list(&archive).unwrap().len(), 3);
+        let entry = find(&archive, "feat", gj).unwrap();
+        assert!(entry.merged);
+        assert_eq!(
+            find(&archive, "gj/main-fix-other", None)
+                .unwrap()
+                .session
+                .name,
+            "gj/main-fix-other"
+        );
+        assert!(find(&archive, "fix-other", gj).is_ok());
+        let err = find(&archive, "other", gj).unwrap_err();
+        assert_eq!(err.to_string(), "archive: no archived session 'other'");
+        assert!(find(&archive, "feat", None).is_err());
+
+        remove(&entry).unwrap();
+        assert!(!find(&archive, "feat", gj).unwrap().merged);
+        assert_eq!(list(&archive).unwrap().len(), 2);
+    }
+}
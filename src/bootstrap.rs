@@ -0,0 +1,294 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Deserialize;
+
+use crate::checks;
+use crate::error::CcmError;
+use crate::git;
+
+/// Bootstrap spec, relative to the main checkout.
+pub const SPEC_FILE: &str = ".ccm/bootstrap.toml";
+/// Written to the new worktree once bootstrap has finished: `ok` or
+/// `failed: <reason>`. `ccm wrap --wait-for` waits for it before starting claude.
+pub const STATUS_FILE: &str = ".ccm/bootstrap.status";
+/// How long `ccm wrap --wait-for` waits for the status file before starting
+/// claude anyway.
+pub const WAIT_TIMEOUT: Duration = Duration::from_secs(15 * 60);
+
+/// What a fresh worktree needs before claude can work in it, read from
+/// `.ccm/bootstrap.toml` in the main checkout:
+///
+/// ```toml
+/// copy = [".env", "config/local.yml"]
+/// symlink = ["node_modules"]
+/// commands = ["npm ci"]
+/// ```
+#[derive(Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Spec {
+    /// Files or directories copied from the main checkout.
+    pub copy: Vec<String>,
+    /// Files or directories linked to the main checkout (shared, not copied).
+    pub symlink: Vec<String>,
+    /// Commands run with `sh -c` in the new worktree, in order.
+    pub commands: Vec<String>,
+}
+
+impl Spec {
+    /// Load the spec of the main checkout `main`, or None if it has none.
+    pub fn load(main: &Path) -> Result<Option<Spec>, CcmError> {
+        let path = main.join(SPEC_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let spec: Spec = toml::from_str(&content)
+            .map_err(|e| CcmError::Bootstrap(format!("invalid {}: {e}", path.display())))?;
+        spec.validate()?;
+        Ok(Some(spec))
+    }
+
+    /// Entries must stay inside the repository.
+    fn validate(&self) -> Result<(), CcmError> {
+        for entry in self.copy.iter().chain(&self.symlink) {
+            let inside = Path::new(entry)
+                .components()
+                .all(|c| matches!(c, Component::Normal(_) | Component::CurDir));
+            if entry.is_empty() || !inside {
+                return Err(CcmError::Bootstrap(format!(
+                    "'{entry}' must be a relative path inside the repository"
+                )));
+            }
+        }
+        if self.commands.iter().any(|c| c.trim().is_empty()) {
+            return Err(CcmError::Bootstrap("commands must not be empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// The checkout whose spec applies to sessions created from `cwd`: the main
+/// worktree of its repository, or `cwd` itself outside of git (jj).
+pub fn source_dir(cwd: &str) -> PathBuf {
+    PathBuf::from(git::main_worktree(cwd).unwrap_or_else(|_| cwd.to_string()))
+}
+
+/// Copy and link the spec's files from `main` into `worktree`, then run its
+/// commands there, printing progress to stdout. Existing files in the worktree
+/// are left alone and missing sources are skipped with a warning.
+pub fn run(spec: &Spec, main: &Path, worktree: &Path) -> Result<(), CcmError> {
+    for (entries, link) in [(&spec.copy, false), (&spec.symlink, true)] {
+        for entry in entries {
+            let (src, dst) = (main.join(entry), worktree.join(entry));
+            let verb = if link { "link" } else { "copy" };
+            if fs::symlink_metadata(&src).is_err() {
+                println!("==> {verb} {entry}: not found in {}, skipped", main.display());
+                continue;
+            }
+            if fs::symlink_metadata(&dst).is_ok() {
+                println!("==> {verb} {entry}: already exists, skipped");
+                continue;
+            }
+            println!("==> {verb} {entry}");
+            if let Some(parent) = dst.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            if link {
+                std::os::unix::fs::symlink(&src, &dst)?;
+            } else {
+                copy_recursive(&src, &dst)?;
+            }
+        }
+    }
+
+    for command in &spec.commands {
+        println!("==> {command}");
+        let status = Command::new("sh")
+            .args(["-c", command])
+            .current_dir(worktree)
+            .status()
+            .map_err(|e| CcmError::Bootstrap(format!("failed to run `{command}`: {e}")))?;
+        if !status.success() {
+            return Err(CcmError::Bootstrap(format!(
+                "`{command}` {}",
+                checks::describe(status)
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Copy a file, symlink or directory tree.
+fn copy_recursive(src: &Path, dst: &Path) -> Result<(), CcmError> {
+    let meta = fs::symlink_metadata(src)?;
+    if meta.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(src)?, dst)?;
+    } else if meta.is_dir() {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+    } else {
+        fs::copy(src, dst)?;
+    }
+    Ok(())
+}
+
+/// Record the outcome of bootstrap in the worktree's status file.
+pub fn write_status(worktree: &Path, result: &Result<(), CcmError>) -> Result<(), CcmError> {
+    let path = worktree.join(STATUS_FILE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+        // Keep .ccm out of git, as for plans
+        let gitignore = parent.join(".gitignore");
+        if !gitignore.exists() {
+            fs::write(gitignore, "*\n")?;
+        }
+    }
+    let status = match result {
+        Ok(()) => "ok".to_string(),
+        Err(e) => format!("failed: {e}"),
+    };
+    fs::write(path, status)?;
+    Ok(())
+}
+
+/// Block until the status file at `path` exists, for at most `timeout`.
+/// Returns the failure reason if bootstrap failed or did not finish in time.
+pub fn wait(path: &Path, timeout: Duration) -> Option<String> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Ok(status) = fs::read_to_string(path) {
+            return status
+                .trim()
+                .strip_prefix("failed: ")
+                .map(str::to_string);
+        }
+        if Instant::now() >= deadline {
+            return Some(format!(
+                "bootstrap did not finish within {}s",
+                timeout.as_secs()
+            ));
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn load_missing_spec_is_none() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(Spec::load(dir.path()).unwrap(), None);
+    }
+
+    #[test]
+    fn load_parses_and_validates() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec_path = dir.path().join(SPEC_FILE);
+        write(
+            &spec_path,
+            "copy = [\".env\"]\nsymlink = [\"node_modules\"]\ncommands = [\"npm ci\"]\n",
+        );
+        let spec = Spec::load(dir.path()).unwrap().unwrap();
+        assert_eq!(spec.copy, [".env"]);
+        assert_eq!(spec.symlink, ["node_modules"]);
+        assert_eq!(spec.commands, ["npm ci"]);
+
+        write(&spec_path, "copy = [\"../secrets\"]\n");
+        let err = Spec::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("inside the repository"));
+
+        write(&spec_path, "copy = [\"/etc/passwd\"]\n");
+        assert!(Spec::load(dir.path()).is_err());
+
+        write(&spec_path, "cmds = [\"x\"]\n");
+        let err = Spec::load(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("invalid"));
+    }
+
+    #[test]
+    fn run_copies_links_and_runs_commands() {
+        let main = tempfile::tempdir().unwrap();
+        let wt = tempfile::tempdir().unwrap();
+        write(&main.path().join(".env"), "SECRET=1\n");
+        write(&main.path().join("config/local/a.yml"), "a: 1\n");
+        write(&main.path().join("node_modules/pkg/index.js"), "\n");
+        write(&wt.path().join("keep.txt"), "worktree\n");
+        write(&main.path().join("keep.txt"), "main\n");
+
+        let spec = Spec {
+            copy: vec![
+                ".env".to_string(),
+                "config/local".to_string(),
+                "keep.txt".to_string(),
+                "missing".to_string(),
+            ],
+            symlink: vec!["node_modules".to_string()],
+            commands: vec!["cat .env > setup.out".to_string()],
+        };
+        run(&spec, main.path(), wt.path()).unwrap();
+
+        assert_eq!(fs::read_to_string(wt.path().join("setup.out")).unwrap(), "SECRET=1\n");
+        assert!(wt.path().join("config/local/a.yml").is_file());
+        assert_eq!(fs::read_to_string(wt.path().join("keep.txt")).unwrap(), "worktree\n");
+        assert!(!wt.path().join("missing").exists());
+        let link = wt.path().join("node_modules");
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_link(&link).unwrap(), main.path().join("node_modules"));
+    }
+
+    #[test]
+    fn run_stops_at_failing_command() {
+        let main = tempfile::tempdir().unwrap();
+        let wt = tempfile::tempdir().unwrap();
+        let spec = Spec {
+            commands: vec!["exit 4".to_string(), "touch never".to_string()],
+            ..Spec::default()
+        };
+        let err = run(&spec, main.path(), wt.path()).unwrap_err();
+        assert_eq!(err.to_string(), "bootstrap failed: `exit 4` exited with status 4");
+        assert!(!wt.path().join("never").exists());
+    }
+
+    #[test]
+    fn status_round_trip() {
+        let wt = tempfile::tempdir().unwrap();
+        let path = wt.path().join(STATUS_FILE);
+        write_status(wt.path(), &Ok(())).unwrap();
+        assert_eq!(wait(&path, WAIT_TIMEOUT), None);
+        assert_eq!(
+            fs::read_to_string(wt.path().join(".ccm/.gitignore")).unwrap(),
+            "*\n"
+        );
+
+        let failed = Err(CcmError::Bootstrap("`npm ci` exited with status 1".to_string()));
+        write_status(wt.path(), &failed).unwrap();
+        assert_eq!(
+            wait(&path, WAIT_TIMEOUT).as_deref(),
+            Some("bootstrap failed: `npm ci` exited with status 1")
+        );
+    }
+
+    #[test]
+    fn wait_times_out() {
+        let wt = tempfile::tempdir().unwrap();
+        assert_eq!(
+            wait(&wt.path().join(STATUS_FILE), Duration::ZERO).as_deref(),
+            Some("bootstrap did not finish within 0s")
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus, Stdio};
+
+use crate::error::CcmError;
+
+/// Where the output of merge checks goes.
+pub enum CheckOutput<'a> {
+    /// Stream to the terminal (`ccm close --merge`).
+    Inherit,
+    /// Write to a log file, for callers that own the terminal (the TUI).
+    Log(&'a Path),
+}
+
+/// Run the `[merge] checks` commands with `sh -c` in `cwd`, in order.
+/// Stops at the first failing command and returns it as `CcmError::Check`.
+pub fn run(checks: &[String], cwd: &str, output: CheckOutput) -> Result<(), CcmError> {
+    let mut log = match output {
+        CheckOutput::Inherit => None,
+        CheckOutput::Log(path) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Some(File::create(path)?)
+        }
+    };
+
+    for check in checks {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", check]).current_dir(cwd);
+        match log {
+            Some(ref mut file) => {
+                writeln!(file, "==> {check}")?;
+                cmd.stdin(Stdio::null())
+                    .stdout(file.try_clone()?)
+                    .stderr(file.try_clone()?);
+            }
+            None => println!("==> {check}"),
+        }
+
+        let status = cmd
+            .status()
+            .map_err(|e| CcmError::Check(format!("failed to run `{check}`: {e}")))?;
+        if !status.success() {
+            return Err(CcmError::Check(format!("`{check}` {}", describe(status))));
+        }
+    }
+    Ok(())
+}
+
+/// Log file for the TUI's merge checks of `session`, next to state.json.
+pub fn log_path(state_path: &Path, session: &str) -> PathBuf {
+    let name = session.replace(['/', '\\'], "-");
+    state_path.with_file_name(format!("merge-checks-{name}.log"))
+}
+
+/// Last non-blank line written to the log at `path` so far, to show the
+/// progress of checks running in the background.
+pub fn last_line(path: &Path) -> Option<String> {
+    let content = fs::read(path).ok()?;
+    String::from_utf8_lossy(&content)
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+pub fn describe(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("exited with status {code}"),
+        None => "was terminated by a signal".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checks(cmds: &[&str]) -> Vec<String> {
+        cmds.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn run_passes_when_all_succeed() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().to_string_lossy();
+        let log = dir.path().join("checks.log");
+        run(&checks(&["true", "pwd"]), &cwd, CheckOutput::Log(&log)).unwrap();
+
+        let content = fs::read_to_string(&log).unwrap();
+        assert!(content.contains("==> true\n==> pwd\n"));
+        assert!(content.contains(&*cwd));
+    }
+
+    #[test]
+    fn run_stops_at_first_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("nested/checks.log");
+        let err = run(
+            &checks(&["echo first", "exit 3", "touch never"]),
+            &dir.path().to_string_lossy(),
+            CheckOutput::Log(&log),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "merge check failed: `exit 3` exited with status 3"
+        );
+        assert!(!dir.path().join("never").exists());
+        assert!(fs::read_to_string(&log).unwrap().contains("first"));
+    }
+
+    #[test]
+    fn run_without_checks_is_ok() {
+        assert!(run(&[], "/nonexistent", CheckOutput::Inherit).is_ok());
+    }
+
+    #[test]
+    fn last_line_skips_blank_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("checks.log");
+        assert_eq!(last_line(&log), None);
+        fs::write(&log, "==> cargo test\nrunning 3 tests\n\n").unwrap();
+        assert_eq!(last_line(&log).as_deref(), Some("running 3 tests"));
+    }
+
+    #[test]
+    fn log_path_is_next_to_state() {
+        assert_eq!(
+            log_path(
+                Path::new("/home/u/.local/state/ccm/state.json"),
+                "gj/main-abc"
+            ),
+            PathBuf::from("/home/u/.local/state/ccm/merge-checks-gj-main-abc.log")
+        );
+    }
+}
@@ -16,9 +16,77 @@ pub enum Command {
         /// Working directory (defaults to current directory)
         #[arg(long)]
         cwd: Option<String>,
+        /// Branch, tag or commit to base the session on (defaults to the current branch)
+        #[arg(long, value_name = "REF")]
+        from: Option<String>,
+        /// What the session is for
+        #[arg(long = "desc", value_name = "TEXT")]
+        description: Option<String>,
+        /// Tag the session (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Linked issue or ticket (e.g. PROJ-123 or a URL)
+        #[arg(long)]
+        ticket: Option<String>,
+    },
+    /// List all sessions, grouped by repository
+    List {
+        /// Only sessions with this tag (repeatable; all must match)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Only sessions of this repository (its root path or directory name)
+        #[arg(long)]
+        repo: Option<String>,
+    },
+    /// Set a session's description, tags, ticket or notes; without options,
+    /// show them
+    Annotate {
+        /// Session name (optional - detects from current worktree if omitted)
+        name: Option<String>,
+        /// Set the description (empty to clear)
+        #[arg(long = "desc", value_name = "TEXT")]
+        description: Option<String>,
+        /// Add a tag (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        /// Remove a tag (repeatable)
+        #[arg(long = "untag", value_name = "TAG")]
+        untags: Vec<String>,
+        /// Set the linked ticket (empty to clear)
+        #[arg(long)]
+        ticket: Option<String>,
+        /// Append a note
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Show files changed by more than one session of the same repository
+    Conflicts,
+    /// Review a session's changes against its base
+    Diff {
+        /// Session name (optional - detects from current worktree if omitted)
+        name: Option<String>,
+    },
+    /// Browse closed sessions
+    Archive {
+        #[command(subcommand)]
+        command: ArchiveCommand,
+    },
+    /// Recreate an archived session on its branch (restored at its last commit if deleted)
+    Reopen {
+        /// Archived session name (or the name it was made from)
+        name: String,
+    },
+    /// Show the event journal: creation, status changes, merges and closes
+    History {
+        /// Only events of this session (full name or the name it was made from)
+        session: Option<String>,
+        /// Only events at or after this time (30m, 2h, 3d, 1w, 2025-01-31 or RFC 3339)
+        #[arg(long, value_name = "TIME")]
+        since: Option<String>,
+        /// Only events before this time (same forms as --since)
+        #[arg(long, value_name = "TIME")]
+        until: Option<String>,
     },
-    /// List all sessions
-    List,
     /// Switch to a session
     Switch {
         /// Session name
@@ -29,20 +97,67 @@ pub enum Command {
     Close {
         /// Session name (optional - detects from current worktree if omitted)
         name: Option<String>,
-        /// Merge the branch before closing (calls `gj exit --merge`)
+        /// Merge the branch into its base branch before closing
         #[arg(long)]
         merge: bool,
+        /// Also remove the worktree of an adopted session
+        #[arg(long)]
+        remove_worktree: bool,
+        /// Merge without running the `[merge] checks` commands
+        #[arg(long, requires = "merge")]
+        skip_checks: bool,
+    },
+    /// Create a session around an existing worktree or branch
+    Adopt {
+        /// Worktree path or branch name (defaults to the current directory)
+        target: Option<String>,
+        /// Session name (defaults to the checked-out branch)
+        #[arg(long)]
+        name: Option<String>,
+        /// Repository to look the branch up in (defaults to current directory)
+        #[arg(long)]
+        cwd: Option<String>,
     },
     /// Create a new session from a plan (opens editor)
     Plan {
         /// Working directory (defaults to current directory)
         #[arg(long)]
         cwd: Option<String>,
+        /// Branch, tag or commit to base the session on (defaults to the current branch)
+        #[arg(long, value_name = "REF")]
+        from: Option<String>,
+    },
+    /// Remove sessions whose panes are all gone
+    Prune {
+        /// Also remove their worktrees (without merging)
+        #[arg(long)]
+        remove_worktrees: bool,
+    },
+    /// Remove worktrees and branches left behind without a session
+    Gc {
+        /// Repository to clean up (defaults to current directory)
+        #[arg(long)]
+        cwd: Option<String>,
+        /// Remove every orphan without asking
+        #[arg(long, short = 'y')]
+        yes: bool,
     },
     /// Initialize config file with defaults
     Init,
     /// Reset the pane layout of the current session tab
     ResetLayout,
+    /// Reopen sessions whose panes are gone (e.g. after a terminal restart)
+    Restore {
+        /// Session name
+        #[arg(required_unless_present = "all", conflicts_with = "all")]
+        name: Option<String>,
+        /// Restore every session whose panes are gone and whose worktree still exists
+        #[arg(long)]
+        all: bool,
+        /// Start a new claude conversation instead of continuing the last one
+        #[arg(long)]
+        fresh: bool,
+    },
     /// Run the tab-watcher TUI sidebar (internal use)
     TabWatcher {
         /// Session name this watcher belongs to
@@ -54,6 +169,15 @@ pub enum Command {
         #[arg(long)]
         cwd: String,
     },
+    /// Bootstrap a new worktree from the main checkout's .ccm/bootstrap.toml (internal use)
+    Bootstrap {
+        /// Worktree to bootstrap
+        #[arg(long)]
+        cwd: String,
+        /// Main checkout holding the bootstrap spec
+        #[arg(long)]
+        main: String,
+    },
     /// Wrap a command in a PTY, intercepting OSC 0 title changes (internal)
     Wrap {
         /// Session name to update status for
@@ -62,8 +186,22 @@ pub enum Command {
         /// Optional file whose content is appended as a positional argument to the command
         #[arg(long)]
         prompt_file: Option<String>,
+        /// Wait (up to 15 minutes) until this bootstrap status file exists before running the command
+        #[arg(long)]
+        wait_for: Option<String>,
         /// Command and arguments to run
         #[arg(trailing_var_arg = true, required = true)]
         command: Vec<String>,
     },
 }
+
+#[derive(Subcommand)]
+pub enum ArchiveCommand {
+    /// List archived sessions, most recently closed first
+    List,
+    /// Show an archived session: metadata, outcome, duration and plans
+    Show {
+        /// Archived session name (or the name it was made from)
+        name: String,
+    },
+}
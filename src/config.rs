@@ -3,14 +3,43 @@ use std::path::PathBuf;
 
 use serde::Deserialize;
 
+use crate::mux::SplitDirection;
+use crate::naming;
+
 #[derive(Clone, Debug, Default, Deserialize)]
 #[serde(default)]
 pub struct Config {
+    pub mux: MuxConfig,
     pub wezterm: WeztermConfig,
+    pub tmux: TmuxConfig,
+    pub zellij: ZellijConfig,
+    pub kitty: KittyConfig,
+    pub worktree: WorktreeConfig,
+    pub git: GitConfig,
+    pub jj: JjConfig,
+    pub merge: MergeConfig,
+    pub naming: NamingConfig,
     pub layout: LayoutConfig,
+    pub aux_panes: Vec<AuxPane>,
     pub tui: TuiConfig,
 }
 
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MuxConfig {
+    pub backend: MuxBackend,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MuxBackend {
+    #[default]
+    Wezterm,
+    Tmux,
+    Zellij,
+    Kitty,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(default)]
 pub struct WeztermConfig {
@@ -18,18 +47,197 @@ pub struct WeztermConfig {
     pub claude_command: String,
 }
 
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TmuxConfig {
+    pub binary: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct ZellijConfig {
+    pub binary: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct KittyConfig {
+    pub binary: String,
+    /// Remote control address passed as `kitty @ --to` (e.g. `unix:/tmp/kitty`).
+    /// Not needed when ccm runs inside a kitty window.
+    pub socket: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct WorktreeConfig {
+    pub provider: WorktreeBackend,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorktreeBackend {
+    #[default]
+    Gj,
+    Git,
+    Jj,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct GitConfig {
+    /// Prepended to `<base>-<suffix>` to form the session branch name.
+    pub branch_prefix: String,
+    /// Where new worktrees are created. Relative paths are resolved against the
+    /// main worktree; `{repo}`, `{base}` and `{suffix}` are substituted.
+    pub worktree_path: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct JjConfig {
+    pub binary: String,
+    /// Bookmark that merged sessions land on.
+    pub trunk: String,
+    /// Session bookmarks are named `<prefix><suffix>`.
+    pub bookmark_prefix: String,
+    /// Where new workspaces are created, like `git.worktree_path`.
+    pub workspace_path: String,
+    pub merge: JjMergeStrategy,
+}
+
+/// How `close --merge` brings a jj session onto trunk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JjMergeStrategy {
+    /// Rebase the session's commits onto trunk as they are.
+    #[default]
+    Rebase,
+    /// Rebase, then squash the session's commits into a single commit.
+    Squash,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct MergeConfig {
+    /// Shell commands run in the session worktree before a merge. The first
+    /// failing command aborts the merge.
+    pub checks: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct NamingConfig {
+    /// Branch suffix handed to the worktree provider for new sessions. It is
+    /// not the full name: the provider puts its own prefix in front, so
+    /// `{user}/{ticket}-{slug}` gives `gj/main-alice/PROJ-1-x` with gj.
+    /// `{user}`, `{date}`, `{ticket}`, `{slug}` and `{rand}` are substituted.
+    pub template: String,
+    /// Regex finding the `{ticket}` id in a plan; its first capture group is
+    /// used if it has one.
+    pub ticket_pattern: String,
+    /// How `ccm plan` turns the plan's first line into `{slug}`.
+    pub slug: SlugMode,
+}
+
+/// Characters kept in slugs derived from plan titles. Every mode yields a
+/// valid git branch name and tab title.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SlugMode {
+    /// ASCII letters and digits only; other characters are dropped.
+    #[default]
+    Ascii,
+    /// Transliterate other scripts to ASCII first (`認証` -> `ren-zheng`).
+    Transliterate,
+    /// Keep letters and digits of any script (`認証機能の実装`).
+    Unicode,
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(default)]
 pub struct LayoutConfig {
+    /// Panes split off the claude pane, in order. When empty, the classic
+    /// watcher/shell/plans layout is built from the width/height keys below.
+    pub panes: Vec<LayoutPane>,
     pub watcher_width: u32,
     pub shell_height: u32,
     pub plans_width: u32,
 }
 
+/// One `[[layout.panes]]` entry.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct LayoutPane {
+    /// Unique name; pane IDs are tracked in state.json under this name.
+    pub name: String,
+    pub split: SplitDirection,
+    /// Pane to split: `claude` or the name of an earlier pane.
+    #[serde(default = "default_split_from")]
+    pub from: String,
+    /// Size of the new pane (%).
+    pub size: u32,
+    /// Program to run, with `{ccm}`, `{session}` and `{cwd}` substituted.
+    /// `None` opens a plain shell.
+    #[serde(default)]
+    pub command: Option<Vec<String>>,
+    /// If false, a failed split only prints a warning.
+    #[serde(default = "default_required")]
+    pub required: bool,
+}
+
+/// One `[[aux_panes]]` entry: an extra pane such as a dev server or log tail,
+/// opened after the layout panes. A failed split only prints a warning.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+pub struct AuxPane {
+    pub name: String,
+    pub split: SplitDirection,
+    /// Pane to split: `claude`, a layout pane or an earlier aux pane.
+    #[serde(default = "default_split_from")]
+    pub from: String,
+    pub size: u32,
+    /// Shell command line, run with `sh -c`. `{ccm}`, `{session}` and `{cwd}`
+    /// are passed to the shell as quoted parameters, so they need no quoting.
+    pub command: String,
+    #[serde(default)]
+    pub restart: RestartPolicy,
+    #[serde(default = "default_restart_delay_secs")]
+    pub restart_delay_secs: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RestartPolicy {
+    #[default]
+    Never,
+    OnFailure,
+    Always,
+}
+
+fn default_restart_delay_secs() -> u64 {
+    2
+}
+
+/// Name of the root pane of every session layout.
+pub const CLAUDE_PANE: &str = "claude";
+
+fn default_split_from() -> String {
+    CLAUDE_PANE.to_string()
+}
+
+fn default_required() -> bool {
+    true
+}
+
 #[derive(Clone, Debug, Deserialize)]
 #[serde(default)]
 pub struct TuiConfig {
     pub tick_interval_secs: u64,
+    /// How long a session may have no live panes before reconcile removes it.
+    pub dead_session_grace_secs: u64,
+    /// Keep dead sessions whose worktree still exists (for `ccm restore`)
+    /// past the grace period, until `ccm prune`. Without it they are dropped
+    /// from state and their worktree is left behind.
+    pub keep_dead_with_worktree: bool,
 }
 
 impl Default for WeztermConfig {
@@ -41,9 +249,66 @@ impl Default for WeztermConfig {
     }
 }
 
+impl Default for TmuxConfig {
+    fn default() -> Self {
+        Self {
+            binary: "tmux".to_string(),
+        }
+    }
+}
+
+impl Default for ZellijConfig {
+    fn default() -> Self {
+        Self {
+            binary: "zellij".to_string(),
+        }
+    }
+}
+
+impl Default for KittyConfig {
+    fn default() -> Self {
+        Self {
+            binary: "kitty".to_string(),
+            socket: None,
+        }
+    }
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            branch_prefix: "ccm/".to_string(),
+            worktree_path: "../{repo}-{suffix}".to_string(),
+        }
+    }
+}
+
+impl Default for JjConfig {
+    fn default() -> Self {
+        Self {
+            binary: "jj".to_string(),
+            trunk: "main".to_string(),
+            bookmark_prefix: "ccm/".to_string(),
+            workspace_path: "../{repo}-{suffix}".to_string(),
+            merge: JjMergeStrategy::Rebase,
+        }
+    }
+}
+
+impl Default for NamingConfig {
+    fn default() -> Self {
+        Self {
+            template: "{slug}".to_string(),
+            ticket_pattern: r"[A-Z][A-Z0-9]+-\d+".to_string(),
+            slug: SlugMode::Ascii,
+        }
+    }
+}
+
 impl Default for LayoutConfig {
     fn default() -> Self {
         Self {
+            panes: Vec::new(),
             watcher_width: 20,
             shell_height: 30,
             plans_width: 30,
@@ -51,10 +316,125 @@ impl Default for LayoutConfig {
     }
 }
 
+impl LayoutConfig {
+    /// The panes to build: `panes` if configured, else the classic layout.
+    pub fn effective_panes(&self) -> Vec<LayoutPane> {
+        if !self.panes.is_empty() {
+            return self.panes.clone();
+        }
+        let pane = |name: &str, split, size, command: Option<&[&str]>, required| LayoutPane {
+            name: name.to_string(),
+            split,
+            from: default_split_from(),
+            size,
+            command: command.map(|c| c.iter().map(|s| s.to_string()).collect()),
+            required,
+        };
+        vec![
+            pane(
+                "watcher",
+                SplitDirection::Left,
+                self.watcher_width,
+                Some(&["{ccm}", "tab-watcher", "--session", "{session}"]),
+                true,
+            ),
+            pane("shell", SplitDirection::Bottom, self.shell_height, None, true),
+            pane(
+                "plans",
+                SplitDirection::Right,
+                self.plans_width,
+                Some(&["{ccm}", "plan-viewer", "--cwd", "{cwd}"]),
+                false,
+            ),
+        ]
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if self.panes.is_empty() {
+            return self.validate_legacy();
+        }
+
+        let mut seen: Vec<&LayoutPane> = Vec::new();
+        for pane in &self.panes {
+            if pane.name.is_empty() {
+                anyhow::bail!("config error: layout.panes entries must have a name");
+            }
+            if pane.name == CLAUDE_PANE || seen.iter().any(|p| p.name == pane.name) {
+                anyhow::bail!("config error: duplicate layout pane name '{}'", pane.name);
+            }
+            if pane.size == 0 || pane.size >= 100 {
+                anyhow::bail!(
+                    "config error: layout pane '{}' size must be between 1 and 99",
+                    pane.name
+                );
+            }
+            if pane.command.as_ref().is_some_and(|c| c.is_empty()) {
+                anyhow::bail!(
+                    "config error: layout pane '{}' command must not be empty",
+                    pane.name
+                );
+            }
+            if pane.from != CLAUDE_PANE {
+                let Some(parent) = seen.iter().find(|p| p.name == pane.from) else {
+                    anyhow::bail!(
+                        "config error: layout pane '{}' splits from unknown or later pane '{}'",
+                        pane.name,
+                        pane.from
+                    );
+                };
+                if pane.required && !parent.required {
+                    anyhow::bail!(
+                        "config error: required layout pane '{}' cannot split from optional pane '{}'",
+                        pane.name,
+                        pane.from
+                    );
+                }
+            }
+            seen.push(pane);
+        }
+
+        // Side-by-side splits of one pane must leave it some width
+        for parent in std::iter::once(CLAUDE_PANE).chain(self.panes.iter().map(|p| p.name.as_str())) {
+            let width: u32 = self
+                .panes
+                .iter()
+                .filter(|p| p.from == parent && p.split != SplitDirection::Bottom)
+                .map(|p| p.size)
+                .sum();
+            if width >= 95 {
+                anyhow::bail!(
+                    "config error: left/right panes split from '{parent}' must total less than 95"
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn validate_legacy(&self) -> anyhow::Result<()> {
+        if self.watcher_width == 0 || self.watcher_width >= 100 {
+            anyhow::bail!("config error: layout.watcher_width must be between 1 and 99");
+        }
+        if self.shell_height == 0 || self.shell_height >= 100 {
+            anyhow::bail!("config error: layout.shell_height must be between 1 and 99");
+        }
+        if self.plans_width == 0 || self.plans_width >= 100 {
+            anyhow::bail!("config error: layout.plans_width must be between 1 and 99");
+        }
+        if self.watcher_width + self.plans_width >= 95 {
+            anyhow::bail!(
+                "config error: layout.watcher_width + layout.plans_width must be less than 95 to leave room for the claude pane"
+            );
+        }
+        Ok(())
+    }
+}
+
 impl Default for TuiConfig {
     fn default() -> Self {
         Self {
             tick_interval_secs: 3,
+            dead_session_grace_secs: 300,
+            keep_dead_with_worktree: true,
         }
     }
 }
@@ -65,29 +445,142 @@ pub fn config_path() -> Option<PathBuf> {
 
 impl Config {
     pub fn exists() -> bool {
-        config_path().map_or(false, |p| p.exists())
+        config_path().is_some_and(|p| p.exists())
     }
 
     pub fn default_toml() -> &'static str {
         r#"# CCM Configuration
 
+[mux]
+# Terminal multiplexer backend: "wezterm", "tmux", "zellij" or "kitty"
+backend = "wezterm"
+
 [wezterm]
 # WezTerm binary path
 binary = "wezterm"
 # Command to send to the claude pane (newline is appended automatically)
 claude_command = "claude"
 
-[layout]
-# Tab-watcher pane width (%, 1-99)
-watcher_width = 20
-# Shell pane height (%, 1-99)
-shell_height = 30
-# Plans viewer pane width (%, 1-99)
-plans_width = 30
+[tmux]
+# tmux binary path (used when mux.backend = "tmux")
+binary = "tmux"
+
+[zellij]
+# zellij binary path (used when mux.backend = "zellij", requires zellij 0.44+)
+binary = "zellij"
+
+[kitty]
+# kitty binary path (used when mux.backend = "kitty", requires kitty 0.31+ with
+# allow_remote_control)
+binary = "kitty"
+# Remote control socket, only needed when running ccm outside kitty
+# socket = "unix:/tmp/kitty"
+
+[worktree]
+# Worktree provider: "gj" (requires the gj CLI), "git" (built-in git worktree)
+# or "jj" (jj workspaces)
+provider = "gj"
+
+[git]
+# Session branches are named <branch_prefix><base>-<suffix> (used when
+# worktree.provider = "git")
+branch_prefix = "ccm/"
+# Worktree location, relative to the main worktree. {repo}, {base} and
+# {suffix} are substituted
+worktree_path = "../{repo}-{suffix}"
+
+[jj]
+# jj binary path (used when worktree.provider = "jj")
+binary = "jj"
+# Bookmark that `close --merge` moves to the session's commits
+trunk = "main"
+# Session bookmarks are named <bookmark_prefix><suffix>
+bookmark_prefix = "ccm/"
+# Workspace location, relative to the repository root ({repo}, {suffix})
+workspace_path = "../{repo}-{suffix}"
+# "rebase" keeps the session's commits, "squash" folds them into one
+merge = "rebase"
+
+[merge]
+# Shell commands run in the session worktree before `close --merge` and the
+# TUI merge action. The first failing command aborts the merge and keeps the
+# session. Bypass with `ccm close --merge --skip-checks`
+checks = []
+# checks = ["cargo test", "cargo clippy -- -D warnings"]
+
+[naming]
+# Branch suffix of new sessions. This is not the full session name: the
+# worktree provider adds its own prefix, so with gj the session is
+# gj/<base>-<suffix> (e.g. "{user}/{ticket}-{slug}" -> gj/main-alice/PROJ-1-x).
+# Placeholders:
+#   {slug}   - the name given to `ccm new`, or the plan's first line for
+#              `ccm plan` (random when there is none)
+#   {ticket} - `ccm new --ticket`, or the id found in the plan by
+#              ticket_pattern (empty if none)
+#   {user}   - $USER
+#   {date}   - creation date, YYYYMMDD
+#   {rand}   - random 6-character suffix
+# A name already in use gets a counter appended (-2, -3, ...)
+template = "{slug}"
+# template = "{user}/{ticket}-{slug}"
+# Regex for {ticket}; its first capture group is used if it has one
+ticket_pattern = "[A-Z][A-Z0-9]+-\\d+"
+# Slug of non-ASCII plan titles: "ascii" drops other characters,
+# "transliterate" romanizes them and "unicode" keeps letters of any script
+slug = "ascii"
+
+# Panes split off the claude pane, in order, when a session is created or its
+# layout is reset. Keys of each [[layout.panes]] entry:
+#   name     - unique pane name
+#   split    - "left", "right" or "bottom"
+#   from     - pane to split: "claude" (default) or an earlier pane's name
+#   size     - size of the new pane (%, 1-99)
+#   command  - program to run; {ccm}, {session} and {cwd} are substituted.
+#              Omit to open a shell
+#   required - if false, a failed split only prints a warning (default true)
+
+[[layout.panes]]
+name = "watcher"
+split = "left"
+size = 20
+command = ["{ccm}", "tab-watcher", "--session", "{session}"]
+
+[[layout.panes]]
+name = "shell"
+split = "bottom"
+size = 30
+
+[[layout.panes]]
+name = "plans"
+split = "right"
+size = 30
+command = ["{ccm}", "plan-viewer", "--cwd", "{cwd}"]
+required = false
+
+# Extra panes opened in every session after the layout panes (dev server, test
+# watcher, log tail, ...). Placement keys are the same as for [[layout.panes]];
+# `from` may also name an earlier aux pane.
+#   command            - shell command line ({ccm}, {session}, {cwd} substituted,
+#                        already quoted)
+#   restart            - "never" (default), "on-failure" or "always"
+#   restart_delay_secs - seconds to wait before restarting (default 2)
+#
+# [[aux_panes]]
+# name = "tests"
+# split = "right"
+# from = "shell"
+# size = 50
+# command = "cargo watch -x test"
+# restart = "on-failure"
 
 [tui]
 # Reconciliation interval in seconds (>= 1)
 tick_interval_secs = 3
+# Seconds a session may have no live panes before it is removed from the list
+dead_session_grace_secs = 300
+# Keep dead sessions whose worktree still exists (for `ccm restore`) past the
+# grace period, until `ccm prune`
+keep_dead_with_worktree = true
 "#
     }
 
@@ -138,22 +631,85 @@ tick_interval_secs = 3
         if self.wezterm.binary.is_empty() {
             anyhow::bail!("config error: wezterm.binary must not be empty");
         }
-        if self.tui.tick_interval_secs == 0 {
-            anyhow::bail!("config error: tui.tick_interval_secs must be >= 1");
+        if self.mux.backend == MuxBackend::Tmux && self.tmux.binary.is_empty() {
+            anyhow::bail!("config error: tmux.binary must not be empty");
         }
-        if self.layout.watcher_width == 0 || self.layout.watcher_width >= 100 {
-            anyhow::bail!("config error: layout.watcher_width must be between 1 and 99");
+        if self.mux.backend == MuxBackend::Zellij && self.zellij.binary.is_empty() {
+            anyhow::bail!("config error: zellij.binary must not be empty");
         }
-        if self.layout.shell_height == 0 || self.layout.shell_height >= 100 {
-            anyhow::bail!("config error: layout.shell_height must be between 1 and 99");
+        if self.mux.backend == MuxBackend::Kitty && self.kitty.binary.is_empty() {
+            anyhow::bail!("config error: kitty.binary must not be empty");
         }
-        if self.layout.plans_width == 0 || self.layout.plans_width >= 100 {
-            anyhow::bail!("config error: layout.plans_width must be between 1 and 99");
+        if self.worktree.provider == WorktreeBackend::Git {
+            if self.git.worktree_path.trim().is_empty() {
+                anyhow::bail!("config error: git.worktree_path must not be empty");
+            }
+            if !self.git.worktree_path.contains("{suffix}") {
+                anyhow::bail!("config error: git.worktree_path must contain {{suffix}}");
+            }
         }
-        if self.layout.watcher_width + self.layout.plans_width >= 95 {
-            anyhow::bail!(
-                "config error: layout.watcher_width + layout.plans_width must be less than 95 to leave room for the claude pane"
-            );
+        if self.worktree.provider == WorktreeBackend::Jj {
+            if self.jj.binary.is_empty() {
+                anyhow::bail!("config error: jj.binary must not be empty");
+            }
+            if self.jj.trunk.trim().is_empty() {
+                anyhow::bail!("config error: jj.trunk must not be empty");
+            }
+            if !self.jj.workspace_path.contains("{suffix}") {
+                anyhow::bail!("config error: jj.workspace_path must contain {{suffix}}");
+            }
+        }
+        if self.merge.checks.iter().any(|c| c.trim().is_empty()) {
+            anyhow::bail!("config error: merge.checks entries must not be empty");
+        }
+        if self.naming.template.trim().is_empty() {
+            anyhow::bail!("config error: naming.template must not be empty");
+        }
+        naming::render(&self.naming.template, &naming::NameVars::default())
+            .map_err(|e| anyhow::anyhow!("config error: {e}"))?;
+        naming::find_ticket(&self.naming.ticket_pattern, "")
+            .map_err(|e| anyhow::anyhow!("config error: {e}"))?;
+        if self.tui.tick_interval_secs == 0 {
+            anyhow::bail!("config error: tui.tick_interval_secs must be >= 1");
+        }
+        self.layout.validate()?;
+        self.validate_aux_panes()
+    }
+
+    fn validate_aux_panes(&self) -> anyhow::Result<()> {
+        let mut names: Vec<String> = std::iter::once(CLAUDE_PANE.to_string())
+            .chain(self.layout.effective_panes().into_iter().map(|p| p.name))
+            .collect();
+        for aux in &self.aux_panes {
+            if aux.name.is_empty() {
+                anyhow::bail!("config error: aux_panes entries must have a name");
+            }
+            if names.contains(&aux.name) {
+                anyhow::bail!("config error: duplicate pane name '{}'", aux.name);
+            }
+            if aux.size == 0 || aux.size >= 100 {
+                anyhow::bail!(
+                    "config error: aux pane '{}' size must be between 1 and 99",
+                    aux.name
+                );
+            }
+            if aux.command.trim().is_empty() {
+                anyhow::bail!("config error: aux pane '{}' command must not be empty", aux.name);
+            }
+            if !names.contains(&aux.from) {
+                anyhow::bail!(
+                    "config error: aux pane '{}' splits from unknown or later pane '{}'",
+                    aux.name,
+                    aux.from
+                );
+            }
+            if aux.restart != RestartPolicy::Never && aux.restart_delay_secs == 0 {
+                anyhow::bail!(
+                    "config error: aux pane '{}' restart_delay_secs must be >= 1",
+                    aux.name
+                );
+            }
+            names.push(aux.name.clone());
         }
         Ok(())
     }
@@ -166,12 +722,28 @@ mod tests {
     #[test]
     fn default_config_has_expected_values() {
         let config = Config::default();
+        assert_eq!(config.mux.backend, MuxBackend::Wezterm);
         assert_eq!(config.wezterm.binary, "wezterm");
         assert_eq!(config.wezterm.claude_command, "claude");
+        assert_eq!(config.tmux.binary, "tmux");
+        assert_eq!(config.zellij.binary, "zellij");
+        assert_eq!(config.kitty.binary, "kitty");
+        assert_eq!(config.kitty.socket, None);
+        assert_eq!(config.worktree.provider, WorktreeBackend::Gj);
+        assert_eq!(config.git.branch_prefix, "ccm/");
+        assert_eq!(config.git.worktree_path, "../{repo}-{suffix}");
+        assert_eq!(config.jj.binary, "jj");
+        assert_eq!(config.jj.trunk, "main");
+        assert_eq!(config.jj.merge, JjMergeStrategy::Rebase);
+        assert!(config.merge.checks.is_empty());
+        assert_eq!(config.naming.template, "{slug}");
+        assert_eq!(config.naming.slug, SlugMode::Ascii);
         assert_eq!(config.layout.watcher_width, 20);
         assert_eq!(config.layout.shell_height, 30);
         assert_eq!(config.layout.plans_width, 30);
         assert_eq!(config.tui.tick_interval_secs, 3);
+        assert_eq!(config.tui.dead_session_grace_secs, 300);
+        assert!(config.tui.keep_dead_with_worktree);
     }
 
     #[test]
@@ -198,6 +770,150 @@ tick_interval_secs = 5
         assert_eq!(config.tui.tick_interval_secs, 5);
     }
 
+    #[test]
+    fn parse_tmux_backend() {
+        let toml_str = r#"
+[mux]
+backend = "tmux"
+
+[tmux]
+binary = "/opt/bin/tmux"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.mux.backend, MuxBackend::Tmux);
+        assert_eq!(config.tmux.binary, "/opt/bin/tmux");
+        assert_eq!(config.wezterm.binary, "wezterm");
+    }
+
+    #[test]
+    fn parse_zellij_backend() {
+        let config: Config = toml::from_str("[mux]\nbackend = \"zellij\"\n").unwrap();
+        assert_eq!(config.mux.backend, MuxBackend::Zellij);
+        assert_eq!(config.zellij.binary, "zellij");
+    }
+
+    #[test]
+    fn parse_kitty_backend() {
+        let toml_str = r#"
+[mux]
+backend = "kitty"
+
+[kitty]
+socket = "unix:/tmp/kitty"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.mux.backend, MuxBackend::Kitty);
+        assert_eq!(config.kitty.binary, "kitty");
+        assert_eq!(config.kitty.socket.as_deref(), Some("unix:/tmp/kitty"));
+    }
+
+    #[test]
+    fn parse_git_provider() {
+        let toml_str = r#"
+[worktree]
+provider = "git"
+
+[git]
+worktree_path = "/work/trees/{repo}/{suffix}"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.worktree.provider, WorktreeBackend::Git);
+        assert_eq!(config.git.branch_prefix, "ccm/");
+        assert_eq!(config.git.worktree_path, "/work/trees/{repo}/{suffix}");
+        assert!(toml::from_str::<Config>("[worktree]\nprovider = \"svn\"\n").is_err());
+    }
+
+    #[test]
+    fn validate_git_worktree_path_needs_suffix() {
+        let mut config = Config::default();
+        config.git.worktree_path = "../{repo}-wt".to_string();
+        assert!(config.validate().is_ok());
+        config.worktree.provider = WorktreeBackend::Git;
+        assert!(config.validate().is_err());
+        config.git.worktree_path = String::new();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn parse_jj_provider() {
+        let toml_str = r#"
+[worktree]
+provider = "jj"
+
+[jj]
+trunk = "trunk"
+merge = "squash"
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.worktree.provider, WorktreeBackend::Jj);
+        assert_eq!(config.jj.trunk, "trunk");
+        assert_eq!(config.jj.merge, JjMergeStrategy::Squash);
+        assert_eq!(config.jj.bookmark_prefix, "ccm/");
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_bad_jj_config() {
+        let mut config = Config::default();
+        config.worktree.provider = WorktreeBackend::Jj;
+        config.jj.trunk = " ".to_string();
+        assert!(config.validate().is_err());
+        config.jj.trunk = "main".to_string();
+        config.jj.workspace_path = "../ws".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn parse_merge_checks() {
+        let toml_str = r#"
+[merge]
+checks = ["cargo test", "cargo clippy -- -D warnings"]
+"#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.merge.checks, ["cargo test", "cargo clippy -- -D warnings"]);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_empty_merge_check() {
+        let mut config = Config::default();
+        config.merge.checks = vec!["cargo test".to_string(), "  ".to_string()];
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn parse_naming() {
+        let toml_str = r##"
+[naming]
+template = "{user}/{ticket}-{slug}"
+ticket_pattern = "#(\\d+)"
+slug = "unicode"
+"##;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.naming.template, "{user}/{ticket}-{slug}");
+        assert_eq!(config.naming.ticket_pattern, "#(\\d+)");
+        assert_eq!(config.naming.slug, SlugMode::Unicode);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_bad_naming() {
+        let mut config = Config::default();
+        config.naming.template = "{branch}".to_string();
+        assert!(config.validate().is_err());
+        config.naming.template = " ".to_string();
+        assert!(config.validate().is_err());
+        config.naming.template = "{slug}".to_string();
+        config.naming.ticket_pattern = "[".to_string();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn parse_unknown_backend_errors() {
+        let result: Result<Config, _> = toml::from_str("[mux]\nbackend = \"screen\"\n");
+        assert!(result.is_err());
+    }
+
     #[test]
     fn parse_partial_toml_fills_defaults() {
         let toml_str = r#"
@@ -232,6 +948,31 @@ tick_interval_secs = 10
         assert!(config.validate().is_err());
     }
 
+    #[test]
+    fn validate_rejects_empty_tmux_binary() {
+        let mut config = Config::default();
+        config.tmux.binary = String::new();
+        assert!(config.validate().is_ok());
+        config.mux.backend = MuxBackend::Tmux;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_empty_zellij_binary() {
+        let mut config = Config::default();
+        config.mux.backend = MuxBackend::Zellij;
+        config.zellij.binary = String::new();
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_empty_kitty_binary() {
+        let mut config = Config::default();
+        config.mux.backend = MuxBackend::Kitty;
+        config.kitty.binary = String::new();
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn validate_rejects_zero_tick_interval() {
         let mut config = Config::default();
@@ -289,6 +1030,225 @@ tick_interval_secs = 10
         assert!(config.validate().is_err());
     }
 
+    fn layout_from(toml_str: &str) -> Config {
+        toml::from_str(toml_str).unwrap()
+    }
+
+    #[test]
+    fn effective_panes_from_legacy_keys() {
+        let mut config = Config::default();
+        config.layout.watcher_width = 25;
+        let panes = config.layout.effective_panes();
+        let names: Vec<&str> = panes.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["watcher", "shell", "plans"]);
+        assert_eq!(panes[0].size, 25);
+        assert_eq!(panes[1].split, SplitDirection::Bottom);
+        assert_eq!(panes[1].command, None);
+        assert!(!panes[2].required);
+    }
+
+    #[test]
+    fn parse_layout_panes() {
+        let config = layout_from(
+            r#"
+[[layout.panes]]
+name = "logs"
+split = "right"
+size = 40
+command = ["tail", "-f", "{cwd}/log"]
+
+[[layout.panes]]
+name = "tests"
+split = "bottom"
+from = "logs"
+size = 50
+required = false
+"#,
+        );
+        assert!(config.validate().is_ok());
+        let panes = config.layout.effective_panes();
+        assert_eq!(panes.len(), 2);
+        assert_eq!(panes[0].from, "claude");
+        assert!(panes[0].required);
+        assert_eq!(panes[1].from, "logs");
+        assert!(!panes[1].required);
+    }
+
+    #[test]
+    fn parse_layout_unknown_split_errors() {
+        let result: Result<Config, _> =
+            toml::from_str("[[layout.panes]]\nname = \"a\"\nsplit = \"up\"\nsize = 10\n");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_pane_names() {
+        let config = layout_from(
+            r#"
+[[layout.panes]]
+name = "a"
+split = "left"
+size = 10
+
+[[layout.panes]]
+name = "a"
+split = "bottom"
+size = 10
+"#,
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_pane_named_claude() {
+        let config = layout_from("[[layout.panes]]\nname = \"claude\"\nsplit = \"left\"\nsize = 10\n");
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_forward_reference() {
+        let config = layout_from(
+            r#"
+[[layout.panes]]
+name = "a"
+split = "left"
+from = "b"
+size = 10
+
+[[layout.panes]]
+name = "b"
+split = "bottom"
+size = 10
+"#,
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_required_pane_from_optional() {
+        let config = layout_from(
+            r#"
+[[layout.panes]]
+name = "a"
+split = "left"
+size = 10
+required = false
+
+[[layout.panes]]
+name = "b"
+split = "bottom"
+from = "a"
+size = 10
+"#,
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_bad_pane_size_and_command() {
+        let config = layout_from("[[layout.panes]]\nname = \"a\"\nsplit = \"left\"\nsize = 0\n");
+        assert!(config.validate().is_err());
+        let config = layout_from(
+            "[[layout.panes]]\nname = \"a\"\nsplit = \"left\"\nsize = 10\ncommand = []\n",
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_side_panes_too_wide() {
+        let config = layout_from(
+            r#"
+[[layout.panes]]
+name = "a"
+split = "left"
+size = 50
+
+[[layout.panes]]
+name = "b"
+split = "right"
+size = 45
+
+[[layout.panes]]
+name = "c"
+split = "bottom"
+size = 90
+"#,
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn parse_aux_panes() {
+        let config = layout_from(
+            r#"
+[[aux_panes]]
+name = "server"
+split = "right"
+from = "shell"
+size = 40
+command = "npm run dev"
+restart = "always"
+
+[[aux_panes]]
+name = "logs"
+split = "bottom"
+from = "server"
+size = 50
+command = "tail -f log/dev.log"
+"#,
+        );
+        assert!(config.validate().is_ok());
+        assert_eq!(config.aux_panes.len(), 2);
+        assert_eq!(config.aux_panes[0].restart, RestartPolicy::Always);
+        assert_eq!(config.aux_panes[0].restart_delay_secs, 2);
+        assert_eq!(config.aux_panes[1].from, "server");
+        assert_eq!(config.aux_panes[1].restart, RestartPolicy::Never);
+    }
+
+    #[test]
+    fn parse_aux_pane_restart_on_failure() {
+        let config = layout_from(
+            "[[aux_panes]]\nname = \"t\"\nsplit = \"left\"\nsize = 10\ncommand = \"x\"\nrestart = \"on-failure\"\n",
+        );
+        assert_eq!(config.aux_panes[0].restart, RestartPolicy::OnFailure);
+        let result: Result<Config, _> = toml::from_str(
+            "[[aux_panes]]\nname = \"t\"\nsplit = \"left\"\nsize = 10\ncommand = \"x\"\nrestart = \"sometimes\"\n",
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn validate_rejects_aux_pane_name_clash() {
+        let config = layout_from(
+            "[[aux_panes]]\nname = \"shell\"\nsplit = \"left\"\nsize = 10\ncommand = \"x\"\n",
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_aux_pane_unknown_from() {
+        let config = layout_from(
+            "[[aux_panes]]\nname = \"t\"\nsplit = \"left\"\nfrom = \"nope\"\nsize = 10\ncommand = \"x\"\n",
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_aux_pane_empty_command() {
+        let config = layout_from(
+            "[[aux_panes]]\nname = \"t\"\nsplit = \"left\"\nsize = 10\ncommand = \" \"\n",
+        );
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_zero_restart_delay() {
+        let config = layout_from(
+            "[[aux_panes]]\nname = \"t\"\nsplit = \"left\"\nsize = 10\ncommand = \"x\"\nrestart = \"always\"\nrestart_delay_secs = 0\n",
+        );
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn validate_accepts_valid_config() {
         let config = Config::default();
@@ -300,15 +1260,33 @@ tick_interval_secs = 10
         let toml_str = Config::default_toml();
         let parsed: Config = toml::from_str(toml_str).expect("default_toml should be valid TOML");
         let default = Config::default();
+        assert_eq!(parsed.mux.backend, default.mux.backend);
+        assert_eq!(parsed.tmux.binary, default.tmux.binary);
+        assert_eq!(parsed.zellij.binary, default.zellij.binary);
+        assert_eq!(parsed.kitty.binary, default.kitty.binary);
+        assert_eq!(parsed.kitty.socket, default.kitty.socket);
         assert_eq!(parsed.wezterm.binary, default.wezterm.binary);
         assert_eq!(parsed.wezterm.claude_command, default.wezterm.claude_command);
         assert_eq!(parsed.layout.watcher_width, default.layout.watcher_width);
         assert_eq!(parsed.layout.shell_height, default.layout.shell_height);
         assert_eq!(parsed.layout.plans_width, default.layout.plans_width);
+        assert_eq!(
+            parsed.layout.effective_panes(),
+            default.layout.effective_panes()
+        );
+        assert!(parsed.aux_panes.is_empty());
         assert_eq!(
             parsed.tui.tick_interval_secs,
             default.tui.tick_interval_secs
         );
+        assert_eq!(
+            parsed.tui.dead_session_grace_secs,
+            default.tui.dead_session_grace_secs
+        );
+        assert_eq!(
+            parsed.tui.keep_dead_with_worktree,
+            default.tui.keep_dead_with_worktree
+        );
     }
 
     #[test]
@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::Path;
+
+use crate::error::CcmError;
+use crate::git;
+use crate::session::Session;
+
+/// Changes to one file, as shown by `git diff`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    pub path: String,
+    /// Lines before the first hunk (`new file mode`, `Binary files differ`, ...).
+    pub header: Vec<String>,
+    pub hunks: Vec<Hunk>,
+}
+
+/// One `@@ ... @@` hunk: its header line and the `+`/`-`/context lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hunk {
+    pub header: String,
+    pub lines: Vec<String>,
+}
+
+impl FileDiff {
+    /// Lines added and removed in this file.
+    pub fn stat(&self) -> (usize, usize) {
+        let lines = self.hunks.iter().flat_map(|h| &h.lines);
+        lines.fold((0, 0), |(ins, del), line| match line.as_bytes().first() {
+            Some(b'+') => (ins + 1, del),
+            Some(b'-') => (ins, del + 1),
+            _ => (ins, del),
+        })
+    }
+}
+
+/// Raw `git diff` of the session's worktree (uncommitted edits included)
+/// against the merge-base with its base.
+pub fn session_diff_text(session: &Session) -> Result<String, CcmError> {
+    let cwd = session.cwd.as_str();
+    let merge_base = git::run(cwd, &["merge-base", &git::session_base(session)?, "HEAD"])?;
+    git::run(cwd, &["diff", "--no-color", "--no-ext-diff", &merge_base])
+}
+
+/// Parsed diff of the session, followed by its untracked files as additions.
+pub fn session_diff(session: &Session) -> Result<Vec<FileDiff>, CcmError> {
+    let mut files = parse(&session_diff_text(session)?);
+    let untracked = git::run(&session.cwd, &["ls-files", "--others", "--exclude-standard"])?;
+    // ccm's own plans are not part of the session's work
+    for path in untracked
+        .lines()
+        .filter(|p| !p.is_empty() && !p.starts_with(".ccm/"))
+    {
+        files.push(untracked_file(&session.cwd, path));
+    }
+    Ok(files)
+}
+
+/// An untracked file rendered as a new-file diff.
+fn untracked_file(cwd: &str, path: &str) -> FileDiff {
+    let mut file = FileDiff {
+        path: path.to_string(),
+        header: vec!["untracked file".to_string()],
+        hunks: Vec::new(),
+    };
+    match fs::read(Path::new(cwd).join(path)).map(String::from_utf8) {
+        Ok(Ok(content)) if !content.is_empty() => {
+            let lines: Vec<String> = content.lines().map(|l| format!("+{l}")).collect();
+            file.hunks.push(Hunk {
+                header: format!("@@ -0,0 +1,{} @@", lines.len()),
+                lines,
+            });
+        }
+        Ok(Ok(_)) => {}
+        Ok(Err(_)) => file.header.push("Binary file".to_string()),
+        Err(e) => file.header.push(format!("cannot read: {e}")),
+    }
+    file
+}
+
+/// Split unified `git diff` output into files and hunks.
+pub fn parse(text: &str) -> Vec<FileDiff> {
+    let mut files: Vec<FileDiff> = Vec::new();
+    for line in text.lines() {
+        if let Some(rest) = line.strip_prefix("diff --git ") {
+            files.push(FileDiff {
+                path: path_from_diff_line(rest),
+                header: Vec::new(),
+                hunks: Vec::new(),
+            });
+            continue;
+        }
+        let Some(file) = files.last_mut() else {
+            continue;
+        };
+        if line.starts_with("@@") {
+            file.hunks.push(Hunk {
+                header: line.to_string(),
+                lines: Vec::new(),
+            });
+        } else if let Some(hunk) = file.hunks.last_mut() {
+            hunk.lines.push(line.to_string());
+        } else if let Some(path) = line.strip_prefix("+++ b/") {
+            file.path = path.to_string();
+        } else if !line.starts_with("--- ") && !line.starts_with("+++ ") && !line.starts_with("index ") {
+            file.header.push(line.to_string());
+        }
+    }
+    files
+}
+
+/// Path from `a/<path> b/<path>`; the `+++` line refines it when present.
+fn path_from_diff_line(rest: &str) -> String {
+    match rest.rsplit_once(" b/") {
+        Some((_, path)) => path.to_string(),
+        None => rest.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIFF: &str = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1111111..2222222 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,3 @@ fn main() {
+ fn main() {
+-    println!(\"hi\");
++    println!(\"hello\");
+ }
+@@ -10,2 +10,3 @@
+ a
++b
+ c
+diff --git a/logo.png b/logo.png
+new file mode 100644
+index 0000000..3333333
+Binary files /dev/null and b/logo.png differ";
+
+    #[test]
+    fn parse_splits_files_and_hunks() {
+        let files = parse(DIFF);
+        assert_eq!(files.len(), 2);
+
+        let main = &files[0];
+        assert_eq!(main.path, "src/main.rs");
+        assert!(main.header.is_empty());
+        assert_eq!(main.hunks.len(), 2);
+        assert_eq!(main.hunks[0].header, "@@ -1,3 +1,3 @@ fn main() {");
+        assert_eq!(main.hunks[0].lines.len(), 4);
+        assert_eq!(main.stat(), (2, 1));
+
+        let logo = &files[1];
+        assert_eq!(logo.path, "logo.png");
+        assert_eq!(
+            logo.header,
+            ["new file mode 100644", "Binary files /dev/null and b/logo.png differ"]
+        );
+        assert!(logo.hunks.is_empty());
+    }
+
+    #[test]
+    fn parse_empty_diff() {
+        assert!(parse("").is_empty());
+    }
+
+    #[test]
+    fn untracked_file_as_addition() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("new.txt"), "one\ntwo\n").unwrap();
+        let file = untracked_file(&dir.path().to_string_lossy(), "new.txt");
+        assert_eq!(file.hunks[0].header, "@@ -0,0 +1,2 @@");
+        assert_eq!(file.hunks[0].lines, ["+one", "+two"]);
+        assert_eq!(file.stat(), (2, 0));
+    }
+
+    #[test]
+    fn session_diff_includes_commits_edits_and_untracked() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git::test_repo(dir.path());
+        let path = Path::new(&repo);
+        git::run(&repo, &["checkout", "-q", "-b", "feat"]).unwrap();
+        fs::write(path.join("a.txt"), "a\n").unwrap();
+        git::run(&repo, &["add", "a.txt"]).unwrap();
+        git::run(&repo, &["commit", "-q", "-m", "a"]).unwrap();
+        fs::write(path.join("a.txt"), "a\nmore\n").unwrap();
+        fs::write(path.join("b.txt"), "b\n").unwrap();
+
+        let session: Session = serde_json::from_value(serde_json::json!({
+            "name": "feat",
+            "tab_id": 1,
+            "claude_pane_id": 1,
+            "cwd": repo,
+            "base_ref": "main",
+            "created_at": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let files = session_diff(&session).unwrap();
+        let paths: Vec<&str> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.txt", "b.txt"]);
+        assert_eq!(files[0].stat(), (2, 0));
+        assert_eq!(files[1].header, ["untracked file"]);
+    }
+}
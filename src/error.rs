@@ -11,12 +11,45 @@ pub enum CcmError {
     #[error("WezTerm CLI failed: {0}")]
     WezTerm(String),
 
+    #[error("tmux CLI failed: {0}")]
+    Tmux(String),
+
+    #[error("zellij CLI failed: {0}")]
+    Zellij(String),
+
+    #[error("kitty remote control failed: {0}")]
+    Kitty(String),
+
     #[error("gj CLI failed: {0}")]
     Gj(String),
 
+    #[error("git failed: {0}")]
+    Git(String),
+
+    #[error("jj failed: {0}")]
+    Jj(String),
+
+    #[error("merge check failed: {0}")]
+    Check(String),
+
+    #[error("bootstrap failed: {0}")]
+    Bootstrap(String),
+
+    #[error("session naming: {0}")]
+    Naming(String),
+
     #[error("State file error: {0}")]
     State(String),
 
+    #[error("event journal: {0}")]
+    Journal(String),
+
+    #[error("archive: {0}")]
+    Archive(String),
+
+    #[error("state.json has schema version {found}, but this ccm only supports up to {supported}; upgrade ccm")]
+    SchemaTooNew { found: u32, supported: u32 },
+
     #[error(transparent)]
     Io(#[from] std::io::Error),
 
@@ -46,18 +79,99 @@ mod tests {
         assert_eq!(e.to_string(), "WezTerm CLI failed: timeout");
     }
 
+    #[test]
+    fn display_tmux() {
+        let e = CcmError::Tmux("no server".to_string());
+        assert_eq!(e.to_string(), "tmux CLI failed: no server");
+    }
+
+    #[test]
+    fn display_zellij() {
+        let e = CcmError::Zellij("no session".to_string());
+        assert_eq!(e.to_string(), "zellij CLI failed: no session");
+    }
+
+    #[test]
+    fn display_kitty() {
+        let e = CcmError::Kitty("remote control disabled".to_string());
+        assert_eq!(
+            e.to_string(),
+            "kitty remote control failed: remote control disabled"
+        );
+    }
+
     #[test]
     fn display_gj() {
         let e = CcmError::Gj("err".to_string());
         assert_eq!(e.to_string(), "gj CLI failed: err");
     }
 
+    #[test]
+    fn display_git() {
+        let e = CcmError::Git("not a repository".to_string());
+        assert_eq!(e.to_string(), "git failed: not a repository");
+    }
+
+    #[test]
+    fn display_jj() {
+        let e = CcmError::Jj("no jj repo".to_string());
+        assert_eq!(e.to_string(), "jj failed: no jj repo");
+    }
+
+    #[test]
+    fn display_check() {
+        let e = CcmError::Check("`cargo test` exited with status 101".to_string());
+        assert_eq!(
+            e.to_string(),
+            "merge check failed: `cargo test` exited with status 101"
+        );
+    }
+
+    #[test]
+    fn display_bootstrap() {
+        let e = CcmError::Bootstrap("`npm ci` exited with status 1".to_string());
+        assert_eq!(
+            e.to_string(),
+            "bootstrap failed: `npm ci` exited with status 1"
+        );
+    }
+
+    #[test]
+    fn display_naming() {
+        let e = CcmError::Naming("unknown placeholder {x} in '{x}'".to_string());
+        assert_eq!(e.to_string(), "session naming: unknown placeholder {x} in '{x}'");
+    }
+
     #[test]
     fn display_state() {
         let e = CcmError::State("corrupt".to_string());
         assert_eq!(e.to_string(), "State file error: corrupt");
     }
 
+    #[test]
+    fn display_journal() {
+        let e = CcmError::Journal("invalid time 'x'".to_string());
+        assert_eq!(e.to_string(), "event journal: invalid time 'x'");
+    }
+
+    #[test]
+    fn display_archive() {
+        let e = CcmError::Archive("no archived session 'x'".to_string());
+        assert_eq!(e.to_string(), "archive: no archived session 'x'");
+    }
+
+    #[test]
+    fn display_schema_too_new() {
+        let e = CcmError::SchemaTooNew {
+            found: 3,
+            supported: 2,
+        };
+        assert_eq!(
+            e.to_string(),
+            "state.json has schema version 3, but this ccm only supports up to 2; upgrade ccm"
+        );
+    }
+
     #[test]
     fn from_io_error() {
         let io_err = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
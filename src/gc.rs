@@ -0,0 +1,196 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+
+use crate::error::CcmError;
+use crate::git;
+use crate::session::Session;
+
+/// A worktree or branch created by the worktree provider that no session in
+/// state.json refers to (e.g. `ccm new` failed midway, or the session was
+/// purged while its worktree was kept).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Orphan {
+    Worktree {
+        path: String,
+        branch: String,
+        /// When the worktree was created, if its directory still exists.
+        created: Option<DateTime<Utc>>,
+        /// Disk usage in bytes, if its directory still exists.
+        size: Option<u64>,
+    },
+    /// A branch without a worktree.
+    Branch {
+        name: String,
+        /// Time of the last commit.
+        last_commit: Option<DateTime<Utc>>,
+    },
+}
+
+impl Orphan {
+    /// When the orphan was last touched, for the age column of `ccm gc`.
+    pub fn since(&self) -> Option<DateTime<Utc>> {
+        match self {
+            Orphan::Worktree { created, .. } => *created,
+            Orphan::Branch { last_commit, .. } => *last_commit,
+        }
+    }
+}
+
+/// Worktrees and branches of the repository at `repo` whose branch starts
+/// with `prefix` and that do not belong to any of `sessions`.
+pub fn find_orphans(
+    repo: &str,
+    prefix: &str,
+    sessions: &[Session],
+) -> Result<Vec<Orphan>, CcmError> {
+    let session_dirs: HashSet<PathBuf> = sessions.iter().map(|s| canonical(&s.cwd)).collect();
+    let worktrees = git::worktrees(repo)?;
+    let mut orphans = Vec::new();
+
+    // The first entry is the main worktree, which is never the provider's
+    for worktree in worktrees.iter().skip(1) {
+        let Some(ref branch) = worktree.branch else {
+            continue;
+        };
+        if !branch.starts_with(prefix) || session_dirs.contains(&canonical(&worktree.path)) {
+            continue;
+        }
+        let path = Path::new(&worktree.path);
+        orphans.push(Orphan::Worktree {
+            path: worktree.path.clone(),
+            branch: branch.clone(),
+            created: fs::symlink_metadata(path.join(".git"))
+                .and_then(|m| m.modified())
+                .ok()
+                .map(DateTime::<Utc>::from),
+            size: path.is_dir().then(|| dir_size(path)),
+        });
+    }
+
+    let checked_out: HashSet<&str> = worktrees
+        .iter()
+        .filter_map(|w| w.branch.as_deref())
+        .collect();
+    for name in git::branches(repo)? {
+        if !name.starts_with(prefix) || checked_out.contains(name.as_str()) {
+            continue;
+        }
+        let reference = format!("refs/heads/{name}");
+        let last_commit = git::run(repo, &["log", "-1", "--format=%ct", &reference])
+            .ok()
+            .and_then(|ts| ts.parse::<i64>().ok())
+            .and_then(|ts| DateTime::from_timestamp(ts, 0));
+        orphans.push(Orphan::Branch { name, last_commit });
+    }
+    Ok(orphans)
+}
+
+fn canonical(path: &str) -> PathBuf {
+    Path::new(path)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(path))
+}
+
+/// Total size of the files under `path`, not following symlinks.
+fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(m) if m.is_dir() => dir_size(&entry.path()),
+            Ok(m) if m.is_file() => m.len(),
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Human-readable byte count, e.g. `"12.3 MiB"`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{value:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_size_units() {
+        assert_eq!(format_size(0), "0 B");
+        assert_eq!(format_size(1023), "1023 B");
+        assert_eq!(format_size(1536), "1.5 KiB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MiB");
+        assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+    }
+
+    #[test]
+    fn dir_size_sums_nested_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a"), [0u8; 100]).unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        fs::write(dir.path().join("sub/b"), [0u8; 23]).unwrap();
+        assert_eq!(dir_size(dir.path()), 123);
+        assert_eq!(dir_size(&dir.path().join("missing")), 0);
+    }
+
+    #[test]
+    fn find_orphans_ignores_sessions_and_foreign_branches() {
+        let dir = tempfile::tempdir().unwrap();
+        let r = git::test_repo(dir.path());
+        git::run(&r, &["branch", "gj/main-stale"]).unwrap();
+        git::run(&r, &["branch", "feature"]).unwrap();
+        let wt = |name: &str| dir.path().join(name).to_string_lossy().into_owned();
+        for (branch, path) in [
+            ("gj/main-live", wt("live")),
+            ("gj/main-orphan", wt("orphan")),
+            ("by-hand", wt("by-hand")),
+        ] {
+            git::run(&r, &["worktree", "add", "-q", "-b", branch, &path]).unwrap();
+        }
+        fs::write(dir.path().join("orphan/big"), [0u8; 2048]).unwrap();
+
+        let session: Session = serde_json::from_value(serde_json::json!({
+            "name": "live",
+            "tab_id": 1,
+            "claude_pane_id": 1,
+            "cwd": wt("live"),
+            "created_at": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        let orphans = find_orphans(&r, "gj/", &[session]).unwrap();
+        assert_eq!(orphans.len(), 2, "{orphans:?}");
+
+        let Orphan::Worktree {
+            ref path,
+            ref branch,
+            created,
+            size,
+        } = orphans[0]
+        else {
+            panic!("expected a worktree: {orphans:?}");
+        };
+        assert_eq!(path, &wt("orphan"));
+        assert_eq!(branch, "gj/main-orphan");
+        assert!(created.is_some());
+        assert!(size.unwrap() >= 2048);
+
+        assert!(matches!(
+            orphans[1],
+            Orphan::Branch { ref name, last_commit: Some(_) } if name == "gj/main-stale"
+        ));
+    }
+}
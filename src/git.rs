@@ -0,0 +1,226 @@
+use std::process::Command;
+
+use crate::error::CcmError;
+use crate::session::Session;
+
+/// Run `git -C <dir> <args>` and return its trimmed stdout.
+pub fn run(dir: &str, args: &[&str]) -> Result<String, CcmError> {
+    let subcommand = args.first().copied().unwrap_or_default();
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| CcmError::Git(format!("failed to run git {subcommand}: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CcmError::Git(format!(
+            "git {subcommand} failed: {}",
+            stderr.trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Like `run`, but a non-zero exit status yields `None` instead of an error
+/// (e.g. `git config` for a key that is not set).
+pub fn run_optional(dir: &str, args: &[&str]) -> Result<Option<String>, CcmError> {
+    let subcommand = args.first().copied().unwrap_or_default();
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .map_err(|e| CcmError::Git(format!("failed to run git {subcommand}: {e}")))?;
+
+    if !output.status.success() {
+        return Ok(None);
+    }
+    Ok(Some(
+        String::from_utf8_lossy(&output.stdout).trim().to_string(),
+    ))
+}
+
+/// Top-level directory of the working tree containing `dir`.
+pub fn toplevel(dir: &str) -> Result<String, CcmError> {
+    run(dir, &["rev-parse", "--show-toplevel"])
+}
+
+/// Name of the branch checked out in `dir`, or an error if HEAD is detached.
+pub fn current_branch(dir: &str) -> Result<String, CcmError> {
+    run_optional(dir, &["symbolic-ref", "--quiet", "--short", "HEAD"])?
+        .ok_or_else(|| CcmError::Git(format!("HEAD is detached in {dir}; check out a branch")))
+}
+
+/// Path of the main worktree of the repository containing `dir`.
+pub fn main_worktree(dir: &str) -> Result<String, CcmError> {
+    let list = run(dir, &["worktree", "list", "--porcelain"])?;
+    parse_main_worktree(&list)
+        .ok_or_else(|| CcmError::Git("could not determine the main worktree".to_string()))
+}
+
+/// A worktree from `git worktree list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Worktree {
+    pub path: String,
+    /// Checked-out branch, or None if HEAD is detached.
+    pub branch: Option<String>,
+}
+
+/// All worktrees of the repository containing `dir`, main worktree first.
+pub fn worktrees(dir: &str) -> Result<Vec<Worktree>, CcmError> {
+    let list = run(dir, &["worktree", "list", "--porcelain"])?;
+    Ok(parse_worktrees(&list))
+}
+
+/// Local branch names.
+pub fn branches(dir: &str) -> Result<Vec<String>, CcmError> {
+    let out = run(dir, &["for-each-ref", "--format=%(refname:short)", "refs/heads"])?;
+    Ok(out.lines().filter(|l| !l.is_empty()).map(str::to_string).collect())
+}
+
+/// Path of the worktree that has `branch` checked out, if any.
+pub fn worktree_for_branch(dir: &str, branch: &str) -> Result<Option<String>, CcmError> {
+    let list = run(dir, &["worktree", "list", "--porcelain"])?;
+    Ok(parse_worktree_for_branch(&list, branch))
+}
+
+/// Whether `branch` exists as a local branch.
+pub fn branch_exists(dir: &str, branch: &str) -> Result<bool, CcmError> {
+    let reference = format!("refs/heads/{branch}");
+    Ok(run_optional(dir, &["rev-parse", "--verify", "--quiet", &reference])?.is_some())
+}
+
+/// Ref `session` compares its changes against: its recorded base, else
+/// `default_base`.
+pub fn session_base(session: &Session) -> Result<String, CcmError> {
+    match session.base_ref {
+        Some(ref base) => Ok(base.clone()),
+        None => default_base(&session.cwd),
+    }
+}
+
+/// Ref a session in `dir` compares against when it did not record a base:
+/// the base the git provider recorded for the branch (`branch.<b>.ccmBase`),
+/// else the branch checked out in the main worktree.
+fn default_base(dir: &str) -> Result<String, CcmError> {
+    if let Ok(branch) = current_branch(dir) {
+        let key = format!("branch.{branch}.ccmBase");
+        if let Some(base) = run_optional(dir, &["config", "--get", &key])? {
+            return Ok(base);
+        }
+    }
+    current_branch(&main_worktree(dir)?)
+}
+
+fn parse_worktree_for_branch(porcelain: &str, branch: &str) -> Option<String> {
+    let wanted = format!("branch refs/heads/{branch}");
+    porcelain.split("\n\n").find_map(|entry| {
+        let mut lines = entry.lines();
+        let path = lines.next()?.strip_prefix("worktree ")?;
+        lines.any(|l| l == wanted).then(|| path.to_string())
+    })
+}
+
+fn parse_worktrees(porcelain: &str) -> Vec<Worktree> {
+    porcelain
+        .split("\n\n")
+        .filter_map(|entry| {
+            let mut lines = entry.lines();
+            let path = lines.next()?.strip_prefix("worktree ")?;
+            let branch = lines
+                .find_map(|l| l.strip_prefix("branch refs/heads/"))
+                .map(str::to_string);
+            Some(Worktree {
+                path: path.to_string(),
+                branch,
+            })
+        })
+        .collect()
+}
+
+fn parse_main_worktree(porcelain: &str) -> Option<String> {
+    porcelain
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("worktree "))
+        .map(str::to_string)
+}
+
+/// A repository at `<dir>/repo` with one empty commit on `main`, for tests.
+#[cfg(test)]
+pub fn test_repo(dir: &std::path::Path) -> String {
+    let repo = dir.join("repo");
+    std::fs::create_dir_all(&repo).unwrap();
+    let repo = repo.to_string_lossy().into_owned();
+    for args in [
+        &["init", "-q", "-b", "main"][..],
+        &["config", "user.name", "ccm"],
+        &["config", "user.email", "ccm@example.com"],
+        &["commit", "-q", "--allow-empty", "-m", "init"],
+    ] {
+        run(&repo, args).unwrap();
+    }
+    repo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_main_worktree_first_entry() {
+        let porcelain = "worktree /src/repo\nHEAD abc\nbranch refs/heads/main\n\nworktree /src/repo-x\nHEAD def\nbranch refs/heads/ccm/main-x\n";
+        assert_eq!(parse_main_worktree(porcelain).as_deref(), Some("/src/repo"));
+        assert_eq!(parse_main_worktree(""), None);
+    }
+
+    #[test]
+    fn parse_worktree_for_branch_matches_exactly() {
+        let porcelain = "worktree /src/repo\nHEAD abc\nbranch refs/heads/main\n\nworktree /src/repo-x\nHEAD def\nbranch refs/heads/feat/x\n\nworktree /src/repo-d\nHEAD 123\ndetached\n";
+        assert_eq!(
+            parse_worktree_for_branch(porcelain, "feat/x").as_deref(),
+            Some("/src/repo-x")
+        );
+        assert_eq!(
+            parse_worktree_for_branch(porcelain, "main").as_deref(),
+            Some("/src/repo")
+        );
+        assert_eq!(parse_worktree_for_branch(porcelain, "feat"), None);
+    }
+
+    #[test]
+    fn parse_worktrees_with_branches() {
+        let porcelain = "worktree /src/repo\nHEAD abc\nbranch refs/heads/main\n\nworktree /src/repo-x\nHEAD def\nbranch refs/heads/gj/main-x\n\nworktree /src/repo-d\nHEAD 123\ndetached\n";
+        assert_eq!(
+            parse_worktrees(porcelain),
+            [
+                Worktree {
+                    path: "/src/repo".to_string(),
+                    branch: Some("main".to_string()),
+                },
+                Worktree {
+                    path: "/src/repo-x".to_string(),
+                    branch: Some("gj/main-x".to_string()),
+                },
+                Worktree {
+                    path: "/src/repo-d".to_string(),
+                    branch: None,
+                },
+            ]
+        );
+        assert!(parse_worktrees("").is_empty());
+    }
+
+    #[test]
+    fn run_reports_failure() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = run(&dir.path().to_string_lossy(), &["rev-parse", "HEAD"]).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("git failed: git rev-parse failed"));
+        let missing = run_optional(&dir.path().to_string_lossy(), &["rev-parse", "HEAD"]).unwrap();
+        assert_eq!(missing, None);
+    }
+}
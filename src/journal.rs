@@ -0,0 +1,365 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::CcmError;
+use crate::worktree;
+
+/// Append-only log of what happened to sessions, one JSON object per line,
+/// kept next to state.json. Unlike state.json it is never rewritten.
+pub fn path_for(state_path: &Path) -> PathBuf {
+    state_path.with_file_name("events.ndjson")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub at: DateTime<Utc>,
+    pub session: String,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    Created {
+        cwd: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        base_ref: Option<String>,
+    },
+    /// Claude went idle or started working, or its task title changed; see
+    /// `normalize_status`.
+    Status {
+        status: String,
+    },
+    /// Reconcile found none of the session's panes alive.
+    Dead,
+    /// Panes of a session marked dead came back.
+    Alive,
+    /// Reconcile removed the session after the grace period.
+    Purged,
+    /// `ccm reopen` recreated the session from the archive.
+    Reopened {
+        cwd: String,
+    },
+    Closed {
+        merged: bool,
+    },
+}
+
+impl Event {
+    pub fn new(session: &str, kind: EventKind) -> Self {
+        Self {
+            at: Utc::now(),
+            session: session.to_string(),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventKind::Created { cwd, base_ref } => {
+                write!(f, "created in {cwd}")?;
+                match base_ref {
+                    Some(base) => write!(f, " from {base}"),
+                    None => Ok(()),
+                }
+            }
+            EventKind::Status { status } => write!(f, "status: {status}"),
+            EventKind::Dead => write!(f, "panes gone"),
+            EventKind::Alive => write!(f, "panes back"),
+            EventKind::Purged => write!(f, "purged"),
+            EventKind::Reopened { cwd } => write!(f, "reopened in {cwd}"),
+            EventKind::Closed { merged: true } => write!(f, "merged and closed"),
+            EventKind::Closed { merged: false } => write!(f, "closed"),
+        }
+    }
+}
+
+/// Claude's pane title as journaled: its leading glyph mapped to `working`
+/// (the braille spinner) or `idle` (`✳`), so that spinner frames, which
+/// change the title many times a minute, do not each become an event.
+pub fn normalize_status(title: &str) -> String {
+    let title = title.trim();
+    let mut chars = title.chars();
+    let (activity, rest) = match chars.next() {
+        Some('\u{2800}'..='\u{28ff}') => ("working", chars.as_str()),
+        Some('✳') => ("idle", chars.as_str()),
+        _ => ("idle", title),
+    };
+    match rest.trim() {
+        "" => activity.to_string(),
+        rest => format!("{activity}: {rest}"),
+    }
+}
+
+/// Append `events` to the journal at `path` in a single write, so that
+/// concurrent writers do not interleave within a line.
+pub fn append(path: &Path, events: &[Event]) -> Result<(), CcmError> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut buf = String::new();
+    for event in events {
+        buf.push_str(&serde_json::to_string(event)?);
+        buf.push('\n');
+    }
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(buf.as_bytes())?;
+    Ok(())
+}
+
+/// Which events `ccm history` shows.
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// Session name, or the name it was made from (`feat` for `gj/main-feat`).
+    pub session: Option<String>,
+    /// Branch prefix of the worktree provider, for the latter form of `session`.
+    pub branch_prefix: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl Filter {
+    /// Whether `event` passes; `base` is the base its session was created from.
+    pub fn matches(&self, event: &Event, base: Option<&str>) -> bool {
+        self.session.as_deref().is_none_or(|s| {
+            worktree::is_named(&event.session, s, self.branch_prefix.as_deref(), base)
+        }) && self.since.is_none_or(|t| event.at >= t)
+            && self.until.is_none_or(|t| event.at < t)
+    }
+}
+
+/// Events of the journal at `path` that pass `filter`, oldest first. A
+/// missing journal has no events; lines that do not parse (a write cut short,
+/// an event of a newer ccm) are skipped.
+pub fn read(path: &Path, filter: &Filter) -> Result<Vec<Event>, CcmError> {
+    let data = match fs::read_to_string(path) {
+        Ok(d) => d,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let events: Vec<Event> = data
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Event>(line).ok())
+        .collect();
+    let bases: HashMap<String, String> = events
+        .iter()
+        .filter_map(|e| match e.kind {
+            EventKind::Created {
+                base_ref: Some(ref base),
+                ..
+            } => Some((e.session.clone(), base.clone())),
+            _ => None,
+        })
+        .collect();
+    Ok(events
+        .into_iter()
+        .filter(|e| filter.matches(e, bases.get(&e.session).map(String::as_str)))
+        .collect())
+}
+
+/// Parse a `--since`/`--until` value: an age such as `30m`, `2h`, `3d` or
+/// `1w` (counted back from `now`), a local date `2025-01-31`, or an RFC 3339
+/// timestamp.
+pub fn parse_time(s: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, CcmError> {
+    let invalid = || {
+        CcmError::Journal(format!(
+            "invalid time '{s}' (expected e.g. 30m, 2h, 3d, 1w, 2025-01-31 or an RFC 3339 timestamp)"
+        ))
+    };
+    if let Some(unit) = s.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        if let Ok(n) = s[..s.len() - 1].parse::<i64>() {
+            let age = match unit {
+                's' => chrono::Duration::seconds(n),
+                'm' => chrono::Duration::minutes(n),
+                'h' => chrono::Duration::hours(n),
+                'd' => chrono::Duration::days(n),
+                'w' => chrono::Duration::weeks(n),
+                _ => return Err(invalid()),
+            };
+            return Ok(now - age);
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        let midnight = date.and_hms_opt(0, 0, 0).expect("midnight is valid");
+        return Local
+            .from_local_datetime(&midnight)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(invalid);
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    fn event(time: &str, session: &str, kind: EventKind) -> Event {
+        Event {
+            at: at(time),
+            session: session.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn event_serializes_flat() {
+        let e = event(
+            "2025-01-02T03:04:05Z",
+            "gj/main-feat",
+            EventKind::Closed { merged: true },
+        );
+        let json = serde_json::to_string(&e).unwrap();
+        assert_eq!(
+            json,
+            r#"{"at":"2025-01-02T03:04:05Z","session":"gj/main-feat","event":"closed","merged":true}"#
+        );
+        assert_eq!(serde_json::from_str::<Event>(&json).unwrap(), e);
+
+        let dead =
+            serde_json::to_string(&event("2025-01-02T03:04:05Z", "s", EventKind::Dead)).unwrap();
+        assert!(dead.ends_with(r#""event":"dead"}"#));
+    }
+
+    #[test]
+    fn event_kind_display() {
+        let created = EventKind::Created {
+            cwd: "/repo-feat".to_string(),
+            base_ref: Some("main".to_string()),
+        };
+        assert_eq!(created.to_string(), "created in /repo-feat from main");
+        let status = EventKind::Status {
+            status: "✳ Working".to_string(),
+        };
+        assert_eq!(status.to_string(), "status: ✳ Working");
+        assert_eq!(EventKind::Closed { merged: false }.to_string(), "closed");
+    }
+
+    #[test]
+    fn normalize_status_drops_spinner_frames() {
+        assert_eq!(normalize_status("⠂ Fix login"), "working: Fix login");
+        assert_eq!(normalize_status("⠐ Fix login"), "working: Fix login");
+        assert_eq!(normalize_status("✳ Fix login"), "idle: Fix login");
+        assert_eq!(normalize_status("✳"), "idle");
+        assert_eq!(normalize_status("zsh"), "idle: zsh");
+    }
+
+    #[test]
+    fn append_and_read_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_for(&dir.path().join("ccm").join("state.json"));
+        assert!(read(&path, &Filter::default()).unwrap().is_empty());
+
+        let events = [
+            event("2025-01-01T10:00:00Z", "gj/main-a", EventKind::Dead),
+            event("2025-01-02T10:00:00Z", "gj/main-b", EventKind::Alive),
+        ];
+        append(&path, &events[..1]).unwrap();
+        append(&path, &[]).unwrap();
+        append(&path, &events[1..]).unwrap();
+        // A torn last line is ignored
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"at":"2025-01-03T"#).unwrap();
+
+        assert_eq!(read(&path, &Filter::default()).unwrap(), events);
+    }
+
+    #[test]
+    fn filter_by_session_and_time() {
+        let e = event("2025-01-02T10:00:00Z", "gj/main-feat", EventKind::Purged);
+        let filter = |session: Option<&str>, since: Option<&str>, until: Option<&str>| Filter {
+            session: session.map(str::to_string),
+            branch_prefix: Some("gj/".to_string()),
+            since: since.map(at),
+            until: until.map(at),
+        };
+        let base = Some("main");
+        assert!(filter(None, None, None).matches(&e, base));
+        assert!(filter(Some("feat"), None, None).matches(&e, base));
+        assert!(!filter(Some("feat"), None, None).matches(&e, None));
+        assert!(filter(Some("gj/main-feat"), None, None).matches(&e, None));
+        assert!(!filter(Some("eat"), None, None).matches(&e, base));
+        assert!(filter(None, Some("2025-01-02T10:00:00Z"), None).matches(&e, base));
+        assert!(!filter(None, Some("2025-01-02T10:00:01Z"), None).matches(&e, base));
+        assert!(!filter(None, None, Some("2025-01-02T10:00:00Z")).matches(&e, base));
+    }
+
+    #[test]
+    fn read_matches_name_against_created_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = path_for(&dir.path().join("state.json"));
+        let created = |base: &str| EventKind::Created {
+            cwd: "/repo".to_string(),
+            base_ref: Some(base.to_string()),
+        };
+        append(
+            &path,
+            &[
+                event("2025-01-01T10:00:00Z", "gj/main-login", created("main")),
+                event("2025-01-01T11:00:00Z", "gj/main-fix-login", created("main")),
+                event("2025-01-01T12:00:00Z", "gj/main-fix-login", EventKind::Dead),
+                event("2025-01-01T13:00:00Z", "gj/main-login", EventKind::Dead),
+            ],
+        )
+        .unwrap();
+        let sessions = |name: &str| -> Vec<String> {
+            let filter = Filter {
+                session: Some(name.to_string()),
+                branch_prefix: Some("gj/".to_string()),
+                ..Filter::default()
+            };
+            read(&path, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|e| e.session)
+                .collect()
+        };
+        assert_eq!(sessions("login"), ["gj/main-login", "gj/main-login"]);
+        assert_eq!(
+            sessions("fix-login"),
+            ["gj/main-fix-login", "gj/main-fix-login"]
+        );
+        assert!(sessions("main-login").is_empty());
+    }
+
+    #[test]
+    fn parse_time_forms() {
+        let now = at("2025-01-10T12:00:00Z");
+        assert_eq!(parse_time("30m", now).unwrap(), at("2025-01-10T11:30:00Z"));
+        assert_eq!(parse_time("2h", now).unwrap(), at("2025-01-10T10:00:00Z"));
+        assert_eq!(parse_time("3d", now).unwrap(), at("2025-01-07T12:00:00Z"));
+        assert_eq!(parse_time("1w", now).unwrap(), at("2025-01-03T12:00:00Z"));
+        assert_eq!(
+            parse_time("2025-01-05T08:00:00+01:00", now).unwrap(),
+            at("2025-01-05T07:00:00Z")
+        );
+        let local = Local.with_ymd_and_hms(2025, 1, 5, 0, 0, 0).unwrap();
+        assert_eq!(
+            parse_time("2025-01-05", now).unwrap(),
+            local.with_timezone(&Utc)
+        );
+
+        for bad in ["", "h", "3y", "yesterday", "2025-13-01"] {
+            let err = parse_time(bad, now).unwrap_err();
+            assert!(err.to_string().contains("invalid time"), "{bad}: {err}");
+        }
+    }
+}
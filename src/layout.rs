@@ -0,0 +1,444 @@
+//! Builds the panes of a session tab from `[[layout.panes]]` and `[[aux_panes]]`.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+
+use crate::config::{AuxPane, Config, LayoutPane, RestartPolicy, CLAUDE_PANE};
+use crate::mux::Multiplexer;
+use crate::session::Session;
+
+/// Values substituted into layout pane commands.
+pub struct LayoutVars<'a> {
+    /// Path of the running ccm executable (`{ccm}`).
+    pub ccm: &'a str,
+    /// Session name (`{session}`).
+    pub session: &'a str,
+    /// Worktree path of the session (`{cwd}`).
+    pub cwd: &'a str,
+}
+
+fn expand_command(command: &[String], vars: &LayoutVars) -> Vec<String> {
+    command
+        .iter()
+        .map(|arg| {
+            arg.replace("{ccm}", vars.ccm)
+                .replace("{session}", vars.session)
+                .replace("{cwd}", vars.cwd)
+        })
+        .collect()
+}
+
+/// Split the layout panes off `claude_pane_id`, in order, and return their
+/// IDs by name. An optional pane that fails to split is skipped with a
+/// warning. If a required pane fails, the panes created so far are killed
+/// and the error is returned; the claude pane is left to the caller.
+pub fn build(
+    mux: &dyn Multiplexer,
+    panes: &[LayoutPane],
+    claude_pane_id: u64,
+    vars: &LayoutVars,
+) -> Result<BTreeMap<String, u64>> {
+    let mut built: BTreeMap<String, u64> = BTreeMap::new();
+    let mut created: Vec<u64> = Vec::new();
+
+    for pane in panes {
+        let parent = if pane.from == CLAUDE_PANE {
+            Some(claude_pane_id)
+        } else {
+            built.get(&pane.from).copied()
+        };
+
+        let result = match parent {
+            Some(parent) => {
+                let command = pane.command.as_ref().map(|c| expand_command(c, vars));
+                let argv: Option<Vec<&str>> = command
+                    .as_ref()
+                    .map(|c| c.iter().map(String::as_str).collect());
+                mux.split_pane(parent, pane.split, pane.size, argv.as_deref())
+                    .with_context(|| format!("failed to create {} pane", pane.name))
+            }
+            None => Err(anyhow::anyhow!(
+                "cannot create {} pane: pane '{}' does not exist",
+                pane.name,
+                pane.from
+            )),
+        };
+
+        match result {
+            Ok(id) => {
+                built.insert(pane.name.clone(), id);
+                created.push(id);
+            }
+            Err(e) if !pane.required => {
+                eprintln!("Warning: {e:#}");
+            }
+            Err(e) => {
+                for &id in created.iter().rev() {
+                    let _ = mux.kill_pane(id);
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    Ok(built)
+}
+
+/// Pane IDs of a freshly built session layout, excluding the claude pane.
+#[derive(Debug, Default)]
+pub struct SessionPanes {
+    pub panes: BTreeMap<String, u64>,
+    pub aux_panes: BTreeMap<String, u64>,
+}
+
+/// Placeholders of an aux pane command and the positional parameters that
+/// carry their values, after the pane name in `$1`.
+const AUX_PARAMS: [(&str, &str); 3] = [
+    ("{session}", "\"$2\""),
+    ("{cwd}", "\"$3\""),
+    ("{ccm}", "\"$4\""),
+];
+
+/// Shell script that runs an aux pane command under its restart policy.
+/// The pane name and placeholder values are passed as positional parameters
+/// (see [`AUX_PARAMS`]) so that they are never parsed as shell syntax.
+fn restart_script(aux: &AuxPane) -> String {
+    let delay = aux.restart_delay_secs;
+    let command = AUX_PARAMS
+        .iter()
+        .fold(aux.command.clone(), |command, (var, param)| {
+            command.replace(var, param)
+        });
+    let notice = format!(
+        "printf '\\n[ccm] %s exited with status %s, restarting in {delay}s\\n' \"$1\" \"$status\""
+    );
+    match aux.restart {
+        RestartPolicy::Never => command,
+        RestartPolicy::OnFailure => format!(
+            "while :; do\n{command}\nstatus=$?\n[ \"$status\" -eq 0 ] && break\n{notice}\nsleep {delay}\ndone"
+        ),
+        RestartPolicy::Always => {
+            format!("while :; do\n{command}\nstatus=$?\n{notice}\nsleep {delay}\ndone")
+        }
+    }
+}
+
+fn aux_layout_pane(aux: &AuxPane) -> LayoutPane {
+    LayoutPane {
+        name: aux.name.clone(),
+        split: aux.split,
+        from: aux.from.clone(),
+        size: aux.size,
+        command: Some(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            restart_script(aux),
+            "ccm-aux".to_string(),
+            aux.name.clone(),
+        ]
+        .into_iter()
+        .chain(AUX_PARAMS.iter().map(|(var, _)| var.to_string()))
+        .collect()),
+        required: false,
+    }
+}
+
+/// Build the layout panes and then the aux panes of a session.
+pub fn build_session(
+    mux: &dyn Multiplexer,
+    config: &Config,
+    claude_pane_id: u64,
+    vars: &LayoutVars,
+) -> Result<SessionPanes> {
+    let mut specs = config.layout.effective_panes();
+    specs.extend(config.aux_panes.iter().map(aux_layout_pane));
+
+    let mut panes = build(mux, &specs, claude_pane_id, vars)?;
+    let aux_panes = config
+        .aux_panes
+        .iter()
+        .filter_map(|aux| panes.remove_entry(&aux.name))
+        .collect();
+    Ok(SessionPanes { panes, aux_panes })
+}
+
+/// Kill every pane of a session, ignoring errors for panes that are already
+/// gone. The pane this process runs in is killed last, so that closing the
+/// session from one of its own panes finishes the others first.
+pub fn kill_session_panes(mux: &dyn Multiplexer, session: &Session) {
+    let current = mux.current_pane_id().ok();
+    let mut own = None;
+    for id in session.pane_ids() {
+        if Some(id) == current {
+            own = Some(id);
+        } else {
+            let _ = mux.kill_pane(id);
+        }
+    }
+    if let Some(id) = own {
+        let _ = mux.kill_pane(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LayoutConfig;
+    use crate::mux::fake::FakeMux;
+    use crate::mux::SplitDirection;
+
+    const VARS: LayoutVars<'static> = LayoutVars {
+        ccm: "/bin/ccm",
+        session: "gj/main-x",
+        cwd: "/work/x",
+    };
+
+    fn pane(name: &str, from: &str, split: SplitDirection, required: bool) -> LayoutPane {
+        LayoutPane {
+            name: name.to_string(),
+            split,
+            from: from.to_string(),
+            size: 30,
+            command: None,
+            required,
+        }
+    }
+
+    #[test]
+    fn expand_command_substitutes_vars() {
+        let cmd = vec![
+            "{ccm}".to_string(),
+            "plan-viewer".to_string(),
+            "--cwd={cwd}".to_string(),
+            "{session}".to_string(),
+        ];
+        assert_eq!(
+            expand_command(&cmd, &VARS),
+            ["/bin/ccm", "plan-viewer", "--cwd=/work/x", "gj/main-x"]
+        );
+    }
+
+    #[test]
+    fn build_default_layout() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/work/x").unwrap();
+        let panes = LayoutConfig::default().effective_panes();
+        let built = build(&mux, &panes, claude, &VARS).unwrap();
+
+        assert_eq!(built.len(), 3);
+        let watcher = mux.pane(built["watcher"]).unwrap();
+        assert_eq!(watcher.split_from, Some((claude, SplitDirection::Left, 20)));
+        assert_eq!(
+            watcher.program.unwrap(),
+            ["/bin/ccm", "tab-watcher", "--session", "gj/main-x"]
+        );
+        assert_eq!(mux.pane(built["shell"]).unwrap().program, None);
+        assert_eq!(
+            mux.pane(built["plans"]).unwrap().program.unwrap(),
+            ["/bin/ccm", "plan-viewer", "--cwd", "/work/x"]
+        );
+    }
+
+    #[test]
+    fn build_splits_from_named_pane() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/w").unwrap();
+        let panes = [
+            pane("side", CLAUDE_PANE, SplitDirection::Right, true),
+            pane("below", "side", SplitDirection::Bottom, true),
+        ];
+        let built = build(&mux, &panes, claude, &VARS).unwrap();
+        let below = mux.pane(built["below"]).unwrap();
+        assert_eq!(below.split_from.unwrap().0, built["side"]);
+    }
+
+    #[test]
+    fn build_skips_failed_optional_pane_and_dependents() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/w").unwrap();
+        mux.fail_on("split_pane", 1);
+        let panes = [
+            pane("opt", CLAUDE_PANE, SplitDirection::Right, false),
+            pane("child", "opt", SplitDirection::Bottom, false),
+            pane("shell", CLAUDE_PANE, SplitDirection::Bottom, true),
+        ];
+        let built = build(&mux, &panes, claude, &VARS).unwrap();
+        assert_eq!(built.keys().collect::<Vec<_>>(), ["shell"]);
+        assert_eq!(mux.calls("split_pane"), 2);
+    }
+
+    #[test]
+    fn build_required_failure_kills_created_panes() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/w").unwrap();
+        mux.fail_on("split_pane", 3);
+        let panes = [
+            pane("a", CLAUDE_PANE, SplitDirection::Left, true),
+            pane("b", CLAUDE_PANE, SplitDirection::Right, false),
+            pane("c", CLAUDE_PANE, SplitDirection::Bottom, true),
+        ];
+        let err = build(&mux, &panes, claude, &VARS).unwrap_err();
+        assert!(err.to_string().contains("c pane"));
+        assert_eq!(mux.live_pane_ids(), vec![claude]);
+    }
+
+    fn aux(restart: RestartPolicy) -> AuxPane {
+        AuxPane {
+            name: "server".to_string(),
+            split: SplitDirection::Right,
+            from: "shell".to_string(),
+            size: 40,
+            command: "npm run dev --prefix {cwd}".to_string(),
+            restart,
+            restart_delay_secs: 3,
+        }
+    }
+
+    #[test]
+    fn restart_script_never_runs_command_once() {
+        assert_eq!(
+            restart_script(&aux(RestartPolicy::Never)),
+            "npm run dev --prefix \"$3\""
+        );
+    }
+
+    #[test]
+    fn restart_script_on_failure_stops_on_success() {
+        let script = restart_script(&aux(RestartPolicy::OnFailure));
+        assert!(script.starts_with("while :; do\nnpm run dev --prefix \"$3\"\n"));
+        assert!(script.contains("-eq 0 ] && break"));
+        assert!(script.contains("sleep 3"));
+    }
+
+    #[test]
+    fn restart_script_always_loops() {
+        let script = restart_script(&aux(RestartPolicy::Always));
+        assert!(!script.contains("break"));
+        assert!(script.contains("restarting in 3s"));
+    }
+
+    #[test]
+    fn restart_scripts_are_valid_sh() {
+        for policy in [RestartPolicy::OnFailure, RestartPolicy::Always] {
+            let status = std::process::Command::new("sh")
+                .args(["-n", "-c", &restart_script(&aux(policy))])
+                .status()
+                .unwrap();
+            assert!(status.success(), "{policy:?}");
+        }
+    }
+
+    #[test]
+    fn restart_script_on_failure_retries_until_success() {
+        let dir = tempfile::tempdir().unwrap();
+        let counter = dir.path().join("runs");
+        let mut a = aux(RestartPolicy::OnFailure);
+        // Fails twice, then succeeds
+        a.command = format!(
+            "echo x >> {0}; [ $(wc -l < {0}) -ge 3 ]",
+            counter.display()
+        );
+        a.restart_delay_secs = 0;
+        let output = std::process::Command::new("sh")
+            .args(["-c", &restart_script(&a), "ccm-aux", "server"])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(std::fs::read_to_string(&counter).unwrap().lines().count(), 3);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("[ccm] server exited with status 1"));
+    }
+
+    #[test]
+    fn restart_script_passes_placeholders_unparsed() {
+        let dir = tempfile::tempdir().unwrap();
+        let cwd = dir.path().join("it's a dir");
+        std::fs::create_dir(&cwd).unwrap();
+        let mut a = aux(RestartPolicy::Never);
+        a.command = "cd {cwd} && printf '%s|%s' {session} \"$PWD\" > out".to_string();
+        let status = std::process::Command::new("sh")
+            .args(["-c", &restart_script(&a), "ccm-aux", "server"])
+            .args(["feat; $(x) `y`", cwd.to_str().unwrap(), "/bin/ccm"])
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert_eq!(
+            std::fs::read_to_string(cwd.join("out")).unwrap(),
+            format!("feat; $(x) `y`|{}", cwd.display())
+        );
+    }
+
+    #[test]
+    fn build_session_separates_aux_panes() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/work/x").unwrap();
+        let config = Config {
+            aux_panes: vec![aux(RestartPolicy::Always)],
+            ..Config::default()
+        };
+        let built = build_session(&mux, &config, claude, &VARS).unwrap();
+        assert_eq!(built.panes.len(), 3);
+        assert_eq!(built.aux_panes.keys().collect::<Vec<_>>(), ["server"]);
+
+        let server = mux.pane(built.aux_panes["server"]).unwrap();
+        assert_eq!(server.split_from.unwrap().0, built.panes["shell"]);
+        let program = server.program.unwrap();
+        assert_eq!(program[..2], ["sh", "-c"]);
+        assert!(program[2].contains("npm run dev --prefix \"$3\""));
+        assert_eq!(
+            program[3..],
+            ["ccm-aux", "server", "gj/main-x", "/work/x", "/bin/ccm"]
+        );
+    }
+
+    #[test]
+    fn build_session_aux_failure_is_not_fatal() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/w").unwrap();
+        let config = Config {
+            aux_panes: vec![aux(RestartPolicy::Never)],
+            ..Config::default()
+        };
+        mux.fail_on("split_pane", 4);
+        let built = build_session(&mux, &config, claude, &VARS).unwrap();
+        assert_eq!(built.panes.len(), 3);
+        assert!(built.aux_panes.is_empty());
+    }
+
+    #[test]
+    fn kill_session_panes_kills_current_pane_last() {
+        let mux = FakeMux::new();
+        let claude = mux.spawn_tab("/w").unwrap();
+        let built = build(
+            &mux,
+            &LayoutConfig::default().effective_panes(),
+            claude,
+            &VARS,
+        )
+        .unwrap();
+        let session = Session {
+            name: "s".to_string(),
+            tab_id: 0,
+            claude_pane_id: claude,
+            panes: built.clone(),
+            aux_panes: BTreeMap::new(),
+            cwd: "/w".to_string(),
+            base_ref: None,
+            created_at: chrono::Utc::now(),
+            claude_status: None,
+            dead_since: None,
+            adopted: false,
+            description: None,
+            tags: Vec::new(),
+            ticket: None,
+            notes: Vec::new(),
+            repo: None,
+        };
+        // Last kill must be the pane we run in; make it fail to observe order
+        mux.set_current_pane(built["shell"]);
+        mux.fail_on("kill_pane", 4);
+        kill_session_panes(&mux, &session);
+        assert_eq!(mux.live_pane_ids(), vec![built["shell"]]);
+    }
+}
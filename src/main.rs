@@ -1,27 +1,44 @@
+mod archive;
+mod bootstrap;
+mod checks;
 mod cli;
 mod config;
+mod diff;
 mod error;
-mod gj;
+mod gc;
+mod git;
+mod journal;
+mod layout;
+mod migrate;
+mod mux;
+mod naming;
+mod overlap;
 mod plan_viewer;
 mod pty_wrap;
 mod session;
 mod state;
+mod summary;
 mod tui;
-mod wezterm;
+mod worktree;
 
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 use anyhow::{Context, Result};
 use chrono::Utc;
 use clap::Parser;
 
-use cli::{Cli, Command};
-use config::Config;
+use cli::{ArchiveCommand, Cli, Command};
+use config::{Config, SlugMode};
 use error::CcmError;
-use session::Session;
+use journal::{Event, EventKind};
+use layout::LayoutVars;
+use mux::Multiplexer;
+use session::{Annotation, Session};
+use worktree::WorktreeProvider;
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
@@ -38,19 +55,95 @@ fn main() -> Result<()> {
         eprintln!("hint: no config file found. Run 'ccm init' to create ~/.config/ccm/config.toml");
     }
 
+    let mux = mux::from_config(&config);
+    let worktrees = worktree::from_config(&config);
+    let ctx = Ctx {
+        config: &config,
+        mux: mux.as_ref(),
+        worktrees: worktrees.as_ref(),
+        state_path: state::state_path()?,
+    };
+
     match cli.command {
-        Command::New { name, cwd } => {
+        Command::New {
+            name,
+            cwd,
+            from,
+            description,
+            tags,
+            ticket,
+        } => {
             let claude_cmd = config.wezterm.claude_command.clone();
-            cmd_new(&config, name, cwd, Some(claude_cmd))?;
+            let info = cmd_new(&ctx, name, ticket.as_deref(), cwd, from, Some(claude_cmd))?;
+            let annotation = Annotation {
+                description,
+                add_tags: tags,
+                ticket,
+                ..Annotation::default()
+            };
+            if !annotation.is_empty() {
+                annotate(&ctx, &info.session_name, &annotation)?;
+            }
+        }
+        Command::List { tags, repo } => cmd_list(&ctx, &tags, repo.as_deref())?,
+        Command::Annotate {
+            name,
+            description,
+            tags,
+            untags,
+            ticket,
+            note,
+        } => {
+            let annotation = Annotation {
+                description,
+                add_tags: tags,
+                remove_tags: untags,
+                ticket,
+                note,
+            };
+            cmd_annotate(&ctx, name, &annotation)?;
+        }
+        Command::Conflicts => {
+            cmd_conflicts(&ctx)?;
+        }
+        Command::Diff { name } => cmd_diff(&ctx, name)?,
+        Command::Archive { command } => match command {
+            ArchiveCommand::List => cmd_archive_list(&ctx)?,
+            ArchiveCommand::Show { name } => cmd_archive_show(&ctx, &name)?,
+        },
+        Command::Reopen { name } => cmd_reopen(&ctx, &name)?,
+        Command::History {
+            session,
+            since,
+            until,
+        } => cmd_history(&ctx, session, since.as_deref(), until.as_deref())?,
+        Command::Switch { name } => cmd_switch(&ctx, &name)?,
+        Command::Close {
+            name,
+            merge,
+            remove_worktree,
+            skip_checks,
+        } => cmd_close(&ctx, name, merge, remove_worktree, skip_checks)?,
+        Command::Adopt { target, name, cwd } => cmd_adopt(&ctx, target, name, cwd)?,
+        Command::Plan { cwd, from } => cmd_plan(&ctx, cwd, from)?,
+        Command::ResetLayout => cmd_reset_layout(&ctx)?,
+        Command::Restore { name, all, fresh } => cmd_restore(&ctx, name, all, fresh)?,
+        Command::Prune { remove_worktrees } => cmd_prune(&ctx, remove_worktrees)?,
+        Command::Gc { cwd, yes } => {
+            cmd_gc(&ctx, cwd, yes, &mut std::io::stdin().lock())?;
         }
-        Command::List => cmd_list(&config)?,
-        Command::Switch { name } => cmd_switch(&config, &name)?,
-        Command::Close { name, merge } => cmd_close(&config, name, merge)?,
-        Command::Plan { cwd } => cmd_plan(&config, cwd)?,
-        Command::ResetLayout => cmd_reset_layout(&config)?,
         Command::TabWatcher { session } => tui::run(&session, &config)?,
         Command::PlanViewer { cwd } => plan_viewer::run(&cwd)?,
-        Command::Wrap { session, prompt_file, command } => {
+        Command::Bootstrap { cwd, main } => cmd_bootstrap(&cwd, &main)?,
+        Command::Wrap {
+            session,
+            prompt_file,
+            wait_for,
+            command,
+        } => {
+            if let Some(path) = wait_for {
+                wait_for_bootstrap(Path::new(&path));
+            }
             let exit_code = pty_wrap::run_wrap(&session, &command, prompt_file.as_deref())?;
             std::process::exit(exit_code);
         }
@@ -60,23 +153,41 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// External dependencies of the commands. Tests substitute an in-memory
+/// multiplexer, a fake worktree provider and a temporary state file.
+struct Ctx<'a> {
+    config: &'a Config,
+    mux: &'a dyn Multiplexer,
+    worktrees: &'a dyn WorktreeProvider,
+    state_path: PathBuf,
+}
+
+#[derive(Debug)]
 struct NewSessionInfo {
     worktree_path: String,
     #[allow(dead_code)]
     session_name: String,
     claude_pane_id: u64,
     #[allow(dead_code)]
-    plans_pane_id: Option<u64>,
+    panes: BTreeMap<String, u64>,
+    /// Bootstrap status file the claude command must wait for.
+    wait_for: Option<String>,
 }
 
-/// Creates a new session. If `claude_command` is `Some`, sends it to the claude pane.
+/// Creates a new session, branching from `from` if given (else the provider's
+/// default base). `name` and `ticket` feed `naming.template` (see
+/// `session_suffix`). If `claude_command` is `Some`, sends it to the claude pane.
 /// If `None`, the caller is responsible for sending the command later.
 fn cmd_new(
-    config: &Config,
+    ctx: &Ctx,
     name: Option<String>,
+    ticket: Option<&str>,
     cwd: Option<String>,
+    from: Option<String>,
     claude_command: Option<String>,
 ) -> Result<NewSessionInfo> {
+    let mux = ctx.mux;
+
     let cwd = match cwd {
         Some(p) => p,
         None => env::current_dir()
@@ -85,352 +196,1040 @@ fn cmd_new(
             .to_string(),
     };
 
-    // 1. Create git worktree via gj
-    let gj_output = gj::new_worktree(&cwd, name.as_deref())
+    // 1. Name the session and create its git worktree
+    let suffix = session_suffix(ctx, &cwd, name.as_deref(), ticket, from.as_deref())?;
+    let new_output = ctx
+        .worktrees
+        .new_worktree(&cwd, Some(&suffix), from.as_deref())
         .context("failed to create git worktree")?;
-    let worktree_path = gj_output.worktree_path;
-    let branch = gj_output.branch;
+    let worktree_path = new_output.worktree_path;
+    let branch = new_output.branch;
+    let base_ref = new_output.base.or(from);
 
     // Validate branch name is not empty
     if branch.is_empty() {
-        let _ = gj::exit_worktree(&worktree_path, false);
-        return Err(anyhow::anyhow!("gj returned empty branch name"));
+        let _ = ctx.worktrees.exit_worktree(&worktree_path, false);
+        return Err(anyhow::anyhow!("worktree provider returned empty branch name"));
     }
 
     // session-name is always the same as branch name
     let session_name = branch.clone();
 
-    let binary = &config.wezterm.binary;
-
-    // Helper: kill panes + clean up worktree on failure
-    let cleanup_panes = |binary: &str, panes: &[u64]| {
-        for &pane_id in panes {
-            let _ = wezterm::kill_pane(binary, pane_id);
-        }
-    };
-
-    // 2. Spawn new tab in the worktree directory (this becomes the claude pane)
-    let claude_pane_id = match wezterm::spawn_tab(binary, &worktree_path) {
-        Ok(id) => id,
-        Err(e) => {
-            let _ = gj::exit_worktree(&worktree_path, false);
-            return Err(e).context("failed to spawn new tab for session");
-        }
-    };
-
-    // 3. Split left for tab-watcher
-    let ccm_path = env::current_exe().context("failed to get ccm executable path")?;
-    let ccm_str = ccm_path.to_string_lossy().to_string();
-    let watcher_pane_id = match wezterm::split_pane(
-        binary,
-        claude_pane_id,
-        wezterm::SplitDirection::Left,
-        config.layout.watcher_width,
-        Some(&[&ccm_str, "tab-watcher", "--session", &session_name]),
-    ) {
-        Ok(id) => id,
-        Err(e) => {
-            cleanup_panes(binary, &[claude_pane_id]);
-            let _ = gj::exit_worktree(&worktree_path, false);
-            return Err(e).context("failed to create tab-watcher pane");
-        }
-    };
-
-    // 4. Split bottom for shell
-    let shell_pane_id = match wezterm::split_pane(
-        binary,
-        claude_pane_id,
-        wezterm::SplitDirection::Bottom,
-        config.layout.shell_height,
-        None,
-    ) {
-        Ok(id) => id,
+    // The main checkout's bootstrap spec, run before claude starts
+    let main_dir = bootstrap::source_dir(&cwd);
+    let spec = match bootstrap::Spec::load(&main_dir) {
+        Ok(spec) => spec,
         Err(e) => {
-            cleanup_panes(binary, &[claude_pane_id, watcher_pane_id]);
-            let _ = gj::exit_worktree(&worktree_path, false);
-            return Err(e).context("failed to create shell pane");
+            let _ = ctx.worktrees.exit_worktree(&worktree_path, false);
+            return Err(e.into());
         }
     };
-
-    // 5. Split right for plans viewer (best-effort: failure doesn't abort session)
-    let plans_pane_id = match wezterm::split_pane(
-        binary,
-        claude_pane_id,
-        wezterm::SplitDirection::Right,
-        config.layout.plans_width,
-        Some(&[&ccm_str, "plan-viewer", "--cwd", &worktree_path]),
+    let bootstrap_from = spec.is_some().then_some(main_dir.as_path());
+
+    // 2. Spawn the tab, build the layout, bootstrap and start claude
+    let tab = match open_session_tab(
+        ctx,
+        &session_name,
+        &worktree_path,
+        claude_command.as_deref(),
+        bootstrap_from,
     ) {
-        Ok(id) => Some(id),
+        Ok(tab) => tab,
         Err(e) => {
-            eprintln!("Warning: failed to create plans viewer pane: {e}");
-            None
+            let _ = ctx.worktrees.exit_worktree(&worktree_path, false);
+            return Err(e);
         }
     };
+    let tab_id = tab.tab_id;
+    let claude_pane_id = tab.claude_pane_id;
 
-    // 6. Send claude command to the claude pane (via PTY wrapper for OSC 0 detection)
-    if let Some(cmd) = &claude_command {
-        let quoted_session = session_name.replace('\'', "'\\''");
-        let wrapped_cmd = format!(
-            "{} wrap --session '{}' -- {}\n",
-            ccm_str,
-            quoted_session,
-            cmd.trim_end_matches('\n')
-        );
-        wezterm::send_text(binary, claude_pane_id, &wrapped_cmd)
-            .context("failed to send claude command to pane")?;
-    }
-
-    // 7. Set tab title
-    wezterm::set_tab_title(binary, watcher_pane_id, &session_name)
-        .context("failed to set tab title")?;
-
-    // 8. Find the tab_id from wezterm list
-    let panes = wezterm::list_panes(binary).context("failed to list panes")?;
-    let tab_id = panes
-        .iter()
-        .find(|p| p.pane_id == claude_pane_id)
-        .map(|p| p.tab_id)
-        .ok_or_else(|| anyhow::anyhow!("could not find tab_id for pane {claude_pane_id}"))?;
-
-    // 9. Save to state (duplicate check inside lock to avoid TOCTOU race)
+    // 3. Save to state (duplicate check inside lock to avoid TOCTOU race)
     let session = Session {
         name: session_name.clone(),
         tab_id,
-        watcher_pane_id,
         claude_pane_id,
-        shell_pane_id,
-        cwd: worktree_path,
+        panes: tab.panes.panes.clone(),
+        aux_panes: tab.panes.aux_panes.clone(),
+        cwd: worktree_path.clone(),
+        base_ref: base_ref.clone(),
         created_at: Utc::now(),
         claude_status: None,
-        plans_pane_id,
+        dead_since: None,
+        adopted: false,
+        description: None,
+        tags: Vec::new(),
+        ticket: None,
+        notes: Vec::new(),
+        repo: Some(main_dir.to_string_lossy().into_owned()),
     };
 
-    let result = state::update(|state| {
-        if state.sessions.iter().any(|s| s.name == session_name) {
-            return Err(CcmError::SessionExists(session_name.clone()));
-        }
-        state.active_session = Some(session_name.clone());
-        state.sessions.push(session.clone());
-        Ok(())
-    });
-
-    if let Err(e) = result {
-        let mut panes_to_kill = vec![watcher_pane_id, shell_pane_id, claude_pane_id];
-        if let Some(pid) = plans_pane_id {
-            panes_to_kill.push(pid);
-        }
-        cleanup_panes(binary, &panes_to_kill);
-        let _ = gj::exit_worktree(&session.cwd, false);
+    if let Err(e) = insert_session(ctx, &session) {
+        tab.kill(mux);
+        let _ = ctx.worktrees.exit_worktree(&worktree_path, false);
         return Err(e.into());
     }
+    let created = EventKind::Created {
+        cwd: worktree_path.clone(),
+        base_ref: base_ref.clone(),
+    };
+    record(ctx, &[Event::new(&session_name, created)]);
 
     // Activate claude pane so user can immediately interact with Claude Code
     // This is best-effort; if it fails, the session is still functional
-    if let Err(e) = wezterm::activate_pane(binary, claude_pane_id) {
+    if let Err(e) = mux.activate_pane(claude_pane_id) {
         eprintln!("Warning: failed to activate claude pane: {e}");
     }
 
-    let created_cwd = session.cwd.clone();
-    println!("Created session '{session_name}' (tab {tab_id}, branch {branch})");
+    match base_ref {
+        Some(base) => println!(
+            "Created session '{session_name}' (tab {tab_id}, branch {branch} from {base})"
+        ),
+        None => println!("Created session '{session_name}' (tab {tab_id}, branch {branch})"),
+    }
     Ok(NewSessionInfo {
-        worktree_path: created_cwd,
+        worktree_path,
         session_name,
         claude_pane_id,
-        plans_pane_id,
+        panes: tab.panes.panes,
+        wait_for: tab.wait_for,
     })
 }
 
-fn cmd_list(config: &Config) -> Result<()> {
-    let state = state::load()?;
-    let live_panes = wezterm::list_panes(&config.wezterm.binary).unwrap_or_default();
-    let live_pane_ids: std::collections::HashSet<u64> =
-        live_panes.iter().map(|p| p.pane_id).collect();
-
-    if state.sessions.is_empty() {
-        println!("No sessions.");
-        return Ok(());
+/// Render `naming.template` for a new session in `cwd`: `slug` is the name
+/// given to `ccm new` (or derived from the plan), `ticket` fills `{ticket}`.
+/// The result is the suffix handed to the worktree provider. A name whose
+/// branch (or session) already exists gets a counter appended.
+fn session_suffix(
+    ctx: &Ctx,
+    cwd: &str,
+    slug: Option<&str>,
+    ticket: Option<&str>,
+    from: Option<&str>,
+) -> Result<String> {
+    let naming = &ctx.config.naming;
+    let mut vars = naming::NameVars::new(Utc::now(), worktree::random_suffix());
+    vars.slug = slug.map(str::to_string);
+    vars.ticket = ticket.map(str::to_string);
+    let mut name = naming::render(&naming.template, &vars)?;
+    if name.is_empty() {
+        name = vars.rand;
     }
 
-    for session in &state.sessions {
-        let is_active = state.active_session.as_deref() == Some(&session.name);
-        let active_mark = if is_active { " *" } else { "" };
+    let state = state::load_from(&ctx.state_path)?;
+    let mut existing: Vec<String> = state.sessions.into_iter().map(|s| s.name).collect();
+    existing.extend(git::branches(cwd).unwrap_or_default());
+    Ok(naming::unique(&name, |candidate| {
+        let branch = ctx.worktrees.branch_name(cwd, candidate, from);
+        existing
+            .iter()
+            .any(|e| e == candidate || Some(e) == branch.as_ref())
+    }))
+}
+
+/// Add `session` to state and make it active. The duplicate check runs inside
+/// the lock to avoid a TOCTOU race with other ccm processes.
+fn insert_session(ctx: &Ctx, session: &Session) -> Result<(), CcmError> {
+    state::update_at(&ctx.state_path, |state| {
+        if state.sessions.iter().any(|s| s.name == session.name) {
+            return Err(CcmError::SessionExists(session.name.clone()));
+        }
+        state.active_session = Some(session.name.clone());
+        state.sessions.push(session.clone());
+        Ok(())
+    })?;
+    Ok(())
+}
 
-        let alive = live_pane_ids.contains(&session.claude_pane_id)
-            || live_pane_ids.contains(&session.shell_pane_id)
-            || session.plans_pane_id.is_some_and(|id| live_pane_ids.contains(&id));
-        let status = if alive { "" } else { " [dead]" };
+/// Wrap an existing worktree (a path, or the worktree of a branch) in a ccm
+/// session without creating a new one through the worktree provider.
+fn cmd_adopt(
+    ctx: &Ctx,
+    target: Option<String>,
+    name: Option<String>,
+    cwd: Option<String>,
+) -> Result<()> {
+    let cwd = match cwd {
+        Some(p) => p,
+        None => env::current_dir()
+            .context("failed to get current directory")?
+            .to_string_lossy()
+            .to_string(),
+    };
+    let target = target.unwrap_or_else(|| cwd.clone());
+    let worktree_path = resolve_adopt_target(ctx, &cwd, &target)?;
 
-        let claude_status = session
-            .claude_status
-            .as_deref()
-            .unwrap_or("");
-        let claude_info = if claude_status.is_empty() {
-            String::new()
-        } else {
-            format!(" [{claude_status}]")
-        };
+    let branch = git::current_branch(&worktree_path).ok();
+    let session_name = match name.or(branch) {
+        Some(n) => n,
+        None => Path::new(&worktree_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .context("cannot derive a session name; pass --name")?,
+    };
 
-        println!(
-            "  {}{active_mark}{status}{claude_info}  (tab:{}, cwd:{})",
-            session.name, session.tab_id, session.cwd
+    let state = state::load_from(&ctx.state_path)?;
+    if let Some(existing) = state.sessions.iter().find(|s| same_path(&s.cwd, &worktree_path)) {
+        anyhow::bail!(
+            "{worktree_path} already belongs to session '{}'",
+            existing.name
         );
     }
+    if state.sessions.iter().any(|s| s.name == session_name) {
+        return Err(CcmError::SessionExists(session_name).into());
+    }
+
+    let claude_command = ctx.config.wezterm.claude_command.clone();
+    let tab = open_session_tab(ctx, &session_name, &worktree_path, Some(&claude_command), None)?;
+
+    let session = Session {
+        name: session_name.clone(),
+        tab_id: tab.tab_id,
+        claude_pane_id: tab.claude_pane_id,
+        panes: tab.panes.panes.clone(),
+        aux_panes: tab.panes.aux_panes.clone(),
+        cwd: worktree_path.clone(),
+        base_ref: None,
+        created_at: Utc::now(),
+        claude_status: None,
+        dead_since: None,
+        adopted: true,
+        description: None,
+        tags: Vec::new(),
+        ticket: None,
+        notes: Vec::new(),
+        repo: git::main_worktree(&worktree_path).ok(),
+    };
+    if let Err(e) = insert_session(ctx, &session) {
+        tab.kill(ctx.mux);
+        return Err(e.into());
+    }
 
+    if let Err(e) = ctx.mux.activate_pane(tab.claude_pane_id) {
+        eprintln!("Warning: failed to activate claude pane: {e}");
+    }
+    println!(
+        "Adopted {worktree_path} as session '{session_name}' (tab {})",
+        tab.tab_id
+    );
     Ok(())
 }
 
-fn cmd_switch(config: &Config, name: &str) -> Result<()> {
-    // Read state under lock, validate session exists, update active, then activate tab
-    let tab_id = state::update(|state| {
-        if !state.sessions.iter().any(|s| s.name == name) {
-            return Err(CcmError::SessionNotFound(name.to_string()));
+/// Resolve the `ccm adopt` argument to a worktree root. A directory must be
+/// the root of a git worktree. Otherwise the argument is a branch: its
+/// worktree is used, or one is created at `git.worktree_path` if the branch
+/// is not checked out anywhere.
+fn resolve_adopt_target(ctx: &Ctx, cwd: &str, target: &str) -> Result<String> {
+    if Path::new(target).is_dir() {
+        let toplevel = git::toplevel(target)
+            .with_context(|| format!("{target} is not inside a git worktree"))?;
+        if !same_path(&toplevel, target) {
+            anyhow::bail!("{target} is not the root of a git worktree (the root is {toplevel})");
         }
-        state.active_session = Some(name.to_string());
-        Ok(())
-    })?
-    .sessions
-    .iter()
-    .find(|s| s.name == name)
-    .map(|s| s.tab_id)
-    .expect("session was just validated to exist");
-
-    wezterm::activate_tab(&config.wezterm.binary, tab_id).context("failed to activate tab")?;
+        return Ok(toplevel);
+    }
 
-    println!("Switched to '{name}'");
-    Ok(())
+    branch_worktree(ctx, cwd, target)?
+        .with_context(|| format!("'{target}' is neither a directory nor a local branch"))
 }
 
-fn get_editor() -> String {
-    env::var("VISUAL")
-        .or_else(|_| env::var("EDITOR"))
-        .unwrap_or_else(|_| "vim".to_string())
+/// The worktree of `branch` in the repository of `cwd`, created at
+/// `git.worktree_path` if the branch is not checked out anywhere. None if
+/// there is no such local branch.
+fn branch_worktree(ctx: &Ctx, cwd: &str, branch: &str) -> Result<Option<String>> {
+    if let Some(path) = git::worktree_for_branch(cwd, branch)? {
+        return Ok(Some(path));
+    }
+    if !git::branch_exists(cwd, branch)? {
+        return Ok(None);
+    }
+    let main = git::main_worktree(cwd)?;
+    let path = worktree::branch_worktree_path(ctx.config, Path::new(&main), branch);
+    let path = path.to_string_lossy().into_owned();
+    git::run(cwd, &["worktree", "add", &path, branch])
+        .with_context(|| format!("failed to create a worktree for branch '{branch}'"))?;
+    println!("Created worktree {path} for branch '{branch}'");
+    Ok(Some(path))
 }
 
-fn open_editor_for_plan() -> Result<String> {
-    use std::io::Read;
-
-    let tmp_dir = env::temp_dir();
-    let tmp_path = tmp_dir.join(format!(
-        "ccm-plan-{}-{}.md",
-        std::process::id(),
-        Utc::now().timestamp_nanos_opt().unwrap_or(0)
-    ));
-
-    // Use create_new (O_EXCL) to prevent symlink attacks on predictable paths
-    fs::File::create_new(&tmp_path).context("failed to create temporary plan file")?;
-
-    let editor = get_editor();
-    let mut parts = editor.split_whitespace();
-    let program = parts.next().unwrap_or("vim");
-    let editor_args: Vec<&str> = parts.collect();
+/// Whether two paths name the same directory (falls back to string equality
+/// when either cannot be canonicalized).
+fn same_path(a: &str, b: &str) -> bool {
+    match (Path::new(a).canonicalize(), Path::new(b).canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
 
-    let status = std::process::Command::new(program)
-        .args(editor_args)
-        .arg(&tmp_path)
-        .status()
-        .context(format!("failed to launch editor '{}'", editor))?;
+/// A session tab built by `open_session_tab`.
+struct SessionTab {
+    tab_id: u64,
+    claude_pane_id: u64,
+    panes: layout::SessionPanes,
+    /// Bootstrap status file the claude command waits for, when bootstrap
+    /// runs in the shell pane.
+    wait_for: Option<String>,
+}
 
-    if !status.success() {
-        fs::remove_file(&tmp_path).ok();
-        anyhow::bail!("editor exited with non-zero status");
+impl SessionTab {
+    /// Kill every pane of the tab (rollback after a later failure).
+    fn kill(&self, mux: &dyn Multiplexer) {
+        let panes = self.panes.panes.values().chain(self.panes.aux_panes.values());
+        for &pane_id in panes {
+            let _ = mux.kill_pane(pane_id);
+        }
+        let _ = mux.kill_pane(self.claude_pane_id);
     }
+}
 
-    let mut content = String::new();
-    fs::File::open(&tmp_path)
-        .context("failed to open plan file after editing")?
-        .read_to_string(&mut content)
-        .context("failed to read plan file")?;
-
-    fs::remove_file(&tmp_path).ok();
+/// Spawn a tab in `cwd`, build the configured layout, send `claude_command`
+/// (if any) to the claude pane through `ccm wrap`, and set the tab title.
+/// With `bootstrap` (the main checkout holding a bootstrap spec), the
+/// worktree is bootstrapped in the shell pane and claude waits for it; without
+/// a shell pane it is bootstrapped here, before claude starts.
+/// On failure, every pane created here is killed.
+fn open_session_tab(
+    ctx: &Ctx,
+    session_name: &str,
+    cwd: &str,
+    claude_command: Option<&str>,
+    bootstrap: Option<&Path>,
+) -> Result<SessionTab> {
+    let mux = ctx.mux;
+
+    // Spawn new tab in the worktree directory (this becomes the claude pane)
+    let claude_pane_id = mux
+        .spawn_tab(cwd)
+        .context("failed to spawn new tab for session")?;
+    let kill_claude = || {
+        let _ = mux.kill_pane(claude_pane_id);
+    };
 
-    Ok(content)
-}
+    // Build the remaining panes from the layout and aux pane config
+    let ccm_str = match env::current_exe() {
+        Ok(path) => path.to_string_lossy().to_string(),
+        Err(e) => {
+            kill_claude();
+            return Err(e).context("failed to get ccm executable path");
+        }
+    };
+    let vars = LayoutVars {
+        ccm: &ccm_str,
+        session: session_name,
+        cwd,
+    };
+    let panes = match layout::build_session(mux, ctx.config, claude_pane_id, &vars) {
+        Ok(panes) => panes,
+        Err(e) => {
+            kill_claude();
+            return Err(e);
+        }
+    };
+    let mut tab = SessionTab {
+        tab_id: 0,
+        claude_pane_id,
+        panes,
+        wait_for: None,
+    };
 
-fn sanitize_session_name(s: &str) -> String {
-    // Single-pass: collapse non-alphanumeric runs into a single hyphen.
-    // Result is always pure ASCII, so byte-level truncation is safe.
-    let mut result = String::with_capacity(s.len());
-    let mut prev_hyphen = false;
+    if let Some(main) = bootstrap {
+        match start_bootstrap(ctx, &tab, &ccm_str, cwd, main) {
+            Ok(wait_for) => tab.wait_for = wait_for,
+            Err(e) => {
+                tab.kill(mux);
+                return Err(e);
+            }
+        }
+    }
 
-    for c in s.to_lowercase().chars() {
-        if c.is_ascii_alphanumeric() {
-            result.push(c);
-            prev_hyphen = false;
-        } else if !prev_hyphen {
-            result.push('-');
-            prev_hyphen = true;
+    // Send claude command to the claude pane (via PTY wrapper for OSC 0 detection)
+    if let Some(cmd) = claude_command {
+        let quoted_session = session_name.replace('\'', "'\\''");
+        let wrapped_cmd = format!(
+            "{} wrap --session '{}'{} -- {}\n",
+            ccm_str,
+            quoted_session,
+            wait_for_arg(tab.wait_for.as_deref()),
+            cmd.trim_end_matches('\n')
+        );
+        if let Err(e) = mux.send_text(claude_pane_id, &wrapped_cmd) {
+            tab.kill(mux);
+            return Err(e).context("failed to send claude command to pane");
         }
     }
 
-    let trimmed = result.trim_matches('-');
-    if trimmed.len() > 50 {
-        trimmed[..50].trim_end_matches('-').to_string()
-    } else {
-        trimmed.to_string()
+    // Set tab title
+    if let Err(e) = mux.set_tab_title(claude_pane_id, session_name) {
+        tab.kill(mux);
+        return Err(e).context("failed to set tab title");
     }
-}
 
-fn extract_name_from_first_line(content: &str) -> Option<String> {
-    let first_line = content
-        .lines()
-        .map(|l| l.trim())
-        .find(|l| !l.is_empty())?;
+    // Find the tab_id from the pane list
+    let tab_id = match mux.list_panes() {
+        Ok(panes) => panes
+            .iter()
+            .find(|p| p.pane_id == claude_pane_id)
+            .map(|p| p.tab_id),
+        Err(e) => {
+            tab.kill(mux);
+            return Err(e).context("failed to list panes");
+        }
+    };
+    let Some(tab_id) = tab_id else {
+        tab.kill(mux);
+        anyhow::bail!("could not find tab_id for pane {claude_pane_id}");
+    };
+    tab.tab_id = tab_id;
 
-    // Strip markdown heading prefix (e.g. "# Title" -> "Title")
-    let stripped = first_line.trim_start_matches('#').trim_start();
+    Ok(tab)
+}
 
-    let name = sanitize_session_name(stripped);
-    if name.is_empty() {
-        return None;
-    }
-    Some(name)
+/// Bootstrap the worktree `cwd` from `main`: in the tab's shell pane, so the
+/// user sees the progress, returning the status file claude has to wait for;
+/// or right here when the layout has no shell pane.
+fn start_bootstrap(
+    ctx: &Ctx,
+    tab: &SessionTab,
+    ccm: &str,
+    cwd: &str,
+    main: &Path,
+) -> Result<Option<String>> {
+    let Some(&shell_pane) = tab.panes.panes.get("shell") else {
+        let spec = bootstrap::Spec::load(main)?.unwrap_or_default();
+        bootstrap::run(&spec, main, Path::new(cwd))?;
+        return Ok(None);
+    };
+    let status = Path::new(cwd).join(bootstrap::STATUS_FILE);
+    // A leftover from an earlier session in the same directory
+    let _ = fs::remove_file(&status);
+    let quote = |s: &str| s.replace('\'', "'\\''");
+    let command = format!(
+        "'{}' bootstrap --cwd '{}' --main '{}'\n",
+        quote(ccm),
+        quote(cwd),
+        quote(&main.to_string_lossy())
+    );
+    ctx.mux
+        .send_text(shell_pane, &command)
+        .context("failed to send bootstrap command to the shell pane")?;
+    Ok(Some(status.to_string_lossy().into_owned()))
 }
 
-fn generate_session_name_from_plan(content: &str) -> String {
-    if let Some(name) = extract_name_from_first_line(content) {
-        if name.len() >= 3 {
-            return name;
-        }
+/// The `--wait-for` argument of `ccm wrap`, with its leading space.
+fn wait_for_arg(wait_for: Option<&str>) -> String {
+    match wait_for {
+        Some(path) => format!(" --wait-for '{}'", path.replace('\'', "'\\''")),
+        None => String::new(),
     }
+}
 
-    let now = Utc::now();
-    format!("plan-{}", now.format("%Y%m%d-%H%M%S"))
+/// `ccm bootstrap` (run in the shell pane of a new session): apply the main
+/// checkout's bootstrap spec to the worktree and record the outcome for the
+/// waiting claude pane.
+fn cmd_bootstrap(cwd: &str, main: &str) -> Result<()> {
+    let worktree = Path::new(cwd);
+    let result = bootstrap::Spec::load(Path::new(main))
+        .and_then(|spec| bootstrap::run(&spec.unwrap_or_default(), Path::new(main), worktree));
+    bootstrap::write_status(worktree, &result)?;
+    result?;
+    println!("==> bootstrap done");
+    Ok(())
 }
 
-fn save_plan_to_worktree(worktree_path: &str, content: &str) -> Result<()> {
-    let ccm_dir = PathBuf::from(worktree_path).join(".ccm");
-    let plans_dir = ccm_dir.join("plans");
+/// Block `ccm wrap` until the shell pane has finished bootstrapping.
+fn wait_for_bootstrap(status: &Path) {
+    if !status.exists() {
+        println!("Waiting for worktree bootstrap (see the shell pane)...");
+    }
+    if let Some(reason) = bootstrap::wait(status, bootstrap::WAIT_TIMEOUT) {
+        eprintln!("Warning: {reason}; starting claude anyway");
+    }
+}
 
-    fs::create_dir_all(&plans_dir).context("failed to create .ccm/plans directory")?;
+fn cmd_list(ctx: &Ctx, tags: &[String], repo: Option<&str>) -> Result<()> {
+    let state = state::load_from(&ctx.state_path)?;
+    let live_panes = ctx.mux.list_panes().unwrap_or_default();
+    let live_pane_ids: std::collections::HashSet<u64> =
+        live_panes.iter().map(|p| p.pane_id).collect();
 
-    // Ensure .ccm contents are gitignored
-    let gitignore = ccm_dir.join(".gitignore");
-    if !gitignore.exists() {
-        fs::write(&gitignore, "*\n").ok();
+    if state.sessions.is_empty() {
+        println!("No sessions.");
+        return Ok(());
+    }
+    let sessions: Vec<Session> = sessions_with_tags(&state.sessions, tags)
+        .into_iter()
+        .filter(|s| repo.is_none_or(|r| in_repo(s, r)))
+        .cloned()
+        .collect();
+    if sessions.is_empty() {
+        println!("No matching sessions.");
+        return Ok(());
     }
 
-    let plan_file = plans_dir.join("init.md");
-    fs::write(&plan_file, content).context("failed to write init.md")?;
+    // Headers only when sessions of several repositories are listed
+    let groups = session::group_by_repo(&sessions);
+    for group in &groups {
+        if groups.len() > 1 {
+            match group.repo {
+                Some(root) => println!("{}  ({root})", group.label(&sessions)),
+                None => println!("{}", group.label(&sessions)),
+            }
+        }
+        for session in group.sessions.iter().map(|&i| &sessions[i]) {
+            let is_active = state.active_session.as_deref() == Some(&session.name);
+            let active_mark = if is_active { " *" } else { "" };
+
+            let alive = session.pane_ids().any(|id| live_pane_ids.contains(&id));
+            let status = match (alive, session.dead_since) {
+                (true, _) => String::new(),
+                (false, Some(since)) => format!(" [dead {}]", format_age(Utc::now() - since)),
+                (false, None) => " [dead]".to_string(),
+            };
+
+            let claude_status = session
+                .claude_status
+                .as_deref()
+                .unwrap_or("");
+            let claude_info = if claude_status.is_empty() {
+                String::new()
+            } else {
+                format!(" [{claude_status}]")
+            };
+
+            let base_info = session
+                .base_ref
+                .as_deref()
+                .map(|base| format!(", from:{base}"))
+                .unwrap_or_default();
+
+            println!(
+                "  {}{active_mark}{status}{claude_info}  (tab:{}{base_info}, cwd:{})",
+                session.name, session.tab_id, session.cwd
+            );
+            if let Some(ref description) = session.description {
+                println!("      {description}");
+            }
+            if let Some(labels) = session.labels() {
+                println!("      {labels}");
+            }
+        }
+    }
 
     Ok(())
 }
 
-fn cmd_plan(config: &Config, cwd: Option<String>) -> Result<()> {
-    let plan_content = open_editor_for_plan().context("failed to capture plan content")?;
-
-    let trimmed = plan_content.trim();
-    if trimmed.is_empty() {
-        anyhow::bail!("plan content is empty. Session not created.");
+/// Whether `session` belongs to the repository `repo`, given as its root
+/// path or its directory name (`ccm list --repo backend`).
+fn in_repo(session: &Session, repo: &str) -> bool {
+    match session.repo.as_deref() {
+        Some(root) => session.repo_name() == Some(repo) || same_path(root, repo),
+        None => false,
     }
+}
 
-    let branch_suffix = generate_session_name_from_plan(&plan_content);
+/// Sessions carrying every one of `tags`.
+fn sessions_with_tags<'a>(sessions: &'a [Session], tags: &[String]) -> Vec<&'a Session> {
+    sessions
+        .iter()
+        .filter(|s| tags.iter().all(|t| s.has_tag(t)))
+        .collect()
+}
 
-    println!("Creating session with branch suffix '{}'...", branch_suffix);
+/// Apply `annotation` to the session `name` and return the updated session.
+fn annotate(ctx: &Ctx, name: &str, annotation: &Annotation) -> Result<Session, CcmError> {
+    let state = state::update_at(&ctx.state_path, |state| {
+        let session = state
+            .sessions
+            .iter_mut()
+            .find(|s| s.name == name)
+            .ok_or_else(|| CcmError::SessionNotFound(name.to_string()))?;
+        annotation.apply(session);
+        Ok(())
+    })?;
+    Ok(state
+        .sessions
+        .into_iter()
+        .find(|s| s.name == name)
+        .expect("annotated session is in state"))
+}
 
-    let info = cmd_new(config, Some(branch_suffix), cwd, None)?;
+/// `ccm annotate`: update the session's metadata, then print it.
+fn cmd_annotate(ctx: &Ctx, name: Option<String>, annotation: &Annotation) -> Result<()> {
+    let name = match name {
+        Some(n) => n,
+        None => resolve_session_from_cwd(ctx)?,
+    };
+    let session = if annotation.is_empty() {
+        let state = state::load_from(&ctx.state_path)?;
+        state
+            .sessions
+            .into_iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| CcmError::SessionNotFound(name.clone()))?
+    } else {
+        annotate(ctx, &name, annotation)?
+    };
+
+    println!("{}", session.name);
+    if let Some(ref description) = session.description {
+        println!("  {description}");
+    }
+    if let Some(labels) = session.labels() {
+        println!("  {labels}");
+    }
+    for note in &session.notes {
+        println!("  - {note}");
+    }
+    Ok(())
+}
+
+/// Open the diff review of a session, or print the plain diff when stdout
+/// is not a terminal (e.g. `ccm diff x | less`).
+fn cmd_diff(ctx: &Ctx, name: Option<String>) -> Result<()> {
+    use std::io::IsTerminal;
+
+    let name = match name {
+        Some(n) => n,
+        None => resolve_session_from_cwd(ctx)?,
+    };
+    let state = state::load_from(&ctx.state_path)?;
+    let session = state
+        .sessions
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| CcmError::SessionNotFound(name.clone()))?;
+
+    if !std::io::stdout().is_terminal() {
+        println!("{}", diff::session_diff_text(session)?);
+        return Ok(());
+    }
+    let view = tui::DiffView::for_session(session)
+        .with_context(|| format!("failed to diff session '{name}'"))?;
+    tui::run_diff(view)
+}
+
+/// Print every pair of sessions whose changes (against their base) touch the
+/// same files, so that collisions show up before `close --merge`.
+fn cmd_conflicts(ctx: &Ctx) -> Result<Vec<overlap::Overlap>> {
+    let state = state::load_from(&ctx.state_path)?;
+    let (changes, failed) = overlap::collect(&state.sessions);
+    for (name, e) in &failed {
+        eprintln!("warning: skipped session '{name}': {e}");
+    }
+
+    let overlaps = overlap::find_overlaps(&changes);
+    if overlaps.is_empty() {
+        println!("No overlapping changes between {} session(s).", changes.len());
+    }
+    for o in &overlaps {
+        let (a, b) = &o.sessions;
+        println!("  {a} <-> {b}  ({} file(s))", o.files.len());
+        for file in &o.files {
+            println!("      {file}");
+        }
+    }
+    Ok(overlaps)
+}
+
+fn cmd_history(
+    ctx: &Ctx,
+    session: Option<String>,
+    since: Option<&str>,
+    until: Option<&str>,
+) -> Result<()> {
+    let now = Utc::now();
+    let filter = journal::Filter {
+        since: since.map(|s| journal::parse_time(s, now)).transpose()?,
+        until: until.map(|s| journal::parse_time(s, now)).transpose()?,
+        session,
+        branch_prefix: ctx.worktrees.branch_prefix().map(str::to_string),
+    };
+    let events = journal::read(&journal::path_for(&ctx.state_path), &filter)?;
+    if events.is_empty() {
+        println!("No events.");
+        return Ok(());
+    }
+    for event in &events {
+        let at = event.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S");
+        if filter.session.is_some() {
+            println!("{at}  {}", event.kind);
+        } else {
+            println!("{at}  {}  {}", event.session, event.kind);
+        }
+    }
+    Ok(())
+}
+
+/// Append `events` to the journal. A session command that did its job does
+/// not fail over the journal, so errors are only reported.
+fn record(ctx: &Ctx, events: &[journal::Event]) {
+    if let Err(e) = journal::append(&journal::path_for(&ctx.state_path), events) {
+        eprintln!("Warning: failed to record history: {e}");
+    }
+}
+
+fn cmd_archive_list(ctx: &Ctx) -> Result<()> {
+    let entries = archive::list(&archive::dir_for(&ctx.state_path))?;
+    if entries.is_empty() {
+        println!("No archived sessions.");
+        return Ok(());
+    }
+    for entry in entries.iter().rev() {
+        let closed = entry.closed_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M");
+        let outcome = if entry.merged { "merged" } else { "closed" };
+        let age = format_age(entry.duration());
+        let labels = entry
+            .session
+            .labels()
+            .map(|l| format!("  {l}"))
+            .unwrap_or_default();
+        println!("{closed}  {}  {outcome} after {age}{labels}", entry.session.name);
+        if let Some(ref description) = entry.session.description {
+            println!("    {description}");
+        }
+    }
+    Ok(())
+}
+
+fn cmd_archive_show(ctx: &Ctx, name: &str) -> Result<()> {
+    let prefix = ctx.worktrees.branch_prefix();
+    let entry = archive::find(&archive::dir_for(&ctx.state_path), name, prefix)?;
+    let session = &entry.session;
+    let local = |t: chrono::DateTime<Utc>| {
+        t.with_timezone(&chrono::Local)
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    };
+
+    println!("{}", session.name);
+    if let Some(ref description) = session.description {
+        println!("  {description}");
+    }
+    if let Some(labels) = session.labels() {
+        println!("  {labels}");
+    }
+    println!("  repository: {}", session.repo.as_deref().unwrap_or("(unknown)"));
+    let branch = entry.branch.as_deref().unwrap_or("(none)");
+    match session.base_ref {
+        Some(ref base) => println!("  branch:     {branch} (from {base})"),
+        None => println!("  branch:     {branch}"),
+    }
+    println!("  created:    {}", local(session.created_at));
+    println!(
+        "  closed:     {} ({} after {})",
+        local(entry.closed_at),
+        if entry.merged { "merged" } else { "not merged" },
+        format_age(entry.duration())
+    );
+    if let Some(ref status) = session.claude_status {
+        println!("  status:     {status}");
+    }
+    for note in &session.notes {
+        println!("  - {note}");
+    }
+    if !entry.plans.is_empty() {
+        println!("  plans:");
+        for plan in &entry.plans {
+            println!("    {}", entry.plan_path(plan).display());
+        }
+    }
+    Ok(())
+}
+
+/// Recreate the most recently archived session named `name` on its branch,
+/// with its description, tags, ticket, notes and plans. A branch deleted by
+/// the close is recreated at the commit it was on; its worktree is reused, or
+/// created as `ccm adopt` does.
+fn cmd_reopen(ctx: &Ctx, name: &str) -> Result<()> {
+    let archive_dir = archive::dir_for(&ctx.state_path);
+    let entry = archive::find(&archive_dir, name, ctx.worktrees.branch_prefix())?;
+    let old = &entry.session;
+    let branch = entry
+        .branch
+        .as_deref()
+        .with_context(|| format!("'{}' was not on a branch when it was closed", old.name))?;
+    let repo = old
+        .repo
+        .as_deref()
+        .filter(|r| Path::new(r).is_dir())
+        .with_context(|| format!("the repository of '{}' is not available", old.name))?;
+
+    let state = state::load_from(&ctx.state_path)?;
+    if state.sessions.iter().any(|s| s.name == old.name) {
+        return Err(CcmError::SessionExists(old.name.clone()).into());
+    }
+    // A close without merge deletes the branch; recreate it at the commit it
+    // was on, as long as git has not pruned that yet
+    let restored_branch = !git::branch_exists(repo, branch)?
+        && entry
+            .head
+            .as_deref()
+            .is_some_and(|head| git::run(repo, &["branch", branch, head]).is_ok());
+    let had_worktree = git::worktree_for_branch(repo, branch)?.is_some();
+    let worktree_path = branch_worktree(ctx, repo, branch)?
+        .with_context(|| format!("branch '{branch}' of '{}' no longer exists", old.name))?;
+    // Undo the above if reopening fails past this point
+    let remove_worktree = || {
+        if !had_worktree {
+            let _ = git::run(repo, &["worktree", "remove", "--force", &worktree_path]);
+        }
+        if restored_branch {
+            let _ = git::run(repo, &["branch", "-D", branch]);
+        }
+    };
+
+    let tab = restore_plans(&entry, &worktree_path).and_then(|()| {
+        let claude_command = ctx.config.wezterm.claude_command.clone();
+        open_session_tab(ctx, &old.name, &worktree_path, Some(&claude_command), None)
+    });
+    let tab = match tab {
+        Ok(tab) => tab,
+        Err(e) => {
+            remove_worktree();
+            return Err(e);
+        }
+    };
+    let session = Session {
+        name: old.name.clone(),
+        tab_id: tab.tab_id,
+        claude_pane_id: tab.claude_pane_id,
+        panes: tab.panes.panes.clone(),
+        aux_panes: tab.panes.aux_panes.clone(),
+        cwd: worktree_path.clone(),
+        base_ref: old.base_ref.clone(),
+        created_at: old.created_at,
+        claude_status: None,
+        dead_since: None,
+        adopted: old.adopted,
+        description: old.description.clone(),
+        tags: old.tags.clone(),
+        ticket: old.ticket.clone(),
+        notes: old.notes.clone(),
+        repo: Some(repo.to_string()),
+    };
+    if let Err(e) = insert_session(ctx, &session) {
+        tab.kill(ctx.mux);
+        remove_worktree();
+        return Err(e.into());
+    }
+    let reopened = EventKind::Reopened {
+        cwd: worktree_path.clone(),
+    };
+    record(ctx, &[Event::new(&session.name, reopened)]);
+    if let Err(e) = archive::remove(&entry) {
+        eprintln!("Warning: failed to remove '{}' from the archive: {e}", session.name);
+    }
+
+    if let Err(e) = ctx.mux.activate_pane(tab.claude_pane_id) {
+        eprintln!("Warning: failed to activate claude pane: {e}");
+    }
+    println!(
+        "Reopened session '{}' in {worktree_path} (tab {})",
+        session.name, tab.tab_id
+    );
+    Ok(())
+}
+
+/// Copy the archived plans of `entry` back into `worktree_path`, leaving any
+/// that are already there alone.
+fn restore_plans(entry: &archive::Entry, worktree_path: &str) -> Result<()> {
+    let plans_dir = Path::new(worktree_path).join(archive::PLANS_DIR);
+    for plan in &entry.plans {
+        let dst = plans_dir.join(plan);
+        if !dst.exists() {
+            fs::create_dir_all(&plans_dir)?;
+            fs::copy(entry.plan_path(plan), dst)?;
+        }
+    }
+    Ok(())
+}
+
+/// Short human-readable age such as `45s`, `12m`, `3h` or `2d`.
+fn format_age(age: chrono::Duration) -> String {
+    let secs = age.num_seconds().max(0);
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+fn cmd_switch(ctx: &Ctx, name: &str) -> Result<()> {
+    // Read state under lock, validate session exists, update active, then activate tab
+    let tab_id = state::update_at(&ctx.state_path, |state| {
+        if !state.sessions.iter().any(|s| s.name == name) {
+            return Err(CcmError::SessionNotFound(name.to_string()));
+        }
+        state.active_session = Some(name.to_string());
+        Ok(())
+    })?
+    .sessions
+    .iter()
+    .find(|s| s.name == name)
+    .map(|s| s.tab_id)
+    .expect("session was just validated to exist");
+
+    ctx.mux.activate_tab(tab_id).context("failed to activate tab")?;
+
+    println!("Switched to '{name}'");
+    Ok(())
+}
+
+fn get_editor() -> String {
+    env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vim".to_string())
+}
+
+fn open_editor_for_plan() -> Result<String> {
+    use std::io::Read;
+
+    let tmp_dir = env::temp_dir();
+    let tmp_path = tmp_dir.join(format!(
+        "ccm-plan-{}-{}.md",
+        std::process::id(),
+        Utc::now().timestamp_nanos_opt().unwrap_or(0)
+    ));
+
+    // Use create_new (O_EXCL) to prevent symlink attacks on predictable paths
+    fs::File::create_new(&tmp_path).context("failed to create temporary plan file")?;
+
+    let editor = get_editor();
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vim");
+    let editor_args: Vec<&str> = parts.collect();
+
+    let status = std::process::Command::new(program)
+        .args(editor_args)
+        .arg(&tmp_path)
+        .status()
+        .context(format!("failed to launch editor '{}'", editor))?;
+
+    if !status.success() {
+        fs::remove_file(&tmp_path).ok();
+        anyhow::bail!("editor exited with non-zero status");
+    }
+
+    let mut content = String::new();
+    fs::File::open(&tmp_path)
+        .context("failed to open plan file after editing")?
+        .read_to_string(&mut content)
+        .context("failed to read plan file")?;
+
+    fs::remove_file(&tmp_path).ok();
+
+    Ok(content)
+}
+
+const MAX_SLUG_WIDTH: usize = 50;
+
+/// Slug of a plan title for the session name (see `SlugMode`), at most
+/// `MAX_SLUG_WIDTH` terminal columns wide.
+fn sanitize_session_name(s: &str, mode: SlugMode) -> String {
+    let text = match mode {
+        SlugMode::Transliterate => deunicode::deunicode(s),
+        SlugMode::Ascii | SlugMode::Unicode => s.to_string(),
+    };
+    let keep = |c: char| match mode {
+        SlugMode::Unicode => c.is_alphanumeric(),
+        SlugMode::Ascii | SlugMode::Transliterate => c.is_ascii_alphanumeric(),
+    };
+
+    // Single-pass: collapse runs of other characters into a single hyphen.
+    let mut result = String::with_capacity(text.len());
+    let mut prev_hyphen = false;
+    for c in text.to_lowercase().chars() {
+        if keep(c) {
+            result.push(c);
+            prev_hyphen = false;
+        } else if !prev_hyphen {
+            result.push('-');
+            prev_hyphen = true;
+        }
+    }
+
+    // Truncate by display width so CJK names stay readable in the sidebar
+    let mut width = 0;
+    let truncated: String = result
+        .trim_matches('-')
+        .chars()
+        .take_while(|&c| {
+            width += unicode_width::UnicodeWidthChar::width(c).unwrap_or(0);
+            width <= MAX_SLUG_WIDTH
+        })
+        .collect();
+    truncated.trim_end_matches('-').to_string()
+}
+
+fn extract_name_from_first_line(content: &str, mode: SlugMode) -> Option<String> {
+    let first_line = content
+        .lines()
+        .map(|l| l.trim())
+        .find(|l| !l.is_empty())?;
+
+    // Strip markdown heading prefix (e.g. "# Title" -> "Title")
+    let stripped = first_line.trim_start_matches('#').trim_start();
+
+    let name = sanitize_session_name(stripped, mode);
+    if name.is_empty() {
+        return None;
+    }
+    Some(name)
+}
+
+fn generate_session_name_from_plan(content: &str, mode: SlugMode) -> String {
+    if let Some(name) = extract_name_from_first_line(content, mode) {
+        if unicode_width::UnicodeWidthStr::width(name.as_str()) >= 3 {
+            return name;
+        }
+    }
+
+    let now = Utc::now();
+    format!("plan-{}", now.format("%Y%m%d-%H%M%S"))
+}
+
+fn save_plan_to_worktree(worktree_path: &str, content: &str) -> Result<()> {
+    let ccm_dir = PathBuf::from(worktree_path).join(".ccm");
+    let plans_dir = ccm_dir.join("plans");
+
+    fs::create_dir_all(&plans_dir).context("failed to create .ccm/plans directory")?;
+
+    // Ensure .ccm contents are gitignored
+    let gitignore = ccm_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n").ok();
+    }
+
+    let plan_file = plans_dir.join("init.md");
+    fs::write(&plan_file, content).context("failed to write init.md")?;
+
+    Ok(())
+}
+
+fn cmd_plan(ctx: &Ctx, cwd: Option<String>, from: Option<String>) -> Result<()> {
+    let plan_content = open_editor_for_plan().context("failed to capture plan content")?;
+
+    let trimmed = plan_content.trim();
+    if trimmed.is_empty() {
+        anyhow::bail!("plan content is empty. Session not created.");
+    }
+
+    let branch_suffix = generate_session_name_from_plan(&plan_content, ctx.config.naming.slug);
+
+    println!("Creating session with branch suffix '{}'...", branch_suffix);
+
+    let ticket = naming::find_ticket(&ctx.config.naming.ticket_pattern, &plan_content)?;
+    let info = cmd_new(ctx, Some(branch_suffix), ticket.as_deref(), cwd, from, None)?;
 
     save_plan_to_worktree(&info.worktree_path, &plan_content)
         .context("failed to save plan to worktree")?;
+    if let Some(ticket) = ticket {
+        let annotation = Annotation {
+            ticket: Some(ticket),
+            ..Annotation::default()
+        };
+        annotate(ctx, &info.session_name, &annotation)?;
+    }
 
     let ccm_path = env::current_exe().context("failed to get ccm executable path")?;
     let ccm_str = ccm_path.to_string_lossy().to_string();
@@ -440,13 +1239,15 @@ fn cmd_plan(config: &Config, cwd: Option<String>) -> Result<()> {
     let plan_path_str = plan_path.to_string_lossy();
     let quoted_plan_path = plan_path_str.replace('\'', "'\\''");
     let claude_cmd = format!(
-        "{} wrap --session '{}' --prompt-file '{}' -- {} --settings '{{\"plansDirectory\": \".ccm/plans/\"}}' --permission-mode=plan\n",
+        "{} wrap --session '{}' --prompt-file '{}'{} -- {} --settings '{{\"plansDirectory\": \".ccm/plans/\"}}' --permission-mode=plan\n",
         ccm_str,
         quoted_session,
         quoted_plan_path,
-        config.wezterm.claude_command.trim_end_matches('\n')
+        wait_for_arg(info.wait_for.as_deref()),
+        ctx.config.wezterm.claude_command.trim_end_matches('\n')
     );
-    wezterm::send_text(&config.wezterm.binary, info.claude_pane_id, &claude_cmd)
+    ctx.mux
+        .send_text(info.claude_pane_id, &claude_cmd)
         .context("failed to send claude plan command to pane")?;
 
     println!("Plan saved to .ccm/plans/init.md");
@@ -454,17 +1255,15 @@ fn cmd_plan(config: &Config, cwd: Option<String>) -> Result<()> {
     Ok(())
 }
 
-fn cmd_reset_layout(config: &Config) -> Result<()> {
-    let binary = &config.wezterm.binary;
+fn cmd_reset_layout(ctx: &Ctx) -> Result<()> {
+    let config = ctx.config;
+    let mux = ctx.mux;
 
     // 1. Get current pane ID from environment
-    let current_pane_id: u64 = env::var("WEZTERM_PANE")
-        .context("WEZTERM_PANE environment variable not set (must run inside a WezTerm pane)")?
-        .parse()
-        .context("WEZTERM_PANE is not a valid pane ID")?;
+    let current_pane_id = mux.current_pane_id()?;
 
     // 2. Find the session that owns this pane
-    let state = state::load()?;
+    let state = state::load_from(&ctx.state_path)?;
     let session = Session::find_by_pane_id(&state.sessions, current_pane_id)
         .ok_or_else(|| anyhow::anyhow!(
             "no session found containing pane {current_pane_id}"
@@ -472,12 +1271,14 @@ fn cmd_reset_layout(config: &Config) -> Result<()> {
 
     let session_name = session.name.clone();
     let claude_pane_id = session.claude_pane_id;
-    let watcher_pane_id = session.watcher_pane_id;
-    let shell_pane_id = session.shell_pane_id;
+    let layout_panes: Vec<u64> = session
+        .pane_ids()
+        .filter(|&id| id != claude_pane_id)
+        .collect();
     let session_cwd = session.cwd.clone();
 
     // 3. Verify claude pane is alive (it's the layout anchor)
-    let live_panes = wezterm::list_panes(binary)?;
+    let live_panes = mux.list_panes()?;
     if !live_panes.iter().any(|p| p.pane_id == claude_pane_id) {
         anyhow::bail!(
             "claude pane ({claude_pane_id}) is not alive; cannot reset layout"
@@ -495,7 +1296,7 @@ fn cmd_reset_layout(config: &Config) -> Result<()> {
     }
 
     // 5. Kill all panes except claude (self last)
-    let mut others: Vec<u64> = [watcher_pane_id, shell_pane_id]
+    let mut others: Vec<u64> = layout_panes
         .iter()
         .copied()
         .filter(|&id| id != claude_pane_id && id != current_pane_id)
@@ -518,71 +1319,43 @@ fn cmd_reset_layout(config: &Config) -> Result<()> {
     }
 
     for &id in &others {
-        let _ = wezterm::kill_pane(binary, id);
+        let _ = mux.kill_pane(id);
     }
 
     // Kill own pane last (if not claude)
     if current_pane_id != claude_pane_id {
-        let _ = wezterm::kill_pane(binary, current_pane_id);
+        let _ = mux.kill_pane(current_pane_id);
         std::thread::sleep(Duration::from_millis(300));
     }
 
     // 6. Rebuild layout from claude pane
     let ccm_path = env::current_exe().context("failed to get ccm executable path")?;
     let ccm_str = ccm_path.to_string_lossy().to_string();
-
-    let new_watcher = wezterm::split_pane(
-        binary,
-        claude_pane_id,
-        wezterm::SplitDirection::Left,
-        config.layout.watcher_width,
-        Some(&[&ccm_str, "tab-watcher", "--session", &session_name]),
-    )
-    .context("failed to create watcher pane")?;
-
-    let new_shell = wezterm::split_pane(
-        binary,
-        claude_pane_id,
-        wezterm::SplitDirection::Bottom,
-        config.layout.shell_height,
-        None,
-    )
-    .context("failed to create shell pane")?;
-
-    // 6b. Split right for plans viewer (best-effort: failure doesn't abort reset)
-    let new_plans = match wezterm::split_pane(
-        binary,
-        claude_pane_id,
-        wezterm::SplitDirection::Right,
-        config.layout.plans_width,
-        Some(&[&ccm_str, "plan-viewer", "--cwd", &session_cwd]),
-    ) {
-        Ok(id) => Some(id),
-        Err(e) => {
-            eprintln!("Warning: failed to create plans viewer pane: {e}");
-            None
-        }
+    let vars = LayoutVars {
+        ccm: &ccm_str,
+        session: &session_name,
+        cwd: &session_cwd,
     };
+    let built = layout::build_session(mux, config, claude_pane_id, &vars)?;
 
     // 7. Set tab title
-    wezterm::set_tab_title(binary, new_watcher, &session_name)
+    mux.set_tab_title(claude_pane_id, &session_name)
         .context("failed to set tab title")?;
 
     // 8. Update state with new pane IDs
-    state::update(|state| {
+    state::update_at(&ctx.state_path, |state| {
         let s = state
             .sessions
             .iter_mut()
             .find(|s| s.name == session_name)
             .ok_or_else(|| CcmError::SessionNotFound(session_name.clone()))?;
-        s.watcher_pane_id = new_watcher;
-        s.shell_pane_id = new_shell;
-        s.plans_pane_id = new_plans;
+        s.panes = built.panes;
+        s.aux_panes = built.aux_panes;
         Ok(())
     })?;
 
     // 9. Activate claude pane
-    let _ = wezterm::activate_pane(binary, claude_pane_id);
+    let _ = mux.activate_pane(claude_pane_id);
 
     // stdout may be gone if we killed our own pane
     use std::io::Write;
@@ -590,29 +1363,309 @@ fn cmd_reset_layout(config: &Config) -> Result<()> {
     Ok(())
 }
 
-fn cmd_close(config: &Config, name: Option<String>, merge: bool) -> Result<()> {
-    let name = match name {
-        Some(n) => n,
-        None => resolve_session_from_cwd()?,
+/// Reopen dead sessions: respawn the tab and layout in the session's worktree,
+/// relaunch claude and record the new pane IDs. Unless `fresh`, claude is
+/// started with `--continue` to resume the last conversation in the worktree.
+fn cmd_restore(ctx: &Ctx, name: Option<String>, all: bool, fresh: bool) -> Result<()> {
+    let mux = ctx.mux;
+    let state = state::load_from(&ctx.state_path)?;
+    // Without a pane list every session would look dead
+    let live_pane_ids: std::collections::HashSet<u64> = mux
+        .list_panes()
+        .context("failed to list panes")?
+        .iter()
+        .map(|p| p.pane_id)
+        .collect();
+    let is_dead = |s: &Session| !s.pane_ids().any(|id| live_pane_ids.contains(&id));
+    let cwd_exists = |s: &Session| std::path::Path::new(&s.cwd).is_dir();
+
+    let targets: Vec<&Session> = match &name {
+        Some(name) => {
+            let session = state
+                .sessions
+                .iter()
+                .find(|s| &s.name == name)
+                .ok_or_else(|| CcmError::SessionNotFound(name.clone()))?;
+            if !is_dead(session) {
+                anyhow::bail!("session '{name}' is still running");
+            }
+            if !cwd_exists(session) {
+                anyhow::bail!(
+                    "cannot restore session '{name}': worktree {} no longer exists",
+                    session.cwd
+                );
+            }
+            vec![session]
+        }
+        None => {
+            debug_assert!(all);
+            let mut targets = Vec::new();
+            for session in state.sessions.iter().filter(|s| is_dead(s)) {
+                if cwd_exists(session) {
+                    targets.push(session);
+                } else {
+                    eprintln!(
+                        "Skipping '{}': worktree {} no longer exists",
+                        session.name, session.cwd
+                    );
+                }
+            }
+            targets
+        }
     };
-    let binary = &config.wezterm.binary;
 
-    // If merging, attempt merge BEFORE destroying session state.
-    // This way, on merge failure the session remains intact for the user to investigate.
-    if merge {
-        let state = state::load()?;
-        let session = state
-            .sessions
-            .iter()
-            .find(|s| s.name == name)
-            .ok_or_else(|| CcmError::SessionNotFound(name.to_string()))?;
-        gj::exit_worktree(&session.cwd, true)
-            .context("failed to merge and clean up worktree")?;
+    if targets.is_empty() {
+        println!("No sessions to restore.");
+        return Ok(());
     }
 
-    // Remove session from state atomically under lock
+    let claude_command = ctx.config.wezterm.claude_command.trim_end_matches('\n');
+    let claude_command = if fresh {
+        claude_command.to_string()
+    } else {
+        format!("{claude_command} --continue")
+    };
+
+    let mut failed = 0;
+    let mut last_tab = None;
+    for session in targets {
+        match restore_session(ctx, session, &claude_command) {
+            Ok(tab_id) => {
+                println!("Restored session '{}' (tab {tab_id})", session.name);
+                last_tab = Some(tab_id);
+            }
+            Err(e) => {
+                eprintln!("Failed to restore session '{}': {e:#}", session.name);
+                failed += 1;
+            }
+        }
+    }
+
+    if name.is_some() {
+        if let Some(tab_id) = last_tab {
+            let _ = mux.activate_tab(tab_id);
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{failed} session(s) could not be restored");
+    }
+    Ok(())
+}
+
+/// Reopen one session and store its new tab and pane IDs. Returns the tab_id.
+fn restore_session(ctx: &Ctx, session: &Session, claude_command: &str) -> Result<u64> {
+    let tab = open_session_tab(ctx, &session.name, &session.cwd, Some(claude_command), None)?;
+
+    let result = state::update_at(&ctx.state_path, |state| {
+        let s = state
+            .sessions
+            .iter_mut()
+            .find(|s| s.name == session.name)
+            .ok_or_else(|| CcmError::SessionNotFound(session.name.clone()))?;
+        s.tab_id = tab.tab_id;
+        s.claude_pane_id = tab.claude_pane_id;
+        s.panes = tab.panes.panes.clone();
+        s.aux_panes = tab.panes.aux_panes.clone();
+        s.claude_status = None;
+        s.dead_since = None;
+        Ok(())
+    });
+    if let Err(e) = result {
+        tab.kill(ctx.mux);
+        return Err(e.into());
+    }
+    Ok(tab.tab_id)
+}
+
+/// Remove every session that has no live panes, regardless of the grace
+/// period. With `remove_worktrees`, their worktrees are discarded as well.
+fn cmd_prune(ctx: &Ctx, remove_worktrees: bool) -> Result<()> {
+    // Without a pane list every session would look dead
+    let live_pane_ids: std::collections::HashSet<u64> = ctx
+        .mux
+        .list_panes()
+        .context("failed to list panes")?
+        .iter()
+        .map(|p| p.pane_id)
+        .collect();
+
+    let mut pruned: Vec<Session> = Vec::new();
+    state::update_at(&ctx.state_path, |state| {
+        let (dead, alive): (Vec<Session>, Vec<Session>) = std::mem::take(&mut state.sessions)
+            .into_iter()
+            .partition(|s| !s.pane_ids().any(|id| live_pane_ids.contains(&id)));
+        state.sessions = alive;
+        if let Some(ref active) = state.active_session {
+            if dead.iter().any(|s| &s.name == active) {
+                state.active_session = None;
+            }
+        }
+        pruned = dead;
+        Ok(())
+    })?;
+
+    if pruned.is_empty() {
+        println!("No dead sessions.");
+        return Ok(());
+    }
+
+    for session in &pruned {
+        if remove_worktrees && session.adopted {
+            println!("Kept worktree {} of adopted session '{}'", session.cwd, session.name);
+        } else if remove_worktrees && std::path::Path::new(&session.cwd).is_dir() {
+            if let Err(e) = ctx.worktrees.exit_worktree(&session.cwd, false) {
+                eprintln!("Warning: failed to remove worktree {}: {e}", session.cwd);
+            }
+        }
+        println!("Pruned session '{}'", session.name);
+    }
+    Ok(())
+}
+
+/// List the provider's worktrees and branches that no session refers to and
+/// remove them, asking for each one unless `yes` is set. Worktrees are
+/// removed through the worktree provider, bare branches with `git branch -D`.
+/// Returns the number of orphans removed.
+fn cmd_gc(
+    ctx: &Ctx,
+    cwd: Option<String>,
+    yes: bool,
+    input: &mut impl std::io::BufRead,
+) -> Result<usize> {
+    let prefix = ctx.worktrees.branch_prefix().ok_or_else(|| {
+        anyhow::anyhow!("ccm gc only supports git worktree providers (gj or git)")
+    })?;
+    let repo = match cwd {
+        Some(p) => p,
+        None => env::current_dir()
+            .context("failed to get current directory")?
+            .to_string_lossy()
+            .to_string(),
+    };
+    let state = state::load_from(&ctx.state_path)?;
+    let orphans = gc::find_orphans(&repo, prefix, &state.sessions)?;
+    if orphans.is_empty() {
+        println!("No orphaned worktrees or branches.");
+        return Ok(0);
+    }
+
+    let now = Utc::now();
+    let age = |o: &gc::Orphan| {
+        o.since()
+            .map(|t| format_age(now - t))
+            .unwrap_or_else(|| "?".to_string())
+    };
+    for orphan in &orphans {
+        match orphan {
+            gc::Orphan::Worktree {
+                path, branch, size, ..
+            } => {
+                let size = size.map(gc::format_size).unwrap_or_else(|| "missing".to_string());
+                println!("  worktree  {path} ({branch})  age:{}  size:{size}", age(orphan));
+            }
+            gc::Orphan::Branch { name, .. } => {
+                println!("  branch    {name}  age:{}", age(orphan));
+            }
+        }
+    }
+
+    let mut removed = 0;
+    for orphan in &orphans {
+        let what = match orphan {
+            gc::Orphan::Worktree { path, .. } => format!("worktree {path}"),
+            gc::Orphan::Branch { name, .. } => format!("branch {name}"),
+        };
+        if !yes && !confirm(input, &format!("Remove {what}?"))? {
+            continue;
+        }
+        let result = match orphan {
+            gc::Orphan::Worktree { path, .. } if Path::new(path).is_dir() => {
+                ctx.worktrees.exit_worktree(path, false)
+            }
+            // The directory is gone; drop git's record of it and the branch
+            gc::Orphan::Worktree { branch, .. } => git::run(&repo, &["worktree", "prune"])
+                .and_then(|_| git::run(&repo, &["branch", "-D", branch]))
+                .map(|_| ()),
+            gc::Orphan::Branch { name, .. } => {
+                git::run(&repo, &["branch", "-D", name]).map(|_| ())
+            }
+        };
+        match result {
+            Ok(()) => {
+                println!("Removed {what}");
+                removed += 1;
+            }
+            Err(e) => eprintln!("Warning: failed to remove {what}: {e}"),
+        }
+    }
+    println!("Removed {removed} of {} orphan(s).", orphans.len());
+    Ok(removed)
+}
+
+/// Ask a yes/no question on stdout and read the answer from `input`.
+/// Anything but `y`/`yes` (including end of input) is a no.
+fn confirm(input: &mut impl std::io::BufRead, question: &str) -> Result<bool> {
+    use std::io::Write;
+
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+fn cmd_close(
+    ctx: &Ctx,
+    name: Option<String>,
+    merge: bool,
+    remove_worktree: bool,
+    skip_checks: bool,
+) -> Result<()> {
+    let mux = ctx.mux;
+    let name = match name {
+        Some(n) => n,
+        None => resolve_session_from_cwd(ctx)?,
+    };
+
+    let state = state::load_from(&ctx.state_path)?;
+    let session = state
+        .sessions
+        .iter()
+        .find(|s| s.name == name)
+        .ok_or_else(|| CcmError::SessionNotFound(name.to_string()))?;
+    // Adopted worktrees predate the session, so they are only removed on request
+    let remove_worktree = remove_worktree || !session.adopted;
+    if merge && !remove_worktree {
+        anyhow::bail!(
+            "'{name}' is an adopted session and merging removes its worktree; \
+             pass --remove-worktree as well"
+        );
+    }
+
+    // Plans and branch go to the archive; read them while the worktree exists
+    let capture = archive::Capture::of(session);
+
+    // If merging, verify and attempt merge BEFORE destroying session state.
+    // This way, on failure the session remains intact for the user to investigate.
+    if merge {
+        let checks = &ctx.config.merge.checks;
+        if skip_checks {
+            if !checks.is_empty() {
+                println!("Skipping {} merge check(s)", checks.len());
+            }
+        } else {
+            checks::run(checks, &session.cwd, checks::CheckOutput::Inherit).with_context(|| {
+                format!("merge aborted, session '{name}' kept (use --skip-checks to merge anyway)")
+            })?;
+        }
+        ctx.worktrees
+            .exit_worktree(&session.cwd, true)
+            .context("failed to merge and clean up worktree")?;
+    }
+
+    // Remove session from state atomically under lock
     let mut removed_session = None;
-    state::update(|state| {
+    state::update_at(&ctx.state_path, |state| {
         let idx = state
             .sessions
             .iter()
@@ -626,24 +1679,56 @@ fn cmd_close(config: &Config, name: Option<String>, merge: bool) -> Result<()> {
     })?;
 
     let session = removed_session.expect("session was just removed in update closure");
+    record(ctx, &[Event::new(&name, EventKind::Closed { merged: merge })]);
+    if let Err(e) = capture.save(&archive::dir_for(&ctx.state_path), session.clone(), merge) {
+        eprintln!("Warning: failed to archive session '{name}': {e}");
+    }
 
     // Kill all panes (ignore errors for already-dead panes)
-    let _ = wezterm::kill_pane(binary, session.watcher_pane_id);
-    let _ = wezterm::kill_pane(binary, session.shell_pane_id);
-    let _ = wezterm::kill_pane(binary, session.claude_pane_id);
-    if let Some(plans_pane_id) = session.plans_pane_id {
-        let _ = wezterm::kill_pane(binary, plans_pane_id);
-    }
+    layout::kill_session_panes(mux, &session);
 
     // Clean up git worktree (best-effort for non-merge path)
-    if !merge {
-        let _ = gj::exit_worktree(&session.cwd, false);
+    if !merge && remove_worktree {
+        let _ = ctx.worktrees.exit_worktree(&session.cwd, false);
     }
 
-    println!("Closed session '{name}'");
+    if remove_worktree {
+        println!("Closed session '{name}'");
+    } else {
+        println!("Closed session '{name}' (kept worktree {})", session.cwd);
+    }
     Ok(())
 }
 
+/// Resolve session name from the current working directory by matching against known sessions.
+/// Uses canonicalized paths and picks the longest (most specific) match.
+fn resolve_session_from_cwd(ctx: &Ctx) -> Result<String> {
+    let cwd = env::current_dir()
+        .context("failed to get current directory")?
+        .canonicalize()
+        .context("failed to canonicalize current directory")?;
+
+    let state = state::load_from(&ctx.state_path)?;
+    let best = state
+        .sessions
+        .iter()
+        .filter(|s| {
+            std::path::Path::new(&s.cwd)
+                .canonicalize()
+                .map(|p| cwd.starts_with(&p))
+                .unwrap_or(false)
+        })
+        .max_by_key(|s| s.cwd.len());
+
+    match best {
+        Some(session) => Ok(session.name.clone()),
+        None => Err(anyhow::anyhow!(
+            "no session found for current directory '{}'. Specify a session name explicitly.",
+            cwd.display()
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -651,33 +1736,89 @@ mod tests {
     #[test]
     fn test_sanitize_basic() {
         assert_eq!(
-            sanitize_session_name("Implement User Auth"),
+            sanitize_session_name("Implement User Auth", SlugMode::Ascii),
             "implement-user-auth"
         );
     }
 
     #[test]
     fn test_sanitize_special_chars() {
-        assert_eq!(sanitize_session_name("Fix bug #123!"), "fix-bug-123");
+        assert_eq!(sanitize_session_name("Fix bug #123!", SlugMode::Ascii), "fix-bug-123");
     }
 
     #[test]
     fn test_sanitize_unicode() {
         assert_eq!(
-            sanitize_session_name("API redesign 🚀"),
+            sanitize_session_name("API redesign 🚀", SlugMode::Ascii),
             "api-redesign"
         );
     }
 
+    #[test]
+    fn test_sanitize_unicode_mode_keeps_other_scripts() {
+        assert_eq!(
+            sanitize_session_name("認証機能の実装", SlugMode::Unicode),
+            "認証機能の実装"
+        );
+        assert_eq!(
+            sanitize_session_name("Größe ändern: API 🚀", SlugMode::Unicode),
+            "größe-ändern-api"
+        );
+        let name = sanitize_session_name("Привет, мир", SlugMode::Unicode);
+        assert_eq!(name, "привет-мир");
+        assert_eq!(naming::clean(&name), name);
+    }
+
+    #[test]
+    fn test_sanitize_transliterate_mode() {
+        assert_eq!(
+            sanitize_session_name("Größe ändern", SlugMode::Transliterate),
+            "grosse-andern"
+        );
+        assert_eq!(
+            sanitize_session_name("Привет мир", SlugMode::Transliterate),
+            "privet-mir"
+        );
+        let name = sanitize_session_name("認証機能の実装", SlugMode::Transliterate);
+        assert!(!name.is_empty() && name.is_ascii(), "{name}");
+    }
+
+    #[test]
+    fn test_sanitize_truncates_by_display_width() {
+        let name = sanitize_session_name(&"漢".repeat(40), SlugMode::Unicode);
+        assert_eq!(name.chars().count(), 25);
+        let name = sanitize_session_name(&"a".repeat(100), SlugMode::Unicode);
+        assert_eq!(name.len(), 50);
+    }
+
+    #[test]
+    fn test_generate_name_from_japanese_plan() {
+        let plan = "# 認証機能の実装\n\n詳細";
+        assert!(generate_session_name_from_plan(plan, SlugMode::Ascii).starts_with("plan-"));
+        assert_eq!(
+            generate_session_name_from_plan(plan, SlugMode::Unicode),
+            "認証機能の実装"
+        );
+    }
+
+    #[test]
+    fn test_new_with_unicode_name() {
+        let h = Harness::new();
+        let info = h.new_session("認証機能の実装").unwrap();
+        assert_eq!(info.session_name, "gj/main-認証機能の実装");
+        let s = &h.state().sessions[0];
+        assert_eq!(h.mux.tab_title(s.tab_id).as_deref(), Some("gj/main-認証機能の実装"));
+    }
+
     #[test]
     fn test_sanitize_consecutive_hyphens() {
-        assert_eq!(sanitize_session_name("a---b"), "a-b");
+        assert_eq!(sanitize_session_name("a---b", SlugMode::Ascii), "a-b");
     }
 
     #[test]
     fn test_sanitize_max_length() {
         let long = "a".repeat(100);
-        let result = sanitize_session_name(&long);
+        let result = sanitize_session_name(&long, SlugMode::Ascii);
         assert!(result.len() <= 50);
     }
 
@@ -685,47 +1826,47 @@ mod tests {
     fn test_generate_name_from_first_line() {
         let plan = "Implement authentication\n\nDetails here";
         assert_eq!(
-            generate_session_name_from_plan(plan),
+            generate_session_name_from_plan(plan, SlugMode::Ascii),
             "implement-authentication"
         );
     }
 
     #[test]
     fn test_generate_name_empty_content() {
-        let name = generate_session_name_from_plan("");
+        let name = generate_session_name_from_plan("", SlugMode::Ascii);
         assert!(name.starts_with("plan-"));
     }
 
     #[test]
     fn test_generate_name_only_whitespace() {
-        let name = generate_session_name_from_plan("   \n\n   ");
+        let name = generate_session_name_from_plan("   \n\n   ", SlugMode::Ascii);
         assert!(name.starts_with("plan-"));
     }
 
     #[test]
     fn test_extract_name_skips_empty_lines() {
         let content = "\n\n  \nImplement feature\n";
-        let result = extract_name_from_first_line(content);
+        let result = extract_name_from_first_line(content, SlugMode::Ascii);
         assert_eq!(result, Some("implement-feature".to_string()));
     }
 
     #[test]
     fn test_extract_name_too_short() {
         let content = "ab";
-        let result = extract_name_from_first_line(content);
+        let result = extract_name_from_first_line(content, SlugMode::Ascii);
         assert_eq!(result, Some("ab".to_string()));
     }
 
     #[test]
     fn test_sanitize_all_special_chars() {
-        assert_eq!(sanitize_session_name("---"), "");
-        assert_eq!(sanitize_session_name("🚀🐛"), "");
+        assert_eq!(sanitize_session_name("---", SlugMode::Ascii), "");
+        assert_eq!(sanitize_session_name("🚀🐛", SlugMode::Ascii), "");
     }
 
     #[test]
     fn test_extract_name_all_special_returns_none() {
         let content = "🚀🐛\nsome text";
-        let result = extract_name_from_first_line(content);
+        let result = extract_name_from_first_line(content, SlugMode::Ascii);
         assert_eq!(result, None);
     }
 
@@ -733,14 +1874,14 @@ mod tests {
     fn test_extract_name_markdown_heading() {
         let content = "# Implement auth\n\nDetails";
         assert_eq!(
-            extract_name_from_first_line(content),
+            extract_name_from_first_line(content, SlugMode::Ascii),
             Some("implement-auth".to_string())
         );
     }
 
     #[test]
     fn test_generate_name_all_special_first_line_falls_back() {
-        let name = generate_session_name_from_plan("🚀🐛\n");
+        let name = generate_session_name_from_plan("🚀🐛\n", SlugMode::Ascii);
         assert!(name.starts_with("plan-"));
     }
 
@@ -749,33 +1890,1297 @@ mod tests {
         let cli = Cli::parse_from(["ccm", "reset-layout"]);
         assert!(matches!(cli.command, Command::ResetLayout));
     }
-}
 
-/// Resolve session name from the current working directory by matching against known sessions.
-/// Uses canonicalized paths and picks the longest (most specific) match.
-fn resolve_session_from_cwd() -> Result<String> {
-    let cwd = env::current_dir()
-        .context("failed to get current directory")?
-        .canonicalize()
-        .context("failed to canonicalize current directory")?;
+    // ---------------------------------------------------------------
+    // Command lifecycle (fake multiplexer + fake worktree provider)
+    // ---------------------------------------------------------------
 
-    let state = state::load()?;
-    let best = state
-        .sessions
-        .iter()
-        .filter(|s| {
-            std::path::Path::new(&s.cwd)
-                .canonicalize()
-                .map(|p| cwd.starts_with(&p))
-                .unwrap_or(false)
+    use crate::mux::fake::FakeMux;
+    use crate::mux::SplitDirection;
+    use crate::worktree::fake::FakeWorktrees;
+
+    struct Harness {
+        _dir: tempfile::TempDir,
+        config: Config,
+        mux: FakeMux,
+        worktrees: FakeWorktrees,
+        state_path: PathBuf,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let state_path = dir.path().join("state.json");
+            Self {
+                _dir: dir,
+                config: Config::default(),
+                mux: FakeMux::new(),
+                worktrees: FakeWorktrees::new(),
+                state_path,
+            }
+        }
+
+        fn ctx(&self) -> Ctx<'_> {
+            Ctx {
+                config: &self.config,
+                mux: &self.mux,
+                worktrees: &self.worktrees,
+                state_path: self.state_path.clone(),
+            }
+        }
+
+        fn new_session(&self, name: &str) -> Result<NewSessionInfo> {
+            cmd_new(
+                &self.ctx(),
+                Some(name.to_string()),
+                None,
+                Some("/repo".to_string()),
+                None,
+                Some("claude\n".to_string()),
+            )
+        }
+
+        /// Like `new_session`, but the worktree directory exists on disk.
+        fn new_session_on_disk(&self, name: &str) -> NewSessionInfo {
+            let repo = self._dir.path().join("repo");
+            let info = cmd_new(
+                &self.ctx(),
+                Some(name.to_string()),
+                None,
+                Some(repo.to_string_lossy().to_string()),
+                None,
+                Some("claude\n".to_string()),
+            )
+            .unwrap();
+            fs::create_dir_all(&info.worktree_path).unwrap();
+            info
+        }
+
+        fn state(&self) -> state::State {
+            state::load_from(&self.state_path).unwrap()
+        }
+
+        /// Nothing created by a failed `cmd_new` may survive.
+        fn assert_rolled_back(&self) {
+            assert!(self.mux.live_pane_ids().is_empty());
+            assert!(self.worktrees.worktrees().is_empty());
+            assert!(self.state().sessions.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_new_builds_layout_and_saves_state() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        assert_eq!(info.session_name, "gj/main-feat");
+        assert_eq!(info.worktree_path, "/repo-feat");
+
+        let state = h.state();
+        assert_eq!(state.sessions.len(), 1);
+        assert_eq!(state.active_session.as_deref(), Some("gj/main-feat"));
+        let s = &state.sessions[0];
+        assert_eq!(s.cwd, "/repo-feat");
+        assert_eq!(s.panes, info.panes);
+
+        let watcher = h.mux.pane(s.panes["watcher"]).unwrap();
+        assert_eq!(
+            watcher.split_from,
+            Some((s.claude_pane_id, SplitDirection::Left, h.config.layout.watcher_width))
+        );
+        assert!(watcher.program.unwrap().contains(&"tab-watcher".to_string()));
+        let shell = h.mux.pane(s.panes["shell"]).unwrap();
+        assert_eq!(shell.split_from.unwrap().1, SplitDirection::Bottom);
+        assert!(shell.program.is_none());
+        let plans = h.mux.pane(s.panes["plans"]).unwrap();
+        assert_eq!(plans.split_from.unwrap().1, SplitDirection::Right);
+
+        let claude = h.mux.pane(s.claude_pane_id).unwrap();
+        assert_eq!(claude.cwd, "/repo-feat");
+        assert_eq!(claude.sent.len(), 1);
+        assert!(claude.sent[0].contains("wrap --session 'gj/main-feat' -- claude\n"));
+
+        assert_eq!(h.mux.tab_title(s.tab_id).as_deref(), Some("gj/main-feat"));
+        assert_eq!(h.mux.active_pane(), Some(s.claude_pane_id));
+    }
+
+    #[test]
+    fn test_new_uses_configured_layout() {
+        let mut h = Harness::new();
+        h.config = toml::from_str(
+            r#"
+[[layout.panes]]
+name = "logs"
+split = "right"
+size = 40
+command = ["tail", "-f", "{cwd}/app.log"]
+"#,
+        )
+        .unwrap();
+        let info = h.new_session("feat").unwrap();
+        assert_eq!(info.panes.keys().collect::<Vec<_>>(), ["logs"]);
+        let logs = h.mux.pane(info.panes["logs"]).unwrap();
+        assert_eq!(logs.program.unwrap(), ["tail", "-f", "/repo-feat/app.log"]);
+        assert_eq!(h.mux.live_pane_ids().len(), 2);
+    }
+
+    fn with_aux_pane(h: &mut Harness) {
+        h.config.aux_panes = vec![config::AuxPane {
+            name: "server".to_string(),
+            split: SplitDirection::Right,
+            from: "shell".to_string(),
+            size: 50,
+            command: "npm run dev".to_string(),
+            restart: config::RestartPolicy::Always,
+            restart_delay_secs: 2,
+        }];
+    }
+
+    #[test]
+    fn test_new_creates_aux_panes() {
+        let mut h = Harness::new();
+        with_aux_pane(&mut h);
+        h.new_session("feat").unwrap();
+        let s = &h.state().sessions[0];
+        assert!(!s.panes.contains_key("server"));
+        let server = h.mux.pane(s.aux_panes["server"]).unwrap();
+        assert_eq!(server.split_from.unwrap().0, s.panes["shell"]);
+        assert_eq!(server.program.unwrap()[..2], ["sh", "-c"]);
+    }
+
+    #[test]
+    fn test_new_rollback_kills_aux_panes() {
+        let mut h = Harness::new();
+        with_aux_pane(&mut h);
+        h.mux.fail_on("send_text", 1);
+        assert!(h.new_session("feat").is_err());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_close_kills_aux_panes() {
+        let mut h = Harness::new();
+        with_aux_pane(&mut h);
+        let info = h.new_session("feat").unwrap();
+        assert_eq!(h.mux.live_pane_ids().len(), 5);
+        cmd_close(&h.ctx(), Some(info.session_name), false, false, false).unwrap();
+        assert!(h.mux.live_pane_ids().is_empty());
+    }
+
+    #[test]
+    fn test_reset_layout_recreates_aux_panes() {
+        let mut h = Harness::new();
+        with_aux_pane(&mut h);
+        let info = h.new_session("feat").unwrap();
+        let before = h.state().sessions[0].aux_panes["server"];
+        h.mux.set_current_pane(info.claude_pane_id);
+        cmd_reset_layout(&h.ctx()).unwrap();
+        let after = h.state().sessions[0].aux_panes["server"];
+        assert_ne!(before, after);
+        assert!(h.mux.pane(before).is_none());
+        assert!(h.mux.pane(after).is_some());
+        assert_eq!(h.mux.live_pane_ids().len(), 5);
+    }
+
+    /// Write a bootstrap spec into the main checkout used by `new_session_on_disk`.
+    fn with_bootstrap_spec(h: &Harness, spec: &str) -> PathBuf {
+        let repo = h._dir.path().join("repo");
+        fs::create_dir_all(repo.join(".ccm")).unwrap();
+        fs::write(repo.join(bootstrap::SPEC_FILE), spec).unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_new_bootstraps_in_shell_pane() {
+        let h = Harness::new();
+        let repo = with_bootstrap_spec(&h, "commands = [\"npm ci\"]\n");
+        let info = h.new_session_on_disk("feat");
+
+        let shell = h.mux.pane(info.panes["shell"]).unwrap();
+        assert_eq!(shell.sent.len(), 1);
+        assert!(shell.sent[0].contains(&format!(
+            "' bootstrap --cwd '{}' --main '{}'\n",
+            info.worktree_path,
+            repo.display()
+        )));
+
+        let status = format!("{}/{}", info.worktree_path, bootstrap::STATUS_FILE);
+        assert_eq!(info.wait_for.as_deref(), Some(status.as_str()));
+        let claude = h.mux.pane(info.claude_pane_id).unwrap();
+        assert!(claude.sent[0].contains(&format!("--wait-for '{status}' -- claude\n")));
+    }
+
+    #[test]
+    fn test_new_bootstraps_inline_without_shell_pane() {
+        let mut h = Harness::new();
+        h.config = toml::from_str(
+            r#"
+[[layout.panes]]
+name = "logs"
+split = "right"
+size = 40
+"#,
+        )
+        .unwrap();
+        let repo = with_bootstrap_spec(&h, "copy = [\".env\"]\n");
+        fs::write(repo.join(".env"), "A=1\n").unwrap();
+        // The fake provider does not create the worktree directory
+        fs::create_dir_all(h._dir.path().join("repo-feat")).unwrap();
+        let info = h.new_session_on_disk("feat");
+
+        let env_file = Path::new(&info.worktree_path).join(".env");
+        assert_eq!(fs::read_to_string(env_file).unwrap(), "A=1\n");
+        assert_eq!(info.wait_for, None);
+        let claude = h.mux.pane(info.claude_pane_id).unwrap();
+        assert!(!claude.sent[0].contains("--wait-for"));
+    }
+
+    #[test]
+    fn test_new_with_invalid_bootstrap_spec_rolls_back() {
+        let h = Harness::new();
+        let repo = with_bootstrap_spec(&h, "copy = [\"../outside\"]\n");
+        let err = cmd_new(
+            &h.ctx(),
+            Some("feat".to_string()),
+            None,
+            Some(repo.to_string_lossy().to_string()),
+            None,
+            Some("claude\n".to_string()),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("bootstrap failed"));
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_cli_parse_wrap_wait_for() {
+        let cli = Cli::parse_from([
+            "ccm", "wrap", "--session", "s", "--wait-for", "/wt/x", "--", "claude",
+        ]);
+        let Command::Wrap { wait_for, command, .. } = cli.command else {
+            panic!("expected wrap");
+        };
+        assert_eq!(wait_for.as_deref(), Some("/wt/x"));
+        assert_eq!(command, ["claude"]);
+    }
+
+    #[test]
+    fn test_new_without_claude_command_sends_nothing() {
+        let h = Harness::new();
+        let info = cmd_new(&h.ctx(), None, None, Some("/repo".to_string()), None, None).unwrap();
+        assert!(h.mux.pane(info.claude_pane_id).unwrap().sent.is_empty());
+    }
+
+    #[test]
+    fn test_new_records_base_ref() {
+        let h = Harness::new();
+        let info = cmd_new(
+            &h.ctx(),
+            Some("hotfix".to_string()),
+            None,
+            Some("/repo".to_string()),
+            Some("release-1.2".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.session_name, "gj/release-1.2-hotfix");
+        assert_eq!(h.state().sessions[0].base_ref.as_deref(), Some("release-1.2"));
+
+        h.new_session("feat").unwrap();
+        assert_eq!(h.state().sessions[1].base_ref.as_deref(), Some("main"));
+    }
+
+    #[test]
+    fn test_new_records_repo() {
+        let h = Harness::new();
+        h.new_session("feat").unwrap();
+        let session = &h.state().sessions[0];
+        assert_eq!(session.repo.as_deref(), Some("/repo"));
+        assert!(in_repo(session, "repo"));
+        assert!(in_repo(session, "/repo"));
+        assert!(!in_repo(session, "other"));
+    }
+
+    #[test]
+    fn test_cli_parse_new_from() {
+        let cli = Cli::parse_from(["ccm", "new", "fix", "--from", "v1.2.0"]);
+        assert!(matches!(
+            cli.command,
+            Command::New { from: Some(ref r), .. } if r == "v1.2.0"
+        ));
+        let cli = Cli::parse_from(["ccm", "plan", "--from", "release"]);
+        assert!(matches!(
+            cli.command,
+            Command::Plan { from: Some(ref r), .. } if r == "release"
+        ));
+    }
+
+    #[test]
+    fn test_new_worktree_failure_creates_nothing() {
+        let h = Harness::new();
+        h.worktrees.fail_new();
+        assert!(h.new_session("feat").is_err());
+        assert_eq!(h.mux.calls("spawn_tab"), 0);
+        assert!(h.state().sessions.is_empty());
+    }
+
+    #[test]
+    fn test_new_empty_branch_removes_worktree() {
+        let h = Harness::new();
+        h.worktrees.return_empty_branch();
+        let err = h.new_session("feat").unwrap_err();
+        assert!(err.to_string().contains("empty branch name"));
+        assert_eq!(h.worktrees.discarded(), vec!["/repo-feat"]);
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_rollback_on_spawn_failure() {
+        let h = Harness::new();
+        h.mux.fail_on("spawn_tab", 1);
+        assert!(h.new_session("feat").is_err());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_rollback_on_watcher_split_failure() {
+        let h = Harness::new();
+        h.mux.fail_on("split_pane", 1);
+        assert!(h.new_session("feat").is_err());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_rollback_on_shell_split_failure() {
+        let h = Harness::new();
+        h.mux.fail_on("split_pane", 2);
+        assert!(h.new_session("feat").is_err());
+        assert_eq!(h.mux.calls("kill_pane"), 2);
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_plans_split_failure_is_not_fatal() {
+        let h = Harness::new();
+        h.mux.fail_on("split_pane", 3);
+        let info = h.new_session("feat").unwrap();
+        assert!(!info.panes.contains_key("plans"));
+        assert!(!h.state().sessions[0].panes.contains_key("plans"));
+        assert_eq!(h.mux.live_pane_ids().len(), 3);
+    }
+
+    #[test]
+    fn test_new_rollback_on_send_text_failure() {
+        let h = Harness::new();
+        h.mux.fail_on("send_text", 1);
+        assert!(h.new_session("feat").is_err());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_rollback_on_set_title_failure() {
+        let h = Harness::new();
+        h.mux.fail_on("set_tab_title", 1);
+        assert!(h.new_session("feat").is_err());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_rollback_on_list_failure() {
+        let h = Harness::new();
+        h.mux.set_list_unavailable(true);
+        assert!(h.new_session("feat").is_err());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_new_duplicate_name_gets_counter() {
+        let h = Harness::new();
+        let first = h.new_session("feat").unwrap();
+        let second = h.new_session("feat").unwrap();
+        let third = h.new_session("feat").unwrap();
+        assert_eq!(first.session_name, "gj/main-feat");
+        assert_eq!(second.session_name, "gj/main-feat-2");
+        assert_eq!(third.session_name, "gj/main-feat-3");
+        assert_eq!(h.state().sessions.len(), 3);
+    }
+
+    #[test]
+    fn test_insert_session_rejects_duplicate() {
+        let h = Harness::new();
+        let first = h.new_session("feat").unwrap();
+        // Another ccm process may save the same name between naming and saving
+        let mut session = h.state().sessions[0].clone();
+        session.claude_pane_id += 100;
+        let err = insert_session(&h.ctx(), &session).unwrap_err();
+        assert!(matches!(err, CcmError::SessionExists(ref n) if n == "gj/main-feat"));
+
+        let state = h.state();
+        assert_eq!(state.sessions.len(), 1);
+        assert_eq!(state.sessions[0].claude_pane_id, first.claude_pane_id);
+    }
+
+    #[test]
+    fn test_new_renders_naming_template() {
+        let mut h = Harness::new();
+        h.config.naming.template = "{ticket}-{slug}".to_string();
+        let plan = "# Fix login redirect\n\nTracked in WEB-481.\n";
+        let slug = generate_session_name_from_plan(plan, SlugMode::Ascii);
+        let ticket = naming::find_ticket(&h.config.naming.ticket_pattern, plan).unwrap();
+        let info = cmd_new(
+            &h.ctx(),
+            Some(slug),
+            ticket.as_deref(),
+            Some("/repo".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.session_name, "gj/main-WEB-481-fix-login-redirect");
+
+        // An explicit ticket (`ccm new --ticket`)
+        let info = cmd_new(
+            &h.ctx(),
+            Some("docs".to_string()),
+            Some("DOC-7"),
+            Some("/repo".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.session_name, "gj/main-DOC-7-docs");
+
+        // Without a ticket, its separator is dropped
+        let info = cmd_new(
+            &h.ctx(),
+            Some("api".to_string()),
+            None,
+            Some("/repo".to_string()),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.session_name, "gj/main-api");
+    }
+
+    #[test]
+    fn test_new_name_collision_is_exact() {
+        let h = Harness::new();
+        h.new_session("fix-login").unwrap();
+        let info = h.new_session("login").unwrap();
+        assert_eq!(info.session_name, "gj/main-login");
+
+        // Another base does not collide either
+        let info = cmd_new(
+            &h.ctx(),
+            Some("login".to_string()),
+            None,
+            Some("/repo".to_string()),
+            Some("dev".to_string()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(info.session_name, "gj/dev-login");
+    }
+
+    #[test]
+    fn test_new_with_invalid_template_creates_nothing() {
+        let mut h = Harness::new();
+        h.config.naming.template = "{branch}".to_string();
+        let err = h.new_session("feat").unwrap_err();
+        assert!(err.to_string().contains("unknown placeholder"));
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_annotate_updates_metadata() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        let annotation = Annotation {
+            description: Some("Fix login".to_string()),
+            add_tags: vec!["auth".to_string(), "urgent".to_string()],
+            ticket: Some("WEB-481".to_string()),
+            note: Some("needs review".to_string()),
+            ..Annotation::default()
+        };
+        cmd_annotate(&h.ctx(), Some(info.session_name.clone()), &annotation).unwrap();
+        let untag = Annotation {
+            remove_tags: vec!["urgent".to_string()],
+            ..Annotation::default()
+        };
+        cmd_annotate(&h.ctx(), Some(info.session_name.clone()), &untag).unwrap();
+
+        let s = &h.state().sessions[0];
+        assert_eq!(s.description.as_deref(), Some("Fix login"));
+        assert_eq!(s.tags, ["auth"]);
+        assert_eq!(s.ticket.as_deref(), Some("WEB-481"));
+        assert_eq!(s.notes, ["needs review"]);
+
+        let err = cmd_annotate(&h.ctx(), Some("nope".to_string()), &annotation).unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_sessions_with_tags_requires_all() {
+        let h = Harness::new();
+        for (name, tags) in [("a", &["auth"][..]), ("b", &["auth", "ui"]), ("c", &[])] {
+            let info = h.new_session(name).unwrap();
+            let annotation = Annotation {
+                add_tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..Annotation::default()
+            };
+            annotate(&h.ctx(), &info.session_name, &annotation).unwrap();
+        }
+        let sessions = h.state().sessions;
+        let names = |tags: &[&str]| -> Vec<String> {
+            let tags: Vec<String> = tags.iter().map(|t| t.to_string()).collect();
+            sessions_with_tags(&sessions, &tags)
+                .iter()
+                .map(|s| s.name.clone())
+                .collect()
+        };
+        assert_eq!(names(&[]).len(), 3);
+        assert_eq!(names(&["auth"]), ["gj/main-a", "gj/main-b"]);
+        assert_eq!(names(&["auth", "ui"]), ["gj/main-b"]);
+        assert!(names(&["none"]).is_empty());
+    }
+
+    #[test]
+    fn test_cli_parse_new_and_annotate_metadata() {
+        let cli = Cli::parse_from([
+            "ccm", "new", "feat", "--desc", "Fix login", "--tag", "auth", "--tag", "ui",
+        ]);
+        let Command::New {
+            description, tags, ..
+        } = cli.command
+        else {
+            panic!("expected new");
+        };
+        assert_eq!(description.as_deref(), Some("Fix login"));
+        assert_eq!(tags, ["auth", "ui"]);
+
+        let cli = Cli::parse_from(["ccm", "annotate", "s", "--untag", "ui", "--note", "x"]);
+        let Command::Annotate {
+            name, untags, note, ..
+        } = cli.command
+        else {
+            panic!("expected annotate");
+        };
+        assert_eq!(name.as_deref(), Some("s"));
+        assert_eq!(untags, ["ui"]);
+        assert_eq!(note.as_deref(), Some("x"));
+
+        let cli = Cli::parse_from(["ccm", "list", "--tag", "auth"]);
+        assert!(matches!(cli.command, Command::List { ref tags, .. } if tags == &["auth"]));
+    }
+
+    #[test]
+    fn test_switch_activates_tab() {
+        let h = Harness::new();
+        let a = h.new_session("a").unwrap();
+        h.new_session("b").unwrap();
+        cmd_switch(&h.ctx(), &a.session_name).unwrap();
+        assert_eq!(h.state().active_session.as_deref(), Some("gj/main-a"));
+        assert_eq!(h.mux.active_tab(), Some(h.state().sessions[0].tab_id));
+    }
+
+    #[test]
+    fn test_history_records_create_and_close() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        h.new_session("other").unwrap();
+        cmd_close(&h.ctx(), Some(info.session_name.clone()), true, false, false).unwrap();
+
+        let journal = journal::path_for(&h.state_path);
+        let filter = journal::Filter {
+            session: Some("feat".to_string()),
+            branch_prefix: Some("gj/".to_string()),
+            ..journal::Filter::default()
+        };
+        let kinds: Vec<EventKind> = journal::read(&journal, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|e| e.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::Created {
+                    cwd: "/repo-feat".to_string(),
+                    base_ref: Some("main".to_string()),
+                },
+                EventKind::Closed { merged: true },
+            ]
+        );
+        assert_eq!(journal::read(&journal, &journal::Filter::default()).unwrap().len(), 3);
+
+        cmd_history(&h.ctx(), None, Some("1h"), None).unwrap();
+        assert!(cmd_history(&h.ctx(), None, Some("soon"), None).is_err());
+    }
+
+    #[test]
+    fn test_cli_parse_history() {
+        let cli = Cli::parse_from(["ccm", "history", "feat", "--since", "2h"]);
+        let Command::History {
+            session,
+            since,
+            until,
+        } = cli.command
+        else {
+            panic!("expected history");
+        };
+        assert_eq!(session.as_deref(), Some("feat"));
+        assert_eq!(since.as_deref(), Some("2h"));
+        assert_eq!(until, None);
+    }
+
+    #[test]
+    fn test_switch_unknown_session() {
+        let h = Harness::new();
+        assert!(cmd_switch(&h.ctx(), "nope").is_err());
+        assert_eq!(h.mux.calls("activate_tab"), 0);
+    }
+
+    #[test]
+    fn test_close_kills_panes_and_discards_worktree() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        h.new_session("other").unwrap();
+        cmd_close(&h.ctx(), Some(info.session_name.clone()), false, false, false).unwrap();
+
+        let state = h.state();
+        assert_eq!(state.sessions.len(), 1);
+        assert_eq!(state.sessions[0].name, "gj/main-other");
+        assert_eq!(h.mux.live_pane_ids().len(), 4);
+        assert!(h.mux.pane(info.claude_pane_id).is_none());
+        assert_eq!(h.worktrees.discarded(), vec!["/repo-feat"]);
+        assert!(h.worktrees.merged().is_empty());
+    }
+
+    #[test]
+    fn test_close_clears_active_session() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        cmd_close(&h.ctx(), Some(info.session_name), false, false, false).unwrap();
+        assert_eq!(h.state().active_session, None);
+    }
+
+    #[test]
+    fn test_close_tolerates_dead_panes() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        h.mux.restart();
+        cmd_close(&h.ctx(), Some(info.session_name), false, false, false).unwrap();
+        assert!(h.state().sessions.is_empty());
+        assert!(h.worktrees.worktrees().is_empty());
+    }
+
+    #[test]
+    fn test_close_with_merge() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        cmd_close(&h.ctx(), Some(info.session_name), true, false, false).unwrap();
+        assert_eq!(h.worktrees.merged(), vec!["/repo-feat"]);
+        assert!(h.worktrees.discarded().is_empty());
+        h.assert_rolled_back();
+    }
+
+    #[test]
+    fn test_close_merge_runs_checks_in_worktree() {
+        let mut h = Harness::new();
+        h.config.merge.checks = vec!["touch checked".to_string()];
+        let info = h.new_session_on_disk("feat");
+        cmd_close(&h.ctx(), Some(info.session_name), true, false, false).unwrap();
+        assert!(Path::new(&info.worktree_path).join("checked").exists());
+        assert_eq!(h.worktrees.merged(), vec![info.worktree_path]);
+    }
+
+    #[test]
+    fn test_close_merge_check_failure_keeps_session() {
+        let mut h = Harness::new();
+        h.config.merge.checks = vec!["true".to_string(), "exit 1".to_string()];
+        let info = h.new_session_on_disk("feat");
+        let err = cmd_close(&h.ctx(), Some(info.session_name.clone()), true, false, false)
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("`exit 1` exited with status 1"));
+        assert!(h.worktrees.merged().is_empty());
+        assert_eq!(h.state().sessions.len(), 1);
+        assert_eq!(h.mux.live_pane_ids().len(), 4);
+
+        cmd_close(&h.ctx(), Some(info.session_name), true, false, true).unwrap();
+        assert_eq!(h.worktrees.merged(), vec![info.worktree_path]);
+    }
+
+    #[test]
+    fn test_cli_parse_skip_checks_requires_merge() {
+        assert!(Cli::try_parse_from(["ccm", "close", "x", "--skip-checks"]).is_err());
+        let cli = Cli::parse_from(["ccm", "close", "x", "--merge", "--skip-checks"]);
+        assert!(matches!(
+            cli.command,
+            Command::Close { merge: true, skip_checks: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_close_merge_failure_keeps_session() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        h.worktrees.fail_merge();
+        assert!(cmd_close(&h.ctx(), Some(info.session_name), true, false, false).is_err());
+        assert_eq!(h.state().sessions.len(), 1);
+        assert_eq!(h.mux.live_pane_ids().len(), 4);
+        assert_eq!(h.worktrees.worktrees().len(), 1);
+    }
+
+    #[test]
+    fn test_close_unknown_session() {
+        let h = Harness::new();
+        assert!(cmd_close(&h.ctx(), Some("nope".to_string()), false, false, false).is_err());
+        assert_eq!(h.mux.calls("kill_pane"), 0);
+    }
+
+    #[test]
+    fn test_restore_reopens_dead_session() {
+        let h = Harness::new();
+        let info = h.new_session_on_disk("feat");
+        let before = h.state().sessions[0].clone();
+        h.mux.restart();
+
+        cmd_restore(&h.ctx(), Some(info.session_name.clone()), false, false).unwrap();
+
+        let after = h.state().sessions[0].clone();
+        assert_eq!(after.name, before.name);
+        assert_eq!(after.cwd, before.cwd);
+        assert_ne!(after.claude_pane_id, before.claude_pane_id);
+        assert_ne!(after.tab_id, before.tab_id);
+        assert_eq!(after.panes.len(), 3);
+        let claude = h.mux.pane(after.claude_pane_id).unwrap();
+        assert_eq!(claude.cwd, info.worktree_path);
+        assert!(claude.sent[0].ends_with("-- claude --continue\n"));
+        assert_eq!(h.mux.active_tab(), Some(after.tab_id));
+        assert_eq!(h.mux.tab_title(after.tab_id).as_deref(), Some("gj/main-feat"));
+    }
+
+    #[test]
+    fn test_restore_fresh_skips_continue() {
+        let h = Harness::new();
+        let info = h.new_session_on_disk("feat");
+        h.mux.restart();
+        cmd_restore(&h.ctx(), Some(info.session_name), false, true).unwrap();
+        let claude = h.state().sessions[0].claude_pane_id;
+        assert!(h.mux.pane(claude).unwrap().sent[0].ends_with("-- claude\n"));
+    }
+
+    #[test]
+    fn test_restore_refuses_live_session() {
+        let h = Harness::new();
+        let info = h.new_session_on_disk("feat");
+        let err = cmd_restore(&h.ctx(), Some(info.session_name), false, false).unwrap_err();
+        assert!(err.to_string().contains("still running"));
+        assert_eq!(h.mux.calls("spawn_tab"), 1);
+    }
+
+    #[test]
+    fn test_restore_requires_worktree() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        h.mux.restart();
+        let err = cmd_restore(&h.ctx(), Some(info.session_name), false, false).unwrap_err();
+        assert!(err.to_string().contains("no longer exists"));
+    }
+
+    #[test]
+    fn test_restore_all_skips_live_and_missing() {
+        let h = Harness::new();
+        let a = h.new_session_on_disk("a");
+        let b = h.new_session_on_disk("b");
+        h.new_session("gone").unwrap();
+        let live = h.new_session_on_disk("live");
+        // a, b and gone lose their panes; live keeps them
+        for session in h.state().sessions {
+            if session.name != live.session_name {
+                for id in session.pane_ids() {
+                    h.mux.close_externally(id);
+                }
+            }
+        }
+        let live_before = h.state().sessions[3].claude_pane_id;
+
+        cmd_restore(&h.ctx(), None, true, false).unwrap();
+
+        let state = h.state();
+        let alive = |name: &str| {
+            let s = state.sessions.iter().find(|s| s.name == name).unwrap();
+            h.mux.pane(s.claude_pane_id).is_some()
+        };
+        assert!(alive(&a.session_name));
+        assert!(alive(&b.session_name));
+        assert!(!alive("gj/main-gone"));
+        assert_eq!(state.sessions[3].claude_pane_id, live_before);
+    }
+
+    #[test]
+    fn test_restore_list_failure_is_an_error() {
+        let h = Harness::new();
+        h.new_session_on_disk("feat");
+        h.mux.set_list_unavailable(true);
+        assert!(cmd_restore(&h.ctx(), None, true, false).is_err());
+        assert_eq!(h.mux.calls("spawn_tab"), 1);
+    }
+
+    #[test]
+    fn test_restore_failure_leaves_no_panes() {
+        let h = Harness::new();
+        let info = h.new_session_on_disk("feat");
+        h.mux.restart();
+        h.mux.fail_on("set_tab_title", 2);
+        assert!(cmd_restore(&h.ctx(), Some(info.session_name), false, false).is_err());
+        assert!(h.mux.live_pane_ids().is_empty());
+        // The session stays in state for a later retry
+        assert_eq!(h.state().sessions.len(), 1);
+    }
+
+    /// A git repository with one commit at `<tmp>/repo`.
+    fn init_git_repo(h: &Harness) -> String {
+        git::test_repo(h._dir.path())
+    }
+
+    #[test]
+    fn test_adopt_existing_worktree() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let wt = h._dir.path().join("by-hand").to_string_lossy().into_owned();
+        git::run(&repo, &["worktree", "add", "-q", "-b", "feat/by-hand", &wt]).unwrap();
+
+        cmd_adopt(&h.ctx(), Some(wt.clone()), None, None).unwrap();
+        let state = h.state();
+        let session = &state.sessions[0];
+        assert_eq!(session.name, "feat/by-hand");
+        assert_eq!(session.cwd, wt);
+        assert!(session.adopted);
+        assert_eq!(state.active_session.as_deref(), Some("feat/by-hand"));
+        assert_eq!(h.mux.live_pane_ids().len(), 4);
+        assert!(h.worktrees.worktrees().is_empty());
+
+        // The same worktree cannot be adopted twice
+        let err = cmd_adopt(&h.ctx(), Some(wt), Some("other".to_string()), None).unwrap_err();
+        assert!(err.to_string().contains("already belongs to session"));
+    }
+
+    #[test]
+    fn test_adopt_branch_uses_or_creates_worktree() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        git::run(&repo, &["branch", "topic"]).unwrap();
+
+        cmd_adopt(&h.ctx(), Some("topic".to_string()), None, Some(repo.clone())).unwrap();
+        let expected = h._dir.path().join("repo-topic");
+        assert_eq!(h.state().sessions[0].cwd, expected.to_string_lossy());
+        assert_eq!(git::current_branch(&h.state().sessions[0].cwd).unwrap(), "topic");
+
+        let err = cmd_adopt(&h.ctx(), Some("nope".to_string()), None, Some(repo)).unwrap_err();
+        assert!(err.to_string().contains("neither a directory nor a local branch"));
+    }
+
+    #[test]
+    fn test_adopt_rejects_non_worktree_dirs() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let sub = Path::new(&repo).join("sub");
+        fs::create_dir(&sub).unwrap();
+        let err = cmd_adopt(&h.ctx(), Some(sub.to_string_lossy().into_owned()), None, None)
+            .unwrap_err();
+        assert!(err.to_string().contains("not the root of a git worktree"));
+
+        let plain = h._dir.path().to_string_lossy().into_owned();
+        assert!(cmd_adopt(&h.ctx(), Some(plain), None, None).is_err());
+        assert!(h.state().sessions.is_empty());
+        assert_eq!(h.mux.calls("spawn_tab"), 0);
+    }
+
+    fn mark_adopted(h: &Harness) {
+        state::update_at(&h.state_path, |state| {
+            state.sessions[0].adopted = true;
+            Ok(())
         })
-        .max_by_key(|s| s.cwd.len());
+        .unwrap();
+    }
 
-    match best {
-        Some(session) => Ok(session.name.clone()),
-        None => Err(anyhow::anyhow!(
-            "no session found for current directory '{}'. Specify a session name explicitly.",
-            cwd.display()
-        )),
+    #[test]
+    fn test_close_adopted_keeps_worktree() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        mark_adopted(&h);
+
+        let err = cmd_close(&h.ctx(), Some(info.session_name.clone()), true, false, false).unwrap_err();
+        assert!(err.to_string().contains("--remove-worktree"));
+        assert_eq!(h.state().sessions.len(), 1);
+
+        cmd_close(&h.ctx(), Some(info.session_name), false, false, false).unwrap();
+        assert!(h.state().sessions.is_empty());
+        assert!(h.mux.live_pane_ids().is_empty());
+        assert!(h.worktrees.discarded().is_empty());
+        assert_eq!(h.worktrees.worktrees().len(), 1);
+    }
+
+    #[test]
+    fn test_close_adopted_with_remove_worktree() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        mark_adopted(&h);
+        cmd_close(&h.ctx(), Some(info.session_name), true, true, false).unwrap();
+        assert_eq!(h.worktrees.merged(), vec![info.worktree_path]);
+    }
+
+    #[test]
+    fn test_conflicts_reports_shared_files() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        for name in ["feat-a", "feat-b", "feat-c"] {
+            let wt = h._dir.path().join(name).to_string_lossy().into_owned();
+            git::run(&repo, &["worktree", "add", "-q", "-b", name, &wt]).unwrap();
+            cmd_adopt(&h.ctx(), Some(wt), None, None).unwrap();
+        }
+        let dir = h._dir.path();
+        fs::write(dir.join("feat-a/shared.rs"), "a\n").unwrap();
+        fs::write(dir.join("feat-b/shared.rs"), "b\n").unwrap();
+        fs::write(dir.join("feat-c/other.rs"), "c\n").unwrap();
+
+        let overlaps = cmd_conflicts(&h.ctx()).unwrap();
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].sessions, ("feat-a".to_string(), "feat-b".to_string()));
+        assert_eq!(overlaps[0].files, ["shared.rs"]);
+    }
+
+    #[test]
+    fn test_conflicts_skips_sessions_without_git() {
+        let h = Harness::new();
+        h.new_session("feat").unwrap();
+        assert!(cmd_conflicts(&h.ctx()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_cli_parse_diff() {
+        let cli = Cli::parse_from(["ccm", "diff", "feat"]);
+        assert!(matches!(cli.command, Command::Diff { name: Some(ref n) } if n == "feat"));
+        let cli = Cli::parse_from(["ccm", "diff"]);
+        assert!(matches!(cli.command, Command::Diff { name: None }));
+    }
+
+    #[test]
+    fn test_close_archives_and_reopen_restores() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let wt = h._dir.path().join("by-hand").to_string_lossy().into_owned();
+        git::run(&repo, &["worktree", "add", "-q", "-b", "feat/by-hand", &wt]).unwrap();
+        cmd_adopt(&h.ctx(), Some(wt.clone()), None, None).unwrap();
+        let annotation = Annotation {
+            description: Some("Login page".to_string()),
+            add_tags: vec!["auth".to_string()],
+            ..Annotation::default()
+        };
+        annotate(&h.ctx(), "feat/by-hand", &annotation).unwrap();
+        let plans = Path::new(&wt).join(archive::PLANS_DIR);
+        fs::create_dir_all(&plans).unwrap();
+        fs::write(plans.join("plan.md"), "# Login\n").unwrap();
+
+        cmd_close(&h.ctx(), Some("feat/by-hand".to_string()), false, false, false).unwrap();
+        let archive_dir = archive::dir_for(&h.state_path);
+        let entry = archive::find(&archive_dir, "feat/by-hand", None).unwrap();
+        assert_eq!(entry.branch.as_deref(), Some("feat/by-hand"));
+        assert_eq!(entry.plans, ["plan.md"]);
+        assert!(!entry.merged);
+        assert_eq!(entry.session.description.as_deref(), Some("Login page"));
+        cmd_archive_list(&h.ctx()).unwrap();
+        cmd_archive_show(&h.ctx(), "feat/by-hand").unwrap();
+
+        // The worktree is gone but the branch is not: reopen checks it out again
+        git::run(&repo, &["worktree", "remove", "--force", &wt]).unwrap();
+        cmd_reopen(&h.ctx(), "feat/by-hand").unwrap();
+        let state = h.state();
+        let session = &state.sessions[0];
+        assert_eq!(session.name, "feat/by-hand");
+        assert_eq!(session.tags, ["auth"]);
+        assert_eq!(session.description.as_deref(), Some("Login page"));
+        assert!(session.adopted);
+        let restored = Path::new(&session.cwd).join(archive::PLANS_DIR).join("plan.md");
+        assert_eq!(fs::read_to_string(restored).unwrap(), "# Login\n");
+        assert!(archive::list(&archive_dir).unwrap().is_empty());
+
+        let err = cmd_reopen(&h.ctx(), "feat/by-hand").unwrap_err();
+        assert!(err.to_string().contains("no archived session"));
+    }
+
+    #[test]
+    fn test_reopen_requires_branch_or_its_commit() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let wt = h._dir.path().join("by-hand").to_string_lossy().into_owned();
+        git::run(&repo, &["worktree", "add", "-q", "-b", "feat/gone", &wt]).unwrap();
+        git::run(&wt, &["commit", "-q", "--allow-empty", "-m", "wip"]).unwrap();
+        cmd_adopt(&h.ctx(), Some(wt.clone()), None, None).unwrap();
+        cmd_close(&h.ctx(), Some("feat/gone".to_string()), false, false, false).unwrap();
+        git::run(&repo, &["worktree", "remove", "--force", &wt]).unwrap();
+        git::run(&repo, &["branch", "-D", "feat/gone"]).unwrap();
+        // Let git forget the commit the branch was on
+        git::run(&repo, &["reflog", "expire", "--expire=now", "--all"]).unwrap();
+        git::run(&repo, &["gc", "-q", "--prune=now"]).unwrap();
+
+        let err = cmd_reopen(&h.ctx(), "feat/gone").unwrap_err();
+        assert!(err.to_string().contains("no longer exists"), "{err}");
+        assert!(h.state().sessions.is_empty());
+        assert_eq!(archive::list(&archive::dir_for(&h.state_path)).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_reopen_after_close_without_merge() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let provider = worktree::Git::new(&config::GitConfig::default());
+        let ctx = Ctx {
+            config: &h.config,
+            mux: &h.mux,
+            worktrees: &provider,
+            state_path: h.state_path.clone(),
+        };
+        let info = cmd_new(&ctx, Some("feat".to_string()), None, Some(repo.clone()), None, None)
+            .unwrap();
+        git::run(&info.worktree_path, &["commit", "-q", "--allow-empty", "-m", "wip"]).unwrap();
+        let head = git::run(&info.worktree_path, &["rev-parse", "HEAD"]).unwrap();
+
+        // Closing without merge deletes the branch along with the worktree
+        cmd_close(&ctx, Some(info.session_name.clone()), false, false, false).unwrap();
+        assert!(!git::branch_exists(&repo, "ccm/main-feat").unwrap());
+
+        cmd_reopen(&ctx, "feat").unwrap();
+        let session = &h.state().sessions[0];
+        assert_eq!(session.name, info.session_name);
+        assert_eq!(git::current_branch(&session.cwd).unwrap(), "ccm/main-feat");
+        assert_eq!(git::run(&session.cwd, &["rev-parse", "HEAD"]).unwrap(), head);
+    }
+
+    #[test]
+    fn test_reopen_failure_removes_new_worktree() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let wt = h._dir.path().join("by-hand").to_string_lossy().into_owned();
+        git::run(&repo, &["worktree", "add", "-q", "-b", "feat/retry", &wt]).unwrap();
+        cmd_adopt(&h.ctx(), Some(wt.clone()), None, None).unwrap();
+        let created_at = h.state().sessions[0].created_at;
+        cmd_close(&h.ctx(), Some("feat/retry".to_string()), false, false, false).unwrap();
+        git::run(&repo, &["worktree", "remove", "--force", &wt]).unwrap();
+
+        // The tab of adopt was the first
+        h.mux.fail_on("spawn_tab", 2);
+        assert!(cmd_reopen(&h.ctx(), "feat/retry").is_err());
+        assert!(h.state().sessions.is_empty());
+        let worktrees = git::worktrees(&repo).unwrap();
+        assert!(worktrees.iter().all(|w| w.branch.as_deref() != Some("feat/retry")));
+        assert_eq!(archive::list(&archive::dir_for(&h.state_path)).unwrap().len(), 1);
+
+        cmd_reopen(&h.ctx(), "feat/retry").unwrap();
+        assert_eq!(h.state().sessions[0].created_at, created_at);
+    }
+
+    #[test]
+    fn test_cli_parse_archive_and_reopen() {
+        let cli = Cli::parse_from(["ccm", "archive", "show", "feat"]);
+        assert!(matches!(
+            cli.command,
+            Command::Archive { command: ArchiveCommand::Show { ref name } } if name == "feat"
+        ));
+        let cli = Cli::parse_from(["ccm", "archive", "list"]);
+        assert!(matches!(
+            cli.command,
+            Command::Archive { command: ArchiveCommand::List }
+        ));
+        let cli = Cli::parse_from(["ccm", "reopen", "feat"]);
+        assert!(matches!(cli.command, Command::Reopen { ref name } if name == "feat"));
+    }
+
+    #[test]
+    fn test_gc_removes_orphans_through_provider() {
+        let h = Harness::new();
+        let repo = init_git_repo(&h);
+        let provider = worktree::Git::new(&config::GitConfig::default());
+        let ctx = Ctx {
+            config: &h.config,
+            mux: &h.mux,
+            worktrees: &provider,
+            state_path: h.state_path.clone(),
+        };
+        let kept = provider.new_worktree(&repo, Some("kept"), None).unwrap();
+        let orphan = provider.new_worktree(&repo, Some("orphan"), None).unwrap();
+        git::run(&repo, &["branch", "ccm/stale"]).unwrap();
+        git::run(&repo, &["branch", "unrelated"]).unwrap();
+        cmd_adopt(&ctx, Some(kept.worktree_path.clone()), None, None).unwrap();
+
+        let removed = cmd_gc(&ctx, Some(repo.clone()), false, &mut "n\n".as_bytes()).unwrap();
+        assert_eq!(removed, 0);
+        assert!(Path::new(&orphan.worktree_path).exists());
+
+        // Yes to the worktree, end of input (no) for the branch
+        let removed = cmd_gc(&ctx, Some(repo.clone()), false, &mut "y\n".as_bytes()).unwrap();
+        assert_eq!(removed, 1);
+        assert!(!Path::new(&orphan.worktree_path).exists());
+        assert!(git::branch_exists(&repo, "ccm/stale").unwrap());
+
+        let removed = cmd_gc(&ctx, Some(repo.clone()), true, &mut "".as_bytes()).unwrap();
+        assert_eq!(removed, 1);
+        assert!(!git::branch_exists(&repo, "ccm/stale").unwrap());
+        assert!(git::branch_exists(&repo, "unrelated").unwrap());
+        assert!(Path::new(&kept.worktree_path).exists());
+        assert_eq!(cmd_gc(&ctx, Some(repo), true, &mut "".as_bytes()).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_gc_requires_git_provider() {
+        let h = Harness::new();
+        let provider = worktree::Jj::new(&config::JjConfig::default());
+        let ctx = Ctx {
+            config: &h.config,
+            mux: &h.mux,
+            worktrees: &provider,
+            state_path: h.state_path.clone(),
+        };
+        let err = cmd_gc(&ctx, Some("/".to_string()), true, &mut "".as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("only supports git worktree providers"));
+    }
+
+    #[test]
+    fn test_cli_parse_adopt() {
+        let cli = Cli::parse_from(["ccm", "adopt", "../wt", "--name", "x"]);
+        assert!(matches!(
+            cli.command,
+            Command::Adopt { target: Some(ref t), name: Some(ref n), cwd: None } if t == "../wt" && n == "x"
+        ));
+        let cli = Cli::parse_from(["ccm", "close", "x", "--remove-worktree"]);
+        assert!(matches!(
+            cli.command,
+            Command::Close { merge: false, remove_worktree: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_restore() {
+        let cli = Cli::parse_from(["ccm", "restore", "--all"]);
+        assert!(matches!(cli.command, Command::Restore { name: None, all: true, fresh: false }));
+        assert!(Cli::try_parse_from(["ccm", "restore"]).is_err());
+        assert!(Cli::try_parse_from(["ccm", "restore", "x", "--all"]).is_err());
+    }
+
+    #[test]
+    fn test_restore_clears_dead_marker() {
+        let h = Harness::new();
+        let info = h.new_session_on_disk("feat");
+        h.mux.restart();
+        state::update_at(&h.state_path, |state| {
+            state.sessions[0].dead_since = Some(Utc::now());
+            Ok(())
+        })
+        .unwrap();
+        cmd_restore(&h.ctx(), Some(info.session_name), false, false).unwrap();
+        assert!(!h.state().sessions[0].is_dead());
+    }
+
+    #[test]
+    fn test_prune_removes_only_dead_sessions() {
+        let h = Harness::new();
+        let a = h.new_session_on_disk("a");
+        h.new_session_on_disk("b");
+        for id in h.state().sessions[0].pane_ids() {
+            h.mux.close_externally(id);
+        }
+
+        cmd_prune(&h.ctx(), false).unwrap();
+
+        let state = h.state();
+        assert_eq!(state.sessions.len(), 1);
+        assert_eq!(state.sessions[0].name, "gj/main-b");
+        // "b" was created last, so it is still the active session
+        assert_eq!(state.active_session.as_deref(), Some("gj/main-b"));
+        // Worktree is left alone by default
+        assert!(h.worktrees.discarded().is_empty());
+        assert!(std::path::Path::new(&a.worktree_path).is_dir());
+    }
+
+    #[test]
+    fn test_prune_clears_active_and_removes_worktrees() {
+        let h = Harness::new();
+        let info = h.new_session_on_disk("a");
+        h.mux.restart();
+        cmd_prune(&h.ctx(), true).unwrap();
+        let state = h.state();
+        assert!(state.sessions.is_empty());
+        assert_eq!(state.active_session, None);
+        assert_eq!(h.worktrees.discarded(), vec![info.worktree_path]);
+    }
+
+    #[test]
+    fn test_prune_list_failure_is_an_error() {
+        let h = Harness::new();
+        h.new_session("a").unwrap();
+        h.mux.set_list_unavailable(true);
+        assert!(cmd_prune(&h.ctx(), false).is_err());
+        assert_eq!(h.state().sessions.len(), 1);
+    }
+
+    #[test]
+    fn test_format_age() {
+        let secs = chrono::Duration::seconds;
+        assert_eq!(format_age(secs(-5)), "0s");
+        assert_eq!(format_age(secs(45)), "45s");
+        assert_eq!(format_age(secs(12 * 60 + 5)), "12m");
+        assert_eq!(format_age(secs(3 * 3600)), "3h");
+        assert_eq!(format_age(secs(2 * 86400 + 10)), "2d");
+    }
+
+    #[test]
+    fn test_reset_layout_rebuilds_panes() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        let before = h.state().sessions[0].clone();
+        // An extra pane the user opened in the session tab is cleaned up too
+        h.mux
+            .split_pane(info.claude_pane_id, SplitDirection::Bottom, 20, None)
+            .unwrap();
+        h.mux.close_externally(before.panes["shell"]);
+        // Run from the claude pane so the test process never kills its own pane
+        h.mux.set_current_pane(info.claude_pane_id);
+
+        cmd_reset_layout(&h.ctx()).unwrap();
+
+        let after = h.state().sessions[0].clone();
+        assert_eq!(after.claude_pane_id, before.claude_pane_id);
+        assert_eq!(after.panes.len(), 3);
+        for (name, id) in &after.panes {
+            assert_ne!(before.panes[name], *id);
+        }
+
+        let mut live = h.mux.live_pane_ids();
+        live.sort_unstable();
+        let mut expected: Vec<u64> = after.pane_ids().collect();
+        expected.sort_unstable();
+        assert_eq!(live, expected);
+        assert_eq!(h.mux.active_pane(), Some(after.claude_pane_id));
+    }
+
+    #[test]
+    fn test_reset_layout_requires_live_claude_pane() {
+        let h = Harness::new();
+        let info = h.new_session("feat").unwrap();
+        let watcher = h.state().sessions[0].panes["watcher"];
+        h.mux.close_externally(info.claude_pane_id);
+        h.mux.set_current_pane(watcher);
+        let err = cmd_reset_layout(&h.ctx()).unwrap_err();
+        assert!(err.to_string().contains("not alive"));
+        assert_eq!(h.mux.calls("kill_pane"), 0);
+    }
+
+    #[test]
+    fn test_reset_layout_unknown_pane() {
+        let h = Harness::new();
+        h.new_session("feat").unwrap();
+        h.mux.set_current_pane(999);
+        assert!(cmd_reset_layout(&h.ctx()).is_err());
     }
 }
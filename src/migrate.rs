@@ -0,0 +1,213 @@
+//! Schema migrations of state.json.
+//!
+//! Each step takes the JSON of one schema version to the next. Files written
+//! before `schema_version` existed are version 0. Add a step to `MIGRATIONS`
+//! whenever the on-disk form of `State` or `Session` changes.
+//!
+//! Steps run on every read of an old file (the TUI polls it), so they must not
+//! spawn processes. Work that does goes in `backfill`, which runs once, when
+//! the migrated state is first written.
+
+use serde_json::{Map, Value};
+
+use crate::error::CcmError;
+use crate::git;
+use crate::session::Session;
+
+type Migration = fn(&mut Map<String, Value>) -> Result<(), CcmError>;
+
+/// `MIGRATIONS[n]` takes schema version `n` to `n + 1`.
+const MIGRATIONS: [Migration; 2] = [legacy_pane_fields, repo_field];
+
+/// Schema version written by this build.
+pub const SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Schema version of a state.json document.
+pub fn version_of(state: &Value) -> u32 {
+    state
+        .get("schema_version")
+        .and_then(Value::as_u64)
+        .map_or(0, |v| v as u32)
+}
+
+/// Bring `state` up to `SCHEMA_VERSION`. A document from a newer build is
+/// left as it is; `state::update_at` refuses to write it back.
+pub fn run(state: &mut Value) -> Result<(), CcmError> {
+    let from = version_of(state);
+    let Value::Object(map) = state else {
+        return Err(CcmError::State(
+            "state.json is not a JSON object".to_string(),
+        ));
+    };
+    for (version, step) in MIGRATIONS.iter().enumerate().skip(from as usize) {
+        step(map).map_err(|e| {
+            CcmError::State(format!(
+                "migrating schema version {version} to {}: {e}",
+                version + 1
+            ))
+        })?;
+        map.insert(
+            "schema_version".to_string(),
+            Value::from(version as u32 + 1),
+        );
+    }
+    Ok(())
+}
+
+/// Sessions of `state`, for steps that rewrite each one.
+fn sessions(state: &mut Map<String, Value>) -> impl Iterator<Item = &mut Map<String, Value>> {
+    state
+        .get_mut("sessions")
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_object_mut)
+}
+
+/// 0 → 1: sessions written before the layout engine stored fixed
+/// `watcher/shell/plans_pane_id` fields instead of the `panes` map.
+fn legacy_pane_fields(state: &mut Map<String, Value>) -> Result<(), CcmError> {
+    for session in sessions(state) {
+        let mut panes = match session.remove("panes") {
+            Some(Value::Object(panes)) => panes,
+            _ => Map::new(),
+        };
+        for name in ["watcher", "shell", "plans"] {
+            if let Some(id) = session.remove(&format!("{name}_pane_id")) {
+                if !id.is_null() {
+                    panes.entry(name).or_insert(id);
+                }
+            }
+        }
+        session.insert("panes".to_string(), Value::Object(panes));
+    }
+    Ok(())
+}
+
+/// 1 → 2: sessions gained `repo`. It is optional, so the JSON stays as it is;
+/// `backfill` fills it in.
+fn repo_field(_state: &mut Map<String, Value>) -> Result<(), CcmError> {
+    Ok(())
+}
+
+/// Finish migrating `sessions` from schema version `stored` where that needs
+/// git. Called by `state::update_at` before it writes an older file back.
+pub fn backfill(sessions: &mut [Session], stored: u32) {
+    if stored < 2 {
+        // The repository of sessions created before `repo` existed, looked
+        // up from their worktree. Left unset when the worktree is gone.
+        for session in sessions.iter_mut().filter(|s| s.repo.is_none()) {
+            session.repo = git::main_worktree(&session.cwd).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state;
+    use serde_json::json;
+
+    fn step(migration: Migration, mut state: Value) -> Value {
+        migration(state.as_object_mut().unwrap()).unwrap();
+        state
+    }
+
+    #[test]
+    fn schema_version_matches_migrations() {
+        assert_eq!(SCHEMA_VERSION, 2);
+        assert_eq!(version_of(&json!({"sessions": []})), 0);
+        assert_eq!(version_of(&json!({"schema_version": 7})), 7);
+    }
+
+    #[test]
+    fn legacy_pane_fields_become_panes() {
+        let state = step(
+            legacy_pane_fields,
+            json!({"sessions": [
+                {"name": "a", "watcher_pane_id": 2, "shell_pane_id": 4, "plans_pane_id": 5},
+                {"name": "b", "watcher_pane_id": 6, "shell_pane_id": 8, "plans_pane_id": null},
+                {"name": "c", "panes": {"shell": 9}},
+            ]}),
+        );
+        assert_eq!(
+            state["sessions"],
+            json!([
+                {"name": "a", "panes": {"watcher": 2, "shell": 4, "plans": 5}},
+                {"name": "b", "panes": {"watcher": 6, "shell": 8}},
+                {"name": "c", "panes": {"shell": 9}},
+            ])
+        );
+    }
+
+    #[test]
+    fn backfill_repo_once_on_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git::test_repo(dir.path());
+        let wt = dir.path().join("repo-feat");
+        git::run(&repo, &["worktree", "add", "-q", wt.to_str().unwrap()]).unwrap();
+
+        let session = |name: &str, cwd: &str| {
+            json!({"name": name, "tab_id": 1, "claude_pane_id": 2, "cwd": cwd,
+                   "created_at": "2025-01-01T00:00:00Z"})
+        };
+        let mut kept = session("c", "/x");
+        kept["repo"] = json!("/kept");
+        let path = dir.path().join("state.json");
+        let json = json!({
+            "schema_version": 1,
+            "sessions": [
+                session("a", wt.to_str().unwrap()),
+                session("b", dir.path().join("gone").to_str().unwrap()),
+                kept,
+            ],
+            "active_session": null,
+            "version": 1,
+        });
+        std::fs::write(&path, json.to_string()).unwrap();
+
+        // Reading does not look anything up
+        let state = state::load_from(&path).unwrap();
+        assert_eq!(state.sessions[0].repo, None);
+
+        let state = state::update_at(&path, |_| Ok(())).unwrap();
+        let root = git::main_worktree(wt.to_str().unwrap()).unwrap();
+        assert_eq!(state.sessions[0].repo.as_deref(), Some(root.as_str()));
+        assert_eq!(state.sessions[1].repo, None);
+        assert_eq!(state.sessions[2].repo.as_deref(), Some("/kept"));
+
+        // Once stored as current, nothing is looked up any more
+        let mut sessions = state.sessions;
+        sessions[1].cwd = wt.to_string_lossy().into_owned();
+        backfill(&mut sessions, SCHEMA_VERSION);
+        assert_eq!(sessions[1].repo, None);
+    }
+
+    #[test]
+    fn run_applies_pending_steps() {
+        let mut state =
+            json!({"sessions": [{"name": "a", "cwd": "/nonexistent", "shell_pane_id": 4}]});
+        run(&mut state).unwrap();
+        assert_eq!(version_of(&state), SCHEMA_VERSION);
+        assert_eq!(state["sessions"][0]["panes"], json!({"shell": 4}));
+
+        // Already current: nothing to do
+        let before = state.clone();
+        run(&mut state).unwrap();
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn run_leaves_newer_schema_alone() {
+        let mut state =
+            json!({"schema_version": SCHEMA_VERSION + 1, "sessions": [{"shell_pane_id": 4}]});
+        let before = state.clone();
+        run(&mut state).unwrap();
+        assert_eq!(state, before);
+    }
+
+    #[test]
+    fn run_rejects_non_object() {
+        assert!(run(&mut json!([])).is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! In-memory multiplexer for command and TUI tests.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::{Multiplexer, PaneInfo, SplitDirection};
+use crate::error::CcmError;
+
+/// Simulated multiplexer with tabs, panes and liveness. Clones share state,
+/// so a test can keep a handle for inspection after passing a boxed copy to
+/// the code under test.
+#[derive(Clone, Default)]
+pub struct FakeMux {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakePane {
+    pub pane_id: u64,
+    pub tab_id: u64,
+    pub cwd: String,
+    pub title: String,
+    /// Program passed to split_pane (None for a plain shell).
+    pub program: Option<Vec<String>>,
+    /// The pane this one was split from, with direction and percent.
+    pub split_from: Option<(u64, SplitDirection, u32)>,
+    /// Text received through send_text, in order.
+    pub sent: Vec<String>,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_pane_id: u64,
+    next_tab_id: u64,
+    panes: Vec<FakePane>,
+    tab_titles: HashMap<u64, String>,
+    active_tab: Option<u64>,
+    active_pane: Option<u64>,
+    current_pane: Option<u64>,
+    /// operation -> number of calls so far
+    calls: HashMap<&'static str, usize>,
+    /// operation -> 1-based call numbers that fail
+    failures: HashMap<&'static str, Vec<usize>>,
+    list_unavailable: bool,
+}
+
+impl Inner {
+    /// Count a call and return an error if it was configured to fail.
+    fn check(&mut self, op: &'static str) -> Result<(), CcmError> {
+        let count = self.calls.entry(op).or_insert(0);
+        *count += 1;
+        let n = *count;
+        if self.failures.get(op).is_some_and(|f| f.contains(&n)) {
+            return Err(CcmError::WezTerm(format!("injected {op} failure")));
+        }
+        Ok(())
+    }
+
+    fn pane(&self, pane_id: u64) -> Result<&FakePane, CcmError> {
+        self.panes
+            .iter()
+            .find(|p| p.pane_id == pane_id)
+            .ok_or_else(|| CcmError::WezTerm(format!("pane {pane_id} not found")))
+    }
+
+    fn add_pane(&mut self, tab_id: u64, cwd: String) -> &mut FakePane {
+        let pane_id = self.next_pane_id;
+        self.next_pane_id += 1;
+        self.panes.push(FakePane {
+            pane_id,
+            tab_id,
+            cwd,
+            title: String::new(),
+            program: None,
+            split_from: None,
+            sent: Vec::new(),
+        });
+        self.panes.last_mut().expect("pane was just pushed")
+    }
+}
+
+impl FakeMux {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the `nth` (1-based) call of `op` fail, e.g. `fail_on("split_pane", 2)`.
+    pub fn fail_on(&self, op: &'static str, nth: usize) {
+        self.inner
+            .borrow_mut()
+            .failures
+            .entry(op)
+            .or_default()
+            .push(nth);
+    }
+
+    /// Make every `list_panes` call fail, like an unreachable mux server.
+    pub fn set_list_unavailable(&self, unavailable: bool) {
+        self.inner.borrow_mut().list_unavailable = unavailable;
+    }
+
+    /// Set the pane reported by `current_pane_id`.
+    pub fn set_current_pane(&self, pane_id: u64) {
+        self.inner.borrow_mut().current_pane = Some(pane_id);
+    }
+
+    /// Set a pane title, as a program would via OSC 0.
+    pub fn set_title(&self, pane_id: u64, title: &str) {
+        if let Some(p) = self
+            .inner
+            .borrow_mut()
+            .panes
+            .iter_mut()
+            .find(|p| p.pane_id == pane_id)
+        {
+            p.title = title.to_string();
+        }
+    }
+
+    /// Remove a pane without going through kill_pane (e.g. the user closed it).
+    pub fn close_externally(&self, pane_id: u64) {
+        self.inner.borrow_mut().panes.retain(|p| p.pane_id != pane_id);
+    }
+
+    /// Remove every pane, as if the terminal had restarted.
+    pub fn restart(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.panes.clear();
+        inner.tab_titles.clear();
+    }
+
+    pub fn pane(&self, pane_id: u64) -> Option<FakePane> {
+        self.inner.borrow().pane(pane_id).ok().cloned()
+    }
+
+    pub fn live_pane_ids(&self) -> Vec<u64> {
+        self.inner.borrow().panes.iter().map(|p| p.pane_id).collect()
+    }
+
+    pub fn panes_in_tab(&self, tab_id: u64) -> Vec<FakePane> {
+        self.inner
+            .borrow()
+            .panes
+            .iter()
+            .filter(|p| p.tab_id == tab_id)
+            .cloned()
+            .collect()
+    }
+
+    pub fn tab_title(&self, tab_id: u64) -> Option<String> {
+        self.inner.borrow().tab_titles.get(&tab_id).cloned()
+    }
+
+    pub fn active_tab(&self) -> Option<u64> {
+        self.inner.borrow().active_tab
+    }
+
+    pub fn active_pane(&self) -> Option<u64> {
+        self.inner.borrow().active_pane
+    }
+
+    pub fn calls(&self, op: &'static str) -> usize {
+        self.inner.borrow().calls.get(op).copied().unwrap_or(0)
+    }
+}
+
+impl Multiplexer for FakeMux {
+    fn spawn_tab(&self, cwd: &str) -> Result<u64, CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("spawn_tab")?;
+        let tab_id = inner.next_tab_id;
+        inner.next_tab_id += 1;
+        Ok(inner.add_pane(tab_id, cwd.to_string()).pane_id)
+    }
+
+    fn split_pane(
+        &self,
+        pane_id: u64,
+        direction: SplitDirection,
+        percent: u32,
+        program: Option<&[&str]>,
+    ) -> Result<u64, CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("split_pane")?;
+        let parent = inner.pane(pane_id)?;
+        let (tab_id, cwd) = (parent.tab_id, parent.cwd.clone());
+        let pane = inner.add_pane(tab_id, cwd);
+        pane.program = program.map(|p| p.iter().map(|s| s.to_string()).collect());
+        pane.split_from = Some((pane_id, direction, percent));
+        Ok(pane.pane_id)
+    }
+
+    fn activate_tab(&self, tab_id: u64) -> Result<(), CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("activate_tab")?;
+        if !inner.panes.iter().any(|p| p.tab_id == tab_id) {
+            return Err(CcmError::WezTerm(format!("tab {tab_id} not found")));
+        }
+        inner.active_tab = Some(tab_id);
+        Ok(())
+    }
+
+    fn activate_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("activate_pane")?;
+        inner.pane(pane_id)?;
+        inner.active_pane = Some(pane_id);
+        Ok(())
+    }
+
+    fn set_tab_title(&self, pane_id: u64, title: &str) -> Result<(), CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("set_tab_title")?;
+        let tab_id = inner.pane(pane_id)?.tab_id;
+        inner.tab_titles.insert(tab_id, title.to_string());
+        Ok(())
+    }
+
+    fn kill_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("kill_pane")?;
+        inner.pane(pane_id)?;
+        inner.panes.retain(|p| p.pane_id != pane_id);
+        Ok(())
+    }
+
+    fn list_panes(&self) -> Result<Vec<PaneInfo>, CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("list_panes")?;
+        if inner.list_unavailable {
+            return Err(CcmError::WezTerm("mux server unavailable".to_string()));
+        }
+        let active_pane = inner.active_pane;
+        Ok(inner
+            .panes
+            .iter()
+            .map(|p| PaneInfo {
+                window_id: 0,
+                tab_id: p.tab_id,
+                pane_id: p.pane_id,
+                title: p.title.clone(),
+                cwd: p.cwd.clone(),
+                is_active: active_pane == Some(p.pane_id),
+            })
+            .collect())
+    }
+
+    fn send_text(&self, pane_id: u64, text: &str) -> Result<(), CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        inner.check("send_text")?;
+        inner.pane(pane_id)?;
+        if let Some(p) = inner.panes.iter_mut().find(|p| p.pane_id == pane_id) {
+            p.sent.push(text.to_string());
+        }
+        Ok(())
+    }
+
+    fn current_pane_id(&self) -> Result<u64, CcmError> {
+        self.inner
+            .borrow()
+            .current_pane
+            .ok_or_else(|| CcmError::WezTerm("not running inside a pane".to_string()))
+    }
+}
@@ -0,0 +1,290 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use serde::Deserialize;
+
+use super::version::Requirement;
+use super::{Multiplexer, PaneInfo, SplitDirection};
+use crate::error::CcmError;
+
+/// kitty backend, driven through remote control (`kitty @`). Requires kitty
+/// 0.31.0 or newer for `kitty @ action` (checked before the first command),
+/// and `allow_remote_control` in kitty.conf, plus `listen_on` and
+/// `kitty.socket` when ccm runs outside of a kitty window. A kitty "window" is
+/// a pane; tabs are switched to the splits layout so that left/right/bottom
+/// splits work.
+pub struct Kitty {
+    binary: String,
+    socket: Option<String>,
+    version: Requirement,
+}
+
+/// `kitty @ ls` output: OS windows → tabs → windows.
+#[derive(Debug, Deserialize)]
+struct OsWindow {
+    id: u64,
+    #[serde(default)]
+    tabs: Vec<KittyTab>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KittyTab {
+    id: u64,
+    #[serde(default)]
+    windows: Vec<KittyWindow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KittyWindow {
+    id: u64,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    cwd: String,
+    #[serde(default)]
+    is_focused: bool,
+}
+
+impl Kitty {
+    pub fn new(binary: &str, socket: Option<&str>) -> Self {
+        Self {
+            binary: binary.to_string(),
+            socket: socket.map(str::to_string),
+            version: Requirement::new("kitty", (0, 31, 0), "kitty @ action"),
+        }
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut cmd = Command::new(&self.binary);
+        cmd.arg("@");
+        if let Some(ref socket) = self.socket {
+            cmd.args(["--to", socket]);
+        }
+        cmd.args(args);
+        cmd
+    }
+
+    /// Run `kitty @ <args>` and return its stdout.
+    fn run(&self, args: &[&str]) -> Result<String, CcmError> {
+        self.version.check(&self.binary).map_err(CcmError::Kitty)?;
+        let subcommand = args.first().copied().unwrap_or_default();
+        let output = self
+            .command(args)
+            .output()
+            .map_err(|e| CcmError::Kitty(format!("failed to run kitty @ {subcommand}: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::Kitty(format!("{subcommand} failed: {stderr}")));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+fn parse_window_id(s: &str) -> Result<u64, CcmError> {
+    s.trim()
+        .parse::<u64>()
+        .map_err(|e| CcmError::Kitty(format!("invalid window id '{}': {e}", s.trim())))
+}
+
+fn parse_ls(bytes: &[u8]) -> Result<Vec<PaneInfo>, CcmError> {
+    let os_windows: Vec<OsWindow> = serde_json::from_slice(bytes)
+        .map_err(|e| CcmError::Kitty(format!("failed to parse kitty @ ls output: {e}")))?;
+
+    let mut panes = Vec::new();
+    for os_window in os_windows {
+        for tab in os_window.tabs {
+            for window in tab.windows {
+                panes.push(PaneInfo {
+                    window_id: os_window.id,
+                    tab_id: tab.id,
+                    pane_id: window.id,
+                    title: window.title,
+                    cwd: window.cwd,
+                    is_active: window.is_focused,
+                });
+            }
+        }
+    }
+    Ok(panes)
+}
+
+impl Multiplexer for Kitty {
+    fn spawn_tab(&self, cwd: &str) -> Result<u64, CcmError> {
+        let stdout = self.run(&["launch", "--type=tab", "--cwd", cwd])?;
+        let window_id = parse_window_id(&stdout)?;
+        // Splits are only placed deterministically in the splits layout
+        self.run(&[
+            "goto-layout",
+            "--match",
+            &format!("window_id:{window_id}"),
+            "splits",
+        ])?;
+        Ok(window_id)
+    }
+
+    fn split_pane(
+        &self,
+        pane_id: u64,
+        direction: SplitDirection,
+        percent: u32,
+        program: Option<&[&str]>,
+    ) -> Result<u64, CcmError> {
+        let next_to = format!("id:{pane_id}");
+        // vsplit/hsplit always place the new window right of/below the target.
+        // For Left it is then swapped with the target, which leaves the split
+        // sizes in place, so it starts with the target's share.
+        let (location, swap, percent) = match direction {
+            SplitDirection::Left => ("--location=vsplit", Some("left"), 100 - percent.min(100)),
+            SplitDirection::Right => ("--location=vsplit", None, percent),
+            SplitDirection::Bottom => ("--location=hsplit", None, percent),
+        };
+        let bias = percent.to_string();
+        let mut args = vec![
+            "launch",
+            "--type=window",
+            "--next-to",
+            &next_to,
+            location,
+            "--bias",
+            &bias,
+            "--cwd=current",
+        ];
+        if let Some(prog) = program {
+            args.push("--");
+            args.extend(prog);
+        }
+
+        let stdout = self.run(&args)?;
+        let new_window_id = parse_window_id(&stdout)?;
+
+        // Only the new window moves: `move_window` swaps it with its neighbor
+        if let Some(swap) = swap {
+            if let Err(e) = self.run(&[
+                "action",
+                "--match",
+                &format!("id:{new_window_id}"),
+                "move_window",
+                swap,
+            ]) {
+                let _ = self.kill_pane(new_window_id);
+                return Err(e);
+            }
+        }
+
+        Ok(new_window_id)
+    }
+
+    fn activate_tab(&self, tab_id: u64) -> Result<(), CcmError> {
+        self.run(&["focus-tab", "--match", &format!("id:{tab_id}")])?;
+        Ok(())
+    }
+
+    fn activate_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        self.run(&["focus-window", "--match", &format!("id:{pane_id}")])?;
+        Ok(())
+    }
+
+    fn set_tab_title(&self, pane_id: u64, title: &str) -> Result<(), CcmError> {
+        self.run(&[
+            "set-tab-title",
+            "--match",
+            &format!("window_id:{pane_id}"),
+            title,
+        ])?;
+        Ok(())
+    }
+
+    fn kill_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        self.run(&["close-window", "--match", &format!("id:{pane_id}")])?;
+        Ok(())
+    }
+
+    fn list_panes(&self) -> Result<Vec<PaneInfo>, CcmError> {
+        let stdout = self.run(&["ls"])?;
+        parse_ls(stdout.as_bytes())
+    }
+
+    /// Send text via stdin: command-line text is subject to kitty's escape
+    /// processing, which would mangle shell quoting like `'\''`.
+    fn send_text(&self, pane_id: u64, text: &str) -> Result<(), CcmError> {
+        self.version.check(&self.binary).map_err(CcmError::Kitty)?;
+        let mut child = self
+            .command(&["send-text", "--match", &format!("id:{pane_id}"), "--stdin"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| CcmError::Kitty(format!("failed to run kitty @ send-text: {e}")))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin
+                .write_all(text.as_bytes())
+                .map_err(|e| CcmError::Kitty(format!("failed to write to send-text: {e}")))?;
+        }
+
+        let output = child
+            .wait_with_output()
+            .map_err(|e| CcmError::Kitty(format!("failed to wait for send-text: {e}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::Kitty(format!("send-text failed: {stderr}")));
+        }
+        Ok(())
+    }
+
+    fn current_pane_id(&self) -> Result<u64, CcmError> {
+        let value = std::env::var("KITTY_WINDOW_ID").map_err(|_| {
+            CcmError::Kitty(
+                "KITTY_WINDOW_ID environment variable not set (must run inside a kitty window)"
+                    .to_string(),
+            )
+        })?;
+        parse_window_id(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_window_id_trims() {
+        assert_eq!(parse_window_id("42\n").unwrap(), 42);
+        assert!(parse_window_id("").is_err());
+    }
+
+    #[test]
+    fn parse_ls_flattens_tabs() {
+        let json = r#"[
+            {"id": 1, "is_focused": true, "tabs": [
+                {"id": 10, "title": "ccm", "windows": [
+                    {"id": 100, "title": "claude", "cwd": "/work", "is_focused": true, "pid": 5},
+                    {"id": 101, "title": "zsh", "cwd": "/work", "is_focused": false}
+                ]},
+                {"id": 11, "title": "other", "windows": [
+                    {"id": 102, "title": "vim", "cwd": "/tmp", "is_focused": false}
+                ]}
+            ]}
+        ]"#;
+        let panes = parse_ls(json.as_bytes()).unwrap();
+        assert_eq!(panes.len(), 3);
+        assert_eq!(panes[0].window_id, 1);
+        assert_eq!(panes[0].tab_id, 10);
+        assert_eq!(panes[0].pane_id, 100);
+        assert!(panes[0].is_active);
+        assert_eq!(panes[1].title, "zsh");
+        assert_eq!(panes[2].tab_id, 11);
+        assert_eq!(panes[2].cwd, "/tmp");
+    }
+
+    #[test]
+    fn parse_ls_empty() {
+        assert!(parse_ls(b"[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_ls_invalid_json() {
+        assert!(parse_ls(b"{").is_err());
+    }
+}
@@ -0,0 +1,89 @@
+mod kitty;
+mod tmux;
+mod version;
+mod wezterm;
+mod zellij;
+
+#[cfg(test)]
+pub mod fake;
+
+use crate::config::{Config, MuxBackend};
+use crate::error::CcmError;
+
+pub use self::kitty::Kitty;
+pub use self::tmux::Tmux;
+pub use self::wezterm::WezTerm;
+pub use self::zellij::Zellij;
+
+/// A pane as reported by the multiplexer. IDs are normalized to integers
+/// (e.g. tmux `%12` becomes `12`) so that they can be stored in state.json
+/// regardless of the backend.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[allow(dead_code)]
+pub struct PaneInfo {
+    pub window_id: u64,
+    pub tab_id: u64,
+    pub pane_id: u64,
+    pub title: String,
+    pub cwd: String,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SplitDirection {
+    Left,
+    Right,
+    Bottom,
+}
+
+/// Operations ccm needs from a terminal multiplexer.
+/// Each session lives in its own tab (WezTerm tab, tmux window, ...).
+pub trait Multiplexer {
+    /// Spawn a new tab and return the pane_id of the initial pane.
+    fn spawn_tab(&self, cwd: &str) -> Result<u64, CcmError>;
+
+    /// Split a pane and return the new pane_id.
+    /// If `program` is provided, it is run in the new pane.
+    fn split_pane(
+        &self,
+        pane_id: u64,
+        direction: SplitDirection,
+        percent: u32,
+        program: Option<&[&str]>,
+    ) -> Result<u64, CcmError>;
+
+    /// Activate a tab by its tab_id.
+    fn activate_tab(&self, tab_id: u64) -> Result<(), CcmError>;
+
+    /// Activate a pane by its pane_id.
+    fn activate_pane(&self, pane_id: u64) -> Result<(), CcmError>;
+
+    /// Set the tab title using a pane_id to identify the tab.
+    fn set_tab_title(&self, pane_id: u64, title: &str) -> Result<(), CcmError>;
+
+    /// Kill a pane by pane_id.
+    fn kill_pane(&self, pane_id: u64) -> Result<(), CcmError>;
+
+    /// List all panes known to the multiplexer.
+    fn list_panes(&self) -> Result<Vec<PaneInfo>, CcmError>;
+
+    /// Send text to a pane as if typed (no bracketed paste).
+    fn send_text(&self, pane_id: u64, text: &str) -> Result<(), CcmError>;
+
+    /// Return the pane_id of the pane this process is running in.
+    fn current_pane_id(&self) -> Result<u64, CcmError>;
+}
+
+/// Build the multiplexer backend selected by `mux.backend`.
+pub fn from_config(config: &Config) -> Box<dyn Multiplexer> {
+    match config.mux.backend {
+        MuxBackend::Wezterm => Box::new(WezTerm::new(&config.wezterm.binary)),
+        MuxBackend::Tmux => Box::new(Tmux::new(&config.tmux.binary)),
+        MuxBackend::Zellij => Box::new(Zellij::new(&config.zellij.binary)),
+        MuxBackend::Kitty => Box::new(Kitty::new(
+            &config.kitty.binary,
+            config.kitty.socket.as_deref(),
+        )),
+    }
+}
@@ -0,0 +1,204 @@
+use std::process::Command;
+
+use super::{Multiplexer, PaneInfo, SplitDirection};
+use crate::error::CcmError;
+
+/// Format used by `list-panes`. The title goes last because it is the only
+/// field that may itself contain tabs.
+const LIST_FORMAT: &str =
+    "#{session_id}\t#{window_id}\t#{pane_id}\t#{pane_active}\t#{pane_current_path}\t#{pane_title}";
+
+/// tmux backend. A ccm session maps to a tmux window, and panes are created
+/// with `split-window`. tmux IDs (`$1`, `@2`, `%3`) are stored without their
+/// sigil: tmux session → `window_id`, window → `tab_id`, pane → `pane_id`.
+pub struct Tmux {
+    binary: String,
+}
+
+impl Tmux {
+    pub fn new(binary: &str) -> Self {
+        Self {
+            binary: binary.to_string(),
+        }
+    }
+
+    /// Run a tmux command and return its stdout.
+    fn run(&self, args: &[&str]) -> Result<String, CcmError> {
+        let subcommand = args.first().copied().unwrap_or_default();
+        let output = Command::new(&self.binary)
+            .args(args)
+            .output()
+            .map_err(|e| CcmError::Tmux(format!("failed to run tmux {subcommand}: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::Tmux(format!("{subcommand} failed: {stderr}")));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Parse a tmux ID such as `%12`, `@3` or `$0` into its numeric part.
+fn parse_id(s: &str) -> Result<u64, CcmError> {
+    s.trim()
+        .trim_start_matches(['%', '@', '$'])
+        .parse::<u64>()
+        .map_err(|e| CcmError::Tmux(format!("invalid tmux id '{s}': {e}")))
+}
+
+fn parse_pane_list(output: &str) -> Result<Vec<PaneInfo>, CcmError> {
+    output
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(|line| {
+            let fields: Vec<&str> = line.splitn(6, '\t').collect();
+            if fields.len() != 6 {
+                return Err(CcmError::Tmux(format!("unexpected list-panes line: {line}")));
+            }
+            Ok(PaneInfo {
+                window_id: parse_id(fields[0])?,
+                tab_id: parse_id(fields[1])?,
+                pane_id: parse_id(fields[2])?,
+                is_active: fields[3] == "1",
+                cwd: fields[4].to_string(),
+                title: fields[5].to_string(),
+            })
+        })
+        .collect()
+}
+
+impl Multiplexer for Tmux {
+    fn spawn_tab(&self, cwd: &str) -> Result<u64, CcmError> {
+        let stdout = self.run(&["new-window", "-P", "-F", "#{pane_id}", "-c", cwd])?;
+        parse_id(&stdout)
+    }
+
+    fn split_pane(
+        &self,
+        pane_id: u64,
+        direction: SplitDirection,
+        percent: u32,
+        program: Option<&[&str]>,
+    ) -> Result<u64, CcmError> {
+        let target = format!("%{pane_id}");
+        let size = format!("{percent}%");
+        let mut args = vec!["split-window", "-t", &target];
+
+        match direction {
+            SplitDirection::Left => args.extend(["-h", "-b"]),
+            SplitDirection::Right => args.push("-h"),
+            SplitDirection::Bottom => args.push("-v"),
+        }
+
+        // New panes start in the target pane's directory, like wezterm split-pane
+        args.extend([
+            "-l",
+            &size,
+            "-c",
+            "#{pane_current_path}",
+            "-P",
+            "-F",
+            "#{pane_id}",
+        ]);
+
+        if let Some(prog) = program {
+            args.push("--");
+            args.extend(prog);
+        }
+
+        let stdout = self.run(&args)?;
+        parse_id(&stdout)
+    }
+
+    fn activate_tab(&self, tab_id: u64) -> Result<(), CcmError> {
+        self.run(&["select-window", "-t", &format!("@{tab_id}")])?;
+        Ok(())
+    }
+
+    fn activate_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        self.run(&["select-pane", "-t", &format!("%{pane_id}")])?;
+        Ok(())
+    }
+
+    /// Rename the window containing the pane (this also disables automatic-rename).
+    fn set_tab_title(&self, pane_id: u64, title: &str) -> Result<(), CcmError> {
+        self.run(&["rename-window", "-t", &format!("%{pane_id}"), title])?;
+        Ok(())
+    }
+
+    fn kill_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        self.run(&["kill-pane", "-t", &format!("%{pane_id}")])?;
+        Ok(())
+    }
+
+    fn list_panes(&self) -> Result<Vec<PaneInfo>, CcmError> {
+        let stdout = self.run(&["list-panes", "-a", "-F", LIST_FORMAT])?;
+        parse_pane_list(&stdout)
+    }
+
+    /// Send text literally (`-l`) so that key names like `Enter` are not interpreted.
+    fn send_text(&self, pane_id: u64, text: &str) -> Result<(), CcmError> {
+        self.run(&["send-keys", "-t", &format!("%{pane_id}"), "-l", text])?;
+        Ok(())
+    }
+
+    fn current_pane_id(&self) -> Result<u64, CcmError> {
+        let value = std::env::var("TMUX_PANE").map_err(|_| {
+            CcmError::Tmux(
+                "TMUX_PANE environment variable not set (must run inside a tmux pane)".to_string(),
+            )
+        })?;
+        parse_id(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_id_strips_sigils() {
+        assert_eq!(parse_id("%12").unwrap(), 12);
+        assert_eq!(parse_id("@3").unwrap(), 3);
+        assert_eq!(parse_id("$0").unwrap(), 0);
+        assert_eq!(parse_id("%7\n").unwrap(), 7);
+    }
+
+    #[test]
+    fn parse_id_invalid() {
+        assert!(parse_id("").is_err());
+        assert!(parse_id("%abc").is_err());
+    }
+
+    #[test]
+    fn parse_pane_list_basic() {
+        let output = "$0\t@1\t%2\t1\t/home/user\tzsh\n$0\t@1\t%3\t0\t/tmp\tClaude Code\n";
+        let panes = parse_pane_list(output).unwrap();
+        assert_eq!(panes.len(), 2);
+        assert_eq!(panes[0].window_id, 0);
+        assert_eq!(panes[0].tab_id, 1);
+        assert_eq!(panes[0].pane_id, 2);
+        assert!(panes[0].is_active);
+        assert_eq!(panes[0].cwd, "/home/user");
+        assert_eq!(panes[1].pane_id, 3);
+        assert!(!panes[1].is_active);
+        assert_eq!(panes[1].title, "Claude Code");
+    }
+
+    #[test]
+    fn parse_pane_list_title_with_tab() {
+        let output = "$0\t@1\t%2\t0\t/\ta\tb\n";
+        let panes = parse_pane_list(output).unwrap();
+        assert_eq!(panes[0].title, "a\tb");
+    }
+
+    #[test]
+    fn parse_pane_list_empty() {
+        assert!(parse_pane_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn parse_pane_list_malformed_line() {
+        assert!(parse_pane_list("$0\t@1\n").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::cell::OnceCell;
+use std::process::Command;
+
+/// A `major.minor.patch` version.
+pub type Version = (u32, u32, u32);
+
+/// Minimum version of a backend CLI, checked the first time the backend runs
+/// it and remembered for the rest of the process.
+pub struct Requirement {
+    name: &'static str,
+    min: Version,
+    /// What the minimum version is needed for, for the error message.
+    needed_for: &'static str,
+    checked: OnceCell<Result<(), String>>,
+}
+
+impl Requirement {
+    pub const fn new(name: &'static str, min: Version, needed_for: &'static str) -> Self {
+        Self {
+            name,
+            min,
+            needed_for,
+            checked: OnceCell::new(),
+        }
+    }
+
+    /// Run `binary --version` (once) and fail if it is older than required.
+    pub fn check(&self, binary: &str) -> Result<(), String> {
+        self.checked
+            .get_or_init(|| {
+                let output = Command::new(binary)
+                    .arg("--version")
+                    .output()
+                    .map_err(|e| format!("failed to run {binary} --version: {e}"))?;
+                self.check_output(&String::from_utf8_lossy(&output.stdout))
+            })
+            .clone()
+    }
+
+    fn check_output(&self, output: &str) -> Result<(), String> {
+        let (name, (major, minor, patch)) = (self.name, self.min);
+        let found = parse(output)
+            .ok_or_else(|| format!("could not read the {name} version from '{}'", output.trim()))?;
+        if found < self.min {
+            let (a, b, c) = found;
+            return Err(format!(
+                "{name} {a}.{b}.{c} is too old; ccm needs {name} {major}.{minor}.{patch} or newer ({})",
+                self.needed_for
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// The first `major.minor[.patch]` in `output`, e.g. `zellij 0.44.0` or
+/// `kitty 0.31.0 created by Kovid Goyal`.
+fn parse(output: &str) -> Option<Version> {
+    output.split_whitespace().find_map(|word| {
+        let mut parts = word.trim_start_matches('v').splitn(3, '.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        // Pre-release or build suffixes such as `1-rc1` are ignored
+        let patch = parts.next().map_or(Some(0), |p| {
+            let digits: String = p.chars().take_while(char::is_ascii_digit).collect();
+            digits.parse().ok()
+        })?;
+        Some((major, minor, patch))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_finds_version() {
+        assert_eq!(parse("zellij 0.44.0\n"), Some((0, 44, 0)));
+        assert_eq!(
+            parse("kitty 0.35.2 created by Kovid Goyal"),
+            Some((0, 35, 2))
+        );
+        assert_eq!(parse("tool v1.2"), Some((1, 2, 0)));
+        assert_eq!(parse("zellij 0.44.1-rc1"), Some((0, 44, 1)));
+        assert_eq!(parse("no version here"), None);
+    }
+
+    #[test]
+    fn check_output_compares_with_minimum() {
+        let req = Requirement::new("zellij", (0, 44, 0), "list-panes --json");
+        assert!(req.check_output("zellij 0.44.0").is_ok());
+        assert!(req.check_output("zellij 1.0.0").is_ok());
+        let err = req.check_output("zellij 0.43.1").unwrap_err();
+        assert_eq!(
+            err,
+            "zellij 0.43.1 is too old; ccm needs zellij 0.44.0 or newer (list-panes --json)"
+        );
+        assert!(req.check_output("").unwrap_err().contains("could not read"));
+    }
+
+    #[test]
+    fn check_is_remembered() {
+        let req = Requirement::new("missing", (1, 0, 0), "tests");
+        let err = req.check("/nonexistent/ccm-test-binary").unwrap_err();
+        assert!(err.contains("failed to run"));
+        assert_eq!(req.check("true").unwrap_err(), err);
+    }
+}
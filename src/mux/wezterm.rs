@@ -0,0 +1,263 @@
+use std::process::Command;
+
+use super::{Multiplexer, PaneInfo, SplitDirection};
+use crate::error::CcmError;
+
+/// WezTerm backend, driven through `wezterm cli`.
+pub struct WezTerm {
+    binary: String,
+}
+
+impl WezTerm {
+    pub fn new(binary: &str) -> Self {
+        Self {
+            binary: binary.to_string(),
+        }
+    }
+}
+
+impl Multiplexer for WezTerm {
+    fn spawn_tab(&self, cwd: &str) -> Result<u64, CcmError> {
+        let output = Command::new(&self.binary)
+            .args(["cli", "spawn", "--cwd", cwd])
+            .output()
+            .map_err(|e| CcmError::WezTerm(format!("failed to run wezterm cli spawn: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("spawn failed: {stderr}")));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout
+            .trim()
+            .parse::<u64>()
+            .map_err(|e| CcmError::WezTerm(format!("failed to parse pane_id from spawn: {e}")))
+    }
+
+    fn split_pane(
+        &self,
+        pane_id: u64,
+        direction: SplitDirection,
+        percent: u32,
+        program: Option<&[&str]>,
+    ) -> Result<u64, CcmError> {
+        let mut args = vec![
+            "cli".to_string(),
+            "split-pane".to_string(),
+            "--pane-id".to_string(),
+            pane_id.to_string(),
+        ];
+
+        match direction {
+            SplitDirection::Left => args.push("--left".to_string()),
+            SplitDirection::Right => args.push("--right".to_string()),
+            SplitDirection::Bottom => args.push("--bottom".to_string()),
+        }
+
+        args.push("--percent".to_string());
+        args.push(percent.to_string());
+
+        if let Some(prog) = program {
+            args.push("--".to_string());
+            for p in prog {
+                args.push(p.to_string());
+            }
+        }
+
+        let output = Command::new(&self.binary)
+            .args(&args)
+            .output()
+            .map_err(|e| {
+                CcmError::WezTerm(format!("failed to run wezterm cli split-pane: {e}"))
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("split-pane failed: {stderr}")));
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        stdout.trim().parse::<u64>().map_err(|e| {
+            CcmError::WezTerm(format!("failed to parse pane_id from split-pane: {e}"))
+        })
+    }
+
+    fn activate_tab(&self, tab_id: u64) -> Result<(), CcmError> {
+        let output = Command::new(&self.binary)
+            .args(["cli", "activate-tab", "--tab-id", &tab_id.to_string()])
+            .output()
+            .map_err(|e| {
+                CcmError::WezTerm(format!("failed to run wezterm cli activate-tab: {e}"))
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("activate-tab failed: {stderr}")));
+        }
+        Ok(())
+    }
+
+    fn activate_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        let output = Command::new(&self.binary)
+            .args(["cli", "activate-pane", "--pane-id", &pane_id.to_string()])
+            .output()
+            .map_err(|e| {
+                CcmError::WezTerm(format!("failed to run wezterm cli activate-pane: {e}"))
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("activate-pane failed: {stderr}")));
+        }
+        Ok(())
+    }
+
+    fn set_tab_title(&self, pane_id: u64, title: &str) -> Result<(), CcmError> {
+        let output = Command::new(&self.binary)
+            .args([
+                "cli",
+                "set-tab-title",
+                "--pane-id",
+                &pane_id.to_string(),
+                title,
+            ])
+            .output()
+            .map_err(|e| {
+                CcmError::WezTerm(format!("failed to run wezterm cli set-tab-title: {e}"))
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!(
+                "set-tab-title failed: {stderr}"
+            )));
+        }
+        Ok(())
+    }
+
+    fn kill_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        let output = Command::new(&self.binary)
+            .args(["cli", "kill-pane", "--pane-id", &pane_id.to_string()])
+            .output()
+            .map_err(|e| CcmError::WezTerm(format!("failed to run wezterm cli kill-pane: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("kill-pane failed: {stderr}")));
+        }
+        Ok(())
+    }
+
+    fn list_panes(&self) -> Result<Vec<PaneInfo>, CcmError> {
+        let output = Command::new(&self.binary)
+            .args(["cli", "list", "--format", "json"])
+            .output()
+            .map_err(|e| CcmError::WezTerm(format!("failed to run wezterm cli list: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("list failed: {stderr}")));
+        }
+
+        let panes: Vec<PaneInfo> = serde_json::from_slice(&output.stdout)
+            .map_err(|e| CcmError::WezTerm(format!("failed to parse pane list: {e}")))?;
+
+        Ok(panes)
+    }
+
+    /// Send text to a pane (with --no-paste to avoid bracketed paste).
+    fn send_text(&self, pane_id: u64, text: &str) -> Result<(), CcmError> {
+        let output = Command::new(&self.binary)
+            .args([
+                "cli",
+                "send-text",
+                "--pane-id",
+                &pane_id.to_string(),
+                "--no-paste",
+                text,
+            ])
+            .output()
+            .map_err(|e| CcmError::WezTerm(format!("failed to run wezterm cli send-text: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::WezTerm(format!("send-text failed: {stderr}")));
+        }
+        Ok(())
+    }
+
+    fn current_pane_id(&self) -> Result<u64, CcmError> {
+        let value = std::env::var("WEZTERM_PANE").map_err(|_| {
+            CcmError::WezTerm(
+                "WEZTERM_PANE environment variable not set (must run inside a WezTerm pane)"
+                    .to_string(),
+            )
+        })?;
+        value
+            .parse()
+            .map_err(|_| CcmError::WezTerm(format!("WEZTERM_PANE is not a valid pane ID: {value}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pane_info_deserialize() {
+        let json = r#"{
+            "window_id": 0,
+            "tab_id": 1,
+            "pane_id": 2,
+            "title": "zsh",
+            "cwd": "/home/user",
+            "is_active": true
+        }"#;
+        let info: PaneInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.pane_id, 2);
+        assert_eq!(info.title, "zsh");
+        assert!(info.is_active);
+    }
+
+    #[test]
+    fn pane_info_array_deserialize() {
+        let json = r#"[
+            {"window_id":0,"tab_id":1,"pane_id":2,"title":"a","cwd":"/","is_active":true},
+            {"window_id":0,"tab_id":1,"pane_id":3,"title":"b","cwd":"/tmp","is_active":false}
+        ]"#;
+        let panes: Vec<PaneInfo> = serde_json::from_str(json).unwrap();
+        assert_eq!(panes.len(), 2);
+        assert_eq!(panes[0].pane_id, 2);
+        assert_eq!(panes[1].pane_id, 3);
+    }
+
+    #[test]
+    fn pane_info_extra_fields_ignored() {
+        let json = r#"{
+            "window_id": 0,
+            "tab_id": 1,
+            "pane_id": 2,
+            "title": "zsh",
+            "cwd": "/",
+            "is_active": false,
+            "unknown_field": "ignored",
+            "another": 42
+        }"#;
+        let info: PaneInfo = serde_json::from_str(json).unwrap();
+        assert_eq!(info.pane_id, 2);
+    }
+
+    #[test]
+    fn pane_info_missing_field_errors() {
+        let json = r#"{
+            "window_id": 0,
+            "tab_id": 1,
+            "title": "zsh",
+            "cwd": "/",
+            "is_active": false
+        }"#;
+        let result = serde_json::from_str::<PaneInfo>(json);
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use std::process::Command;
+
+use serde::Deserialize;
+
+use super::version::Requirement;
+use super::{Multiplexer, PaneInfo, SplitDirection};
+use crate::error::CcmError;
+
+/// Zellij backend, driven through `zellij action`. Requires zellij 0.44.0 or
+/// newer for `new-pane --pane-id`, `list-panes --json` and `rename-tab-by-id`;
+/// the version is checked before the first action. A ccm session maps to a
+/// zellij tab. Terminal panes are addressed as `terminal_<id>`; plugin panes
+/// are never created by ccm and are ignored when listing.
+///
+/// `zellij action new-pane` has no split ratio for tiled panes, so the pane is
+/// created with zellij's default split and then grown or shrunk with `resize`.
+pub struct Zellij {
+    binary: String,
+    version: Requirement,
+}
+
+/// One entry of `zellij action list-panes --json`.
+#[derive(Debug, Deserialize)]
+struct ZellijPane {
+    id: u64,
+    #[serde(default)]
+    is_plugin: bool,
+    #[serde(default)]
+    is_focused: bool,
+    #[serde(default)]
+    title: String,
+    tab_id: u64,
+    #[serde(default)]
+    pane_cwd: Option<String>,
+}
+
+impl Zellij {
+    pub fn new(binary: &str) -> Self {
+        Self {
+            binary: binary.to_string(),
+            version: Requirement::new(
+                "zellij",
+                (0, 44, 0),
+                "new-pane --pane-id, list-panes --json, rename-tab-by-id",
+            ),
+        }
+    }
+
+    /// Run `zellij action <args>` and return its stdout.
+    fn action(&self, args: &[&str]) -> Result<String, CcmError> {
+        self.version.check(&self.binary).map_err(CcmError::Zellij)?;
+        let subcommand = args.first().copied().unwrap_or_default();
+        let output = Command::new(&self.binary)
+            .arg("action")
+            .args(args)
+            .output()
+            .map_err(|e| {
+                CcmError::Zellij(format!("failed to run zellij action {subcommand}: {e}"))
+            })?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::Zellij(format!("{subcommand} failed: {stderr}")));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+fn pane_ref(pane_id: u64) -> String {
+    format!("terminal_{pane_id}")
+}
+
+/// Parse a pane id as printed by `new-pane`/`new-tab` (`terminal_5` or `5`).
+fn parse_pane_id(s: &str) -> Result<u64, CcmError> {
+    let trimmed = s.trim();
+    trimmed
+        .strip_prefix("terminal_")
+        .unwrap_or(trimmed)
+        .parse::<u64>()
+        .map_err(|e| CcmError::Zellij(format!("invalid pane id '{trimmed}': {e}")))
+}
+
+fn parse_pane_list(bytes: &[u8]) -> Result<Vec<PaneInfo>, CcmError> {
+    let panes: Vec<ZellijPane> = serde_json::from_slice(bytes)
+        .map_err(|e| CcmError::Zellij(format!("failed to parse pane list: {e}")))?;
+    Ok(panes
+        .into_iter()
+        .filter(|p| !p.is_plugin)
+        .map(|p| PaneInfo {
+            // zellij is addressed per session, so there is no window level
+            window_id: 0,
+            tab_id: p.tab_id,
+            pane_id: p.id,
+            title: p.title,
+            cwd: p.pane_cwd.unwrap_or_default(),
+            is_active: p.is_focused,
+        })
+        .collect())
+}
+
+/// zellij splits in half; resize by the difference from 50% to honor `percent`.
+/// Returns the resize direction (`increase`/`decrease`) and the number of steps,
+/// where one step is zellij's default 5% resize increment.
+fn resize_steps(percent: u32) -> Option<(&'static str, u32)> {
+    let percent = percent.min(100);
+    match percent.cmp(&50) {
+        std::cmp::Ordering::Equal => None,
+        std::cmp::Ordering::Greater => Some(("increase", (percent - 50) / 5)),
+        std::cmp::Ordering::Less => Some(("decrease", (50 - percent) / 5)),
+    }
+    .filter(|(_, steps)| *steps > 0)
+}
+
+impl Multiplexer for Zellij {
+    fn spawn_tab(&self, cwd: &str) -> Result<u64, CcmError> {
+        let stdout = self.action(&["new-tab", "--cwd", cwd])?;
+        parse_pane_id(&stdout)
+    }
+
+    fn split_pane(
+        &self,
+        pane_id: u64,
+        direction: SplitDirection,
+        percent: u32,
+        program: Option<&[&str]>,
+    ) -> Result<u64, CcmError> {
+        let target = pane_ref(pane_id);
+        // The border shared with the target pane is the one to move when resizing
+        let (direction, border) = match direction {
+            SplitDirection::Left => ("left", "right"),
+            SplitDirection::Right => ("right", "left"),
+            SplitDirection::Bottom => ("down", "up"),
+        };
+        let mut args = vec![
+            "new-pane",
+            "--pane-id",
+            &target,
+            "--direction",
+            direction,
+        ];
+        if let Some(prog) = program {
+            // Close the pane when the program exits, like wezterm/tmux do
+            args.push("--close-on-exit");
+            args.push("--");
+            args.extend(prog);
+        }
+
+        let stdout = self.action(&args)?;
+        let new_pane_id = parse_pane_id(&stdout)?;
+
+        // Best-effort: a pane that can't be resized is still usable
+        if let Some((resize, steps)) = resize_steps(percent) {
+            let new_ref = pane_ref(new_pane_id);
+            for _ in 0..steps {
+                if self
+                    .action(&["resize", "--pane-id", &new_ref, resize, border])
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }
+
+        Ok(new_pane_id)
+    }
+
+    fn activate_tab(&self, tab_id: u64) -> Result<(), CcmError> {
+        self.action(&["go-to-tab-by-id", &tab_id.to_string()])?;
+        Ok(())
+    }
+
+    fn activate_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        self.action(&["focus-pane-id", &pane_ref(pane_id)])?;
+        Ok(())
+    }
+
+    fn set_tab_title(&self, pane_id: u64, title: &str) -> Result<(), CcmError> {
+        let tab_id = self
+            .list_panes()?
+            .into_iter()
+            .find(|p| p.pane_id == pane_id)
+            .map(|p| p.tab_id)
+            .ok_or_else(|| CcmError::Zellij(format!("pane {} not found", pane_ref(pane_id))))?;
+        self.action(&["rename-tab-by-id", &tab_id.to_string(), title])?;
+        Ok(())
+    }
+
+    fn kill_pane(&self, pane_id: u64) -> Result<(), CcmError> {
+        self.action(&["close-pane", "--pane-id", &pane_ref(pane_id)])?;
+        Ok(())
+    }
+
+    fn list_panes(&self) -> Result<Vec<PaneInfo>, CcmError> {
+        let stdout = self.action(&["list-panes", "--json"])?;
+        parse_pane_list(stdout.as_bytes())
+    }
+
+    fn send_text(&self, pane_id: u64, text: &str) -> Result<(), CcmError> {
+        self.action(&["write-chars", "--pane-id", &pane_ref(pane_id), text])?;
+        Ok(())
+    }
+
+    fn current_pane_id(&self) -> Result<u64, CcmError> {
+        let value = std::env::var("ZELLIJ_PANE_ID").map_err(|_| {
+            CcmError::Zellij(
+                "ZELLIJ_PANE_ID environment variable not set (must run inside a zellij pane)"
+                    .to_string(),
+            )
+        })?;
+        parse_pane_id(&value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_pane_id_variants() {
+        assert_eq!(parse_pane_id("terminal_5\n").unwrap(), 5);
+        assert_eq!(parse_pane_id("12").unwrap(), 12);
+        assert!(parse_pane_id("plugin_3").is_err());
+        assert!(parse_pane_id("").is_err());
+    }
+
+    #[test]
+    fn parse_pane_list_skips_plugins() {
+        let json = r#"[
+            {"id":0,"is_plugin":true,"is_focused":false,"title":"tab-bar","tab_id":0},
+            {"id":1,"is_plugin":false,"is_focused":true,"title":"claude","tab_id":0,"pane_cwd":"/work"},
+            {"id":2,"is_plugin":false,"is_focused":false,"title":"zsh","tab_id":1}
+        ]"#;
+        let panes = parse_pane_list(json.as_bytes()).unwrap();
+        assert_eq!(panes.len(), 2);
+        assert_eq!(panes[0].pane_id, 1);
+        assert_eq!(panes[0].cwd, "/work");
+        assert!(panes[0].is_active);
+        assert_eq!(panes[1].pane_id, 2);
+        assert_eq!(panes[1].tab_id, 1);
+        assert_eq!(panes[1].cwd, "");
+    }
+
+    #[test]
+    fn parse_pane_list_invalid_json() {
+        assert!(parse_pane_list(b"not json").is_err());
+    }
+
+    #[test]
+    fn resize_steps_from_half() {
+        assert_eq!(resize_steps(50), None);
+        assert_eq!(resize_steps(52), None);
+        assert_eq!(resize_steps(70), Some(("increase", 4)));
+        assert_eq!(resize_steps(20), Some(("decrease", 6)));
+        assert_eq!(resize_steps(30), Some(("decrease", 4)));
+    }
+}
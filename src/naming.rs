@@ -0,0 +1,200 @@
+use chrono::{DateTime, Utc};
+use regex::Regex;
+
+use crate::error::CcmError;
+
+/// Values substituted into `naming.template`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NameVars {
+    /// `$USER`.
+    pub user: String,
+    /// `YYYYMMDD` of the creation time.
+    pub date: String,
+    /// Ticket id found in the plan by `naming.ticket_pattern`.
+    pub ticket: Option<String>,
+    /// The name given to `ccm new`, or the one derived from the plan's first
+    /// line. Falls back to `rand` when there is none.
+    pub slug: Option<String>,
+    /// Random lowercase alphanumeric suffix.
+    pub rand: String,
+}
+
+impl NameVars {
+    pub fn new(now: DateTime<Utc>, rand: String) -> Self {
+        Self {
+            user: std::env::var("USER").unwrap_or_default(),
+            date: now.format("%Y%m%d").to_string(),
+            ticket: None,
+            slug: None,
+            rand,
+        }
+    }
+}
+
+/// Expand `{user}`, `{date}`, `{ticket}`, `{slug}` and `{rand}` in `template`
+/// and clean the result up into a valid branch name. Separators left dangling
+/// by an empty placeholder (no ticket in the plan) are dropped.
+pub fn render(template: &str, vars: &NameVars) -> Result<String, CcmError> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            return Err(CcmError::Naming(format!("unclosed '{{' in '{template}'")));
+        };
+        let value = match &rest[start + 1..start + len] {
+            "user" => vars.user.as_str(),
+            "date" => vars.date.as_str(),
+            "ticket" => vars.ticket.as_deref().unwrap_or_default(),
+            "slug" => vars.slug.as_deref().unwrap_or(&vars.rand),
+            "rand" => vars.rand.as_str(),
+            other => {
+                return Err(CcmError::Naming(format!(
+                    "unknown placeholder {{{other}}} in '{template}'"
+                )))
+            }
+        };
+        out.push_str(value);
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    Ok(clean(&out))
+}
+
+/// Make `name` a valid git branch name: characters other than letters and
+/// digits (of any script), `-`, `_` and `.` become `-`, runs of `-` and `.`
+/// collapse, and empty `/`-separated components are dropped.
+pub fn clean(name: &str) -> String {
+    name.split('/')
+        .map(|component| {
+            let mut out = String::with_capacity(component.len());
+            for c in component.chars() {
+                let c = if c.is_alphanumeric() || c == '_' || c == '.' {
+                    c
+                } else {
+                    '-'
+                };
+                if (c == '-' || c == '.') && out.ends_with(c) {
+                    continue;
+                }
+                out.push(c);
+            }
+            out.trim_matches(|c| c == '-' || c == '.').to_string()
+        })
+        .filter(|component| !component.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// First match of `pattern` in `text`, or its first capture group if it has one.
+pub fn find_ticket(pattern: &str, text: &str) -> Result<Option<String>, CcmError> {
+    let re = Regex::new(pattern)
+        .map_err(|e| CcmError::Naming(format!("invalid ticket_pattern '{pattern}': {e}")))?;
+    Ok(re.captures(text).and_then(|caps| {
+        caps.get(1)
+            .or_else(|| caps.get(0))
+            .map(|m| m.as_str().to_string())
+    }))
+}
+
+/// `name`, or `name-2`, `name-3`, ... for the first one not `taken`.
+pub fn unique(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|n| format!("{name}-{n}"))
+        .find(|candidate| !taken(candidate))
+        .expect("unbounded counter")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> NameVars {
+        NameVars {
+            user: "alice".to_string(),
+            date: "20250102".to_string(),
+            ticket: Some("PROJ-42".to_string()),
+            slug: Some("add-login".to_string()),
+            rand: "x1y2z3".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_substitutes_placeholders() {
+        let v = vars();
+        assert_eq!(render("{slug}", &v).unwrap(), "add-login");
+        assert_eq!(
+            render("{user}/{ticket}-{slug}", &v).unwrap(),
+            "alice/PROJ-42-add-login"
+        );
+        assert_eq!(render("{date}-{rand}", &v).unwrap(), "20250102-x1y2z3");
+    }
+
+    #[test]
+    fn render_drops_separators_of_empty_placeholders() {
+        let v = NameVars {
+            ticket: None,
+            ..vars()
+        };
+        assert_eq!(
+            render("{user}/{ticket}-{slug}", &v).unwrap(),
+            "alice/add-login"
+        );
+        let v = NameVars {
+            user: String::new(),
+            ..v
+        };
+        assert_eq!(render("{user}/{ticket}-{slug}", &v).unwrap(), "add-login");
+    }
+
+    #[test]
+    fn render_falls_back_to_rand_without_slug() {
+        let v = NameVars {
+            slug: None,
+            ..vars()
+        };
+        assert_eq!(render("{slug}", &v).unwrap(), "x1y2z3");
+    }
+
+    #[test]
+    fn render_rejects_bad_templates() {
+        let err = render("{user}-{name}", &vars()).unwrap_err();
+        assert!(err.to_string().contains("unknown placeholder {name}"));
+        assert!(render("{slug", &vars()).is_err());
+    }
+
+    #[test]
+    fn clean_makes_valid_branch_names() {
+        assert_eq!(clean("Fix bug #12"), "Fix-bug-12");
+        assert_eq!(clean("a..b//c--d"), "a.b/c-d");
+        assert_eq!(clean("-/.hidden./-x-"), "hidden/x");
+        assert_eq!(clean(""), "");
+        assert_eq!(clean("認証 機能~実装"), "認証-機能-実装");
+    }
+
+    #[test]
+    fn find_ticket_uses_first_group() {
+        let text = "# Fix login\n\nSee PROJ-123 and PROJ-9";
+        assert_eq!(
+            find_ticket(r"[A-Z][A-Z0-9]+-\d+", text).unwrap().as_deref(),
+            Some("PROJ-123")
+        );
+        assert_eq!(
+            find_ticket(r"#(\d+)", "closes #77").unwrap().as_deref(),
+            Some("77")
+        );
+        assert_eq!(find_ticket(r"[A-Z]+-\d+", "no ticket").unwrap(), None);
+        assert!(find_ticket("(", "x").is_err());
+    }
+
+    #[test]
+    fn unique_appends_counter() {
+        let existing = ["feat", "feat-2"];
+        let taken = |name: &str| existing.contains(&name);
+        assert_eq!(unique("feat", taken), "feat-3");
+        assert_eq!(unique("other", taken), "other");
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+
+use crate::error::CcmError;
+use crate::git;
+use crate::session::Session;
+
+/// Files a session has touched relative to its base, in repo-relative paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionChanges {
+    pub name: String,
+    /// Common git dir; sessions are only compared within the same repository.
+    pub repo: PathBuf,
+    pub files: BTreeSet<String>,
+}
+
+/// Two sessions of the same repository that changed the same files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    pub sessions: (String, String),
+    pub files: Vec<String>,
+}
+
+/// Compute the changed-file set of `session`: committed changes since the
+/// merge-base with its base, uncommitted changes and untracked files.
+/// ccm's own `.ccm/` directory (plans) is ignored.
+pub fn session_changes(session: &Session) -> Result<SessionChanges, CcmError> {
+    let cwd = session.cwd.as_str();
+    let repo = git::run(cwd, &["rev-parse", "--path-format=absolute", "--git-common-dir"])?;
+    let base = git::session_base(session)?;
+    let merge_base = git::run(cwd, &["merge-base", &base, "HEAD"])?;
+
+    let diff = git::run(cwd, &["diff", "--name-only", &merge_base])?;
+    let untracked = git::run(cwd, &["ls-files", "--others", "--exclude-standard"])?;
+    let files = diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|f| !f.is_empty() && !f.starts_with(".ccm/"))
+        .map(str::to_string)
+        .collect();
+
+    Ok(SessionChanges {
+        name: session.name.clone(),
+        repo: PathBuf::from(repo),
+        files,
+    })
+}
+
+/// Changed files of every session. Sessions whose changes cannot be computed
+/// (worktree gone, not a git checkout) are returned separately with the error.
+pub fn collect(sessions: &[Session]) -> (Vec<SessionChanges>, Vec<(String, CcmError)>) {
+    let mut changes = Vec::new();
+    let mut failed = Vec::new();
+    for session in sessions {
+        match session_changes(session) {
+            Ok(c) => changes.push(c),
+            Err(e) => failed.push((session.name.clone(), e)),
+        }
+    }
+    (changes, failed)
+}
+
+/// Every pair of sessions in the same repository whose changed files
+/// intersect, in the order the sessions were given.
+pub fn find_overlaps(changes: &[SessionChanges]) -> Vec<Overlap> {
+    let mut overlaps = Vec::new();
+    for (i, a) in changes.iter().enumerate() {
+        for b in &changes[i + 1..] {
+            if a.repo != b.repo {
+                continue;
+            }
+            let files: Vec<String> = a.files.intersection(&b.files).cloned().collect();
+            if !files.is_empty() {
+                overlaps.push(Overlap {
+                    sessions: (a.name.clone(), b.name.clone()),
+                    files,
+                });
+            }
+        }
+    }
+    overlaps
+}
+
+/// For each session, the other sessions it overlaps with.
+pub fn overlapping_sessions(overlaps: &[Overlap]) -> BTreeMap<String, Vec<String>> {
+    let mut map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for overlap in overlaps {
+        let (a, b) = &overlap.sessions;
+        map.entry(a.clone()).or_default().push(b.clone());
+        map.entry(b.clone()).or_default().push(a.clone());
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    fn changes(name: &str, repo: &str, files: &[&str]) -> SessionChanges {
+        SessionChanges {
+            name: name.to_string(),
+            repo: PathBuf::from(repo),
+            files: files.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn find_overlaps_pairs_within_repo() {
+        let all = [
+            changes("a", "/r/.git", &["src/main.rs", "README"]),
+            changes("b", "/r/.git", &["src/main.rs", "src/lib.rs"]),
+            changes("c", "/r/.git", &["docs/x.md"]),
+            changes("d", "/other/.git", &["src/main.rs"]),
+        ];
+        let overlaps = find_overlaps(&all);
+        assert_eq!(
+            overlaps,
+            [Overlap {
+                sessions: ("a".to_string(), "b".to_string()),
+                files: vec!["src/main.rs".to_string()],
+            }]
+        );
+
+        let map = overlapping_sessions(&overlaps);
+        assert_eq!(map["a"], ["b"]);
+        assert_eq!(map["b"], ["a"]);
+        assert!(!map.contains_key("c"));
+    }
+
+    fn git_session(name: &str, cwd: &Path, base: Option<&str>) -> Session {
+        serde_json::from_value(serde_json::json!({
+            "name": name,
+            "tab_id": 1,
+            "claude_pane_id": 1,
+            "cwd": cwd.to_string_lossy(),
+            "base_ref": base,
+            "created_at": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn session_changes_covers_commits_edits_and_untracked() {
+        let dir = tempfile::tempdir().unwrap();
+        let r = git::test_repo(dir.path());
+        let repo = Path::new(&r);
+        std::fs::write(repo.join("a.txt"), "a\n").unwrap();
+        std::fs::write(repo.join("b.txt"), "b\n").unwrap();
+        git::run(&r, &["add", "."]).unwrap();
+        git::run(&r, &["commit", "-q", "-m", "files"]).unwrap();
+
+        let wt = dir.path().join("wt");
+        let w = wt.to_string_lossy().into_owned();
+        git::run(&r, &["worktree", "add", "-q", "-b", "feat", &w]).unwrap();
+        std::fs::write(wt.join("a.txt"), "changed\n").unwrap();
+        git::run(&w, &["commit", "-q", "-am", "edit a"]).unwrap();
+        std::fs::write(wt.join("b.txt"), "dirty\n").unwrap();
+        std::fs::write(wt.join("new.txt"), "new\n").unwrap();
+        std::fs::create_dir_all(wt.join(".ccm/plans")).unwrap();
+        std::fs::write(wt.join(".ccm/plans/init.md"), "plan\n").unwrap();
+        // Later commits on main are not the session's changes
+        std::fs::write(repo.join("main-only.txt"), "m\n").unwrap();
+        git::run(&r, &["add", "."]).unwrap();
+        git::run(&r, &["commit", "-q", "-m", "main"]).unwrap();
+
+        let changes = session_changes(&git_session("feat", &wt, None)).unwrap();
+        let files: Vec<&str> = changes.files.iter().map(String::as_str).collect();
+        assert_eq!(files, ["a.txt", "b.txt", "new.txt"]);
+        assert_eq!(
+            changes.repo.canonicalize().unwrap(),
+            repo.join(".git").canonicalize().unwrap()
+        );
+
+        let explicit = session_changes(&git_session("feat", &wt, Some("main"))).unwrap();
+        assert_eq!(explicit.files, changes.files);
+    }
+}
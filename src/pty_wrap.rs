@@ -6,6 +6,7 @@ use std::time::Instant;
 
 use anyhow::{Context, Result};
 
+use crate::journal::{self, Event, EventKind};
 use crate::state;
 
 // ---------------------------------------------------------------------------
@@ -223,7 +224,7 @@ fn setup_sigwinch_pipe() -> io::Result<RawFd> {
 
     // Install handler
     let mut sa: libc::sigaction = unsafe { std::mem::zeroed() };
-    sa.sa_sigaction = sigwinch_handler as libc::sighandler_t;
+    sa.sa_sigaction = sigwinch_handler as *const () as libc::sighandler_t;
     unsafe { libc::sigemptyset(&mut sa.sa_mask) };
     sa.sa_flags = libc::SA_RESTART;
     if unsafe { libc::sigaction(libc::SIGWINCH, &sa, std::ptr::null_mut()) } != 0 {
@@ -241,6 +242,7 @@ fn update_claude_status(
     session_name: &str,
     title: &str,
     last_title: &mut String,
+    last_status: &mut String,
     last_update: &mut Instant,
 ) {
     if title == last_title.as_str() {
@@ -251,14 +253,35 @@ fn update_claude_status(
     }
     *last_title = title.to_string();
     *last_update = Instant::now();
-    let session_name = session_name.to_string();
-    let title = title.to_string();
-    let _ = state::update(|state| {
+    let mut found = false;
+    let saved = state::update(|state| {
         if let Some(s) = state.sessions.iter_mut().find(|s| s.name == session_name) {
-            s.claude_status = Some(title.clone());
+            s.claude_status = Some(title.to_string());
+            found = true;
         }
         Ok(())
-    });
+    })
+    .is_ok();
+    if !(found && saved) {
+        return;
+    }
+    if let (Some(event), Ok(path)) = (
+        status_event(session_name, title, last_status),
+        state::state_path(),
+    ) {
+        let _ = journal::append(&journal::path_for(&path), &[event]);
+    }
+}
+
+/// The `Status` event for a new pane title, or None if it only advanced the
+/// spinner (same normalized status as `last_status`).
+fn status_event(session_name: &str, title: &str, last_status: &mut String) -> Option<Event> {
+    let status = journal::normalize_status(title);
+    if status == *last_status {
+        return None;
+    }
+    *last_status = status.clone();
+    Some(Event::new(session_name, EventKind::Status { status }))
 }
 
 // ---------------------------------------------------------------------------
@@ -401,6 +424,7 @@ pub fn run_wrap(session_name: &str, command: &[String], prompt_file: Option<&str
 
     // State for debounce
     let mut last_title = String::new();
+    let mut last_status = String::new();
     let mut last_update = Instant::now();
     let mut scanner = OscScanner::new();
 
@@ -493,7 +517,13 @@ pub fn run_wrap(session_name: &str, command: &[String], prompt_file: Option<&str
             // Scan for OSC sequences
             for &byte in &read_buf[..n] {
                 if let Some(title) = scanner.feed(byte) {
-                    update_claude_status(session_name, &title, &mut last_title, &mut last_update);
+                    update_claude_status(
+                        session_name,
+                        &title,
+                        &mut last_title,
+                        &mut last_status,
+                        &mut last_update,
+                    );
                 }
             }
 
@@ -582,6 +612,30 @@ mod tests {
         assert_eq!(shell_join(&args), "cmd -_./:=abc123");
     }
 
+    // ---------------------------------------------------------------
+    // status_event tests
+    // ---------------------------------------------------------------
+
+    #[test]
+    fn status_event_once_per_status() {
+        let mut last = String::new();
+        let titles = ["⠂ Fix login", "⠐ Fix login", "⠂ Fix login", "⠐ Fix login"];
+        let events: Vec<Event> = titles
+            .iter()
+            .filter_map(|t| status_event("s", t, &mut last))
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(
+            events[0].kind,
+            EventKind::Status {
+                status: "working: Fix login".to_string()
+            }
+        );
+
+        assert!(status_event("s", "✳ Fix login", &mut last).is_some());
+        assert!(status_event("s", "✳ Fix login", &mut last).is_none());
+    }
+
     // ---------------------------------------------------------------
     // OscScanner tests
     // ---------------------------------------------------------------
@@ -721,7 +775,7 @@ mod tests {
         let mut scanner = OscScanner::new();
         let mut input = Vec::new();
         input.extend_from_slice(b"\x1b]0;");
-        input.extend(std::iter::repeat(b'A').take(5000));
+        input.extend(std::iter::repeat_n(b'A', 5000));
         input.push(0x07);
         let mut result = None;
         for &byte in &input {
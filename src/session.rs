@@ -1,3 +1,6 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -5,26 +8,162 @@ use serde::{Deserialize, Serialize};
 pub struct Session {
     pub name: String,
     pub tab_id: u64,
-    pub watcher_pane_id: u64,
     pub claude_pane_id: u64,
-    pub shell_pane_id: u64,
+    /// Panes built from `[[layout.panes]]`, keyed by layout pane name.
+    #[serde(default)]
+    pub panes: BTreeMap<String, u64>,
+    /// Panes built from `[[aux_panes]]`, keyed by aux pane name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aux_panes: BTreeMap<String, u64>,
     pub cwd: String,
+    /// Ref the session branch was created from; `close --merge` goes back here.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_ref: Option<String>,
     pub created_at: DateTime<Utc>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub claude_status: Option<String>,
+    /// When reconcile first found none of the panes alive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dead_since: Option<DateTime<Utc>>,
+    /// Created by `ccm adopt` around an existing worktree. Closing it leaves
+    /// the worktree in place unless removal is requested explicitly.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub adopted: bool,
+    /// What the session is for, in a sentence.
     #[serde(default, skip_serializing_if = "Option::is_none")]
-    pub plans_pane_id: Option<u64>,
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Linked issue or ticket (e.g. `PROJ-123` or a URL).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    /// Free-form notes, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+    /// Root of the repository the session was created from (its main
+    /// worktree). None when it could not be determined.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<String>,
 }
 
 impl Session {
+    /// All pane IDs of the session, claude pane first.
+    pub fn pane_ids(&self) -> impl Iterator<Item = u64> + '_ {
+        std::iter::once(self.claude_pane_id)
+            .chain(self.panes.values().copied())
+            .chain(self.aux_panes.values().copied())
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.dead_since.is_some()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+
+    /// Tags and ticket on one line, e.g. `#auth #urgent  WEB-481`.
+    pub fn labels(&self) -> Option<String> {
+        let mut parts: Vec<String> = self.tags.iter().map(|t| format!("#{t}")).collect();
+        parts.extend(self.ticket.clone());
+        (!parts.is_empty()).then(|| parts.join("  "))
+    }
+
+    /// Short name of the session's repository (the root's directory name).
+    pub fn repo_name(&self) -> Option<&str> {
+        let repo = self.repo.as_deref()?;
+        Some(
+            Path::new(repo)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(repo),
+        )
+    }
+
+    /// `repo_name`, or a placeholder for sessions that did not record one.
+    pub fn repo_label(&self) -> &str {
+        self.repo_name().unwrap_or("(unknown repository)")
+    }
+
     /// Find a session from a slice by matching any of its pane IDs.
     pub fn find_by_pane_id(sessions: &[Session], pane_id: u64) -> Option<&Session> {
-        sessions.iter().find(|s| {
-            s.watcher_pane_id == pane_id
-                || s.claude_pane_id == pane_id
-                || s.shell_pane_id == pane_id
-                || s.plans_pane_id == Some(pane_id)
-        })
+        sessions
+            .iter()
+            .find(|s| s.pane_ids().any(|id| id == pane_id))
+    }
+}
+
+/// Sessions of one repository, in state order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoGroup<'a> {
+    /// `Session::repo`; None groups the sessions that did not record one.
+    pub repo: Option<&'a str>,
+    /// Indices into the grouped slice.
+    pub sessions: Vec<usize>,
+}
+
+impl RepoGroup<'_> {
+    /// Header label, e.g. `backend`.
+    pub fn label<'s>(&self, sessions: &'s [Session]) -> &'s str {
+        sessions[self.sessions[0]].repo_label()
+    }
+}
+
+/// Group `sessions` by repository, in order of each repository's first session.
+pub fn group_by_repo(sessions: &[Session]) -> Vec<RepoGroup<'_>> {
+    let mut groups: Vec<RepoGroup> = Vec::new();
+    for (i, session) in sessions.iter().enumerate() {
+        let repo = session.repo.as_deref();
+        match groups.iter_mut().find(|g| g.repo == repo) {
+            Some(group) => group.sessions.push(i),
+            None => groups.push(RepoGroup {
+                repo,
+                sessions: vec![i],
+            }),
+        }
+    }
+    groups
+}
+
+/// Changes to a session's description, tags, ticket and notes, from
+/// `ccm new --desc/--tag` and `ccm annotate`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotation {
+    /// New description; an empty one clears it.
+    pub description: Option<String>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+    /// New ticket; an empty one clears it.
+    pub ticket: Option<String>,
+    /// Note to append.
+    pub note: Option<String>,
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, session: &mut Session) {
+        let non_empty = |s: &String| {
+            let s = s.trim();
+            (!s.is_empty()).then(|| s.to_string())
+        };
+        if let Some(ref description) = self.description {
+            session.description = non_empty(description);
+        }
+        if let Some(ref ticket) = self.ticket {
+            session.ticket = non_empty(ticket);
+        }
+        session.tags.retain(|t| !self.remove_tags.contains(t));
+        for tag in self.add_tags.iter().filter_map(non_empty) {
+            if !session.has_tag(&tag) {
+                session.tags.push(tag);
+            }
+        }
+        if let Some(note) = self.note.as_ref().and_then(non_empty) {
+            session.notes.push(note);
+        }
     }
 }
 
@@ -37,13 +176,20 @@ mod tests {
         Session {
             name: "test".to_string(),
             tab_id: 1,
-            watcher_pane_id: 2,
             claude_pane_id: 3,
-            shell_pane_id: 4,
+            panes: BTreeMap::from([("watcher".to_string(), 2), ("shell".to_string(), 4)]),
+            aux_panes: BTreeMap::new(),
             cwd: "/tmp".to_string(),
+            base_ref: None,
             created_at: Utc::now(),
             claude_status: None,
-            plans_pane_id: None,
+            dead_since: None,
+            adopted: false,
+            description: None,
+            tags: Vec::new(),
+            ticket: None,
+            notes: Vec::new(),
+            repo: None,
         }
     }
 
@@ -54,6 +200,7 @@ mod tests {
         let restored: Session = serde_json::from_str(&json).unwrap();
         assert_eq!(restored.name, session.name);
         assert_eq!(restored.tab_id, session.tab_id);
+        assert_eq!(restored.panes, session.panes);
         assert_eq!(restored.claude_status, None);
     }
 
@@ -76,8 +223,7 @@ mod tests {
     #[test]
     fn deserialize_missing_claude_status() {
         let json = r#"{
-            "name":"s","tab_id":1,"watcher_pane_id":2,
-            "claude_pane_id":3,"shell_pane_id":4,
+            "name":"s","tab_id":1,"claude_pane_id":3,"panes":{"shell":4},
             "cwd":"/tmp","created_at":"2024-01-01T00:00:00Z"
         }"#;
         let session: Session = serde_json::from_str(json).unwrap();
@@ -87,8 +233,7 @@ mod tests {
     #[test]
     fn deserialize_null_claude_status() {
         let json = r#"{
-            "name":"s","tab_id":1,"watcher_pane_id":2,
-            "claude_pane_id":3,"shell_pane_id":4,
+            "name":"s","tab_id":1,"claude_pane_id":3,"panes":{"shell":4},
             "cwd":"/tmp","created_at":"2024-01-01T00:00:00Z",
             "claude_status":null
         }"#;
@@ -96,6 +241,95 @@ mod tests {
         assert_eq!(session.claude_status, None);
     }
 
+    #[test]
+    fn metadata_roundtrip_and_omitted_when_empty() {
+        let mut session = sample_session();
+        let json = serde_json::to_string(&session).unwrap();
+        for field in ["description", "tags", "ticket", "notes"] {
+            assert!(!json.contains(field), "{field} in {json}");
+        }
+
+        session.description = Some("Login redirect fix".to_string());
+        session.tags = vec!["auth".to_string(), "urgent".to_string()];
+        session.ticket = Some("WEB-481".to_string());
+        session.notes = vec!["blocked on review".to_string()];
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.description, session.description);
+        assert_eq!(restored.tags, session.tags);
+        assert_eq!(restored.ticket, session.ticket);
+        assert_eq!(restored.notes, session.notes);
+    }
+
+    #[test]
+    fn annotation_apply() {
+        let mut session = sample_session();
+        session.tags = vec!["old".to_string()];
+        Annotation {
+            description: Some("  Fix login  ".to_string()),
+            add_tags: vec!["auth".to_string(), "old".to_string(), " ".to_string()],
+            ticket: Some("WEB-1".to_string()),
+            note: Some("first".to_string()),
+            ..Annotation::default()
+        }
+        .apply(&mut session);
+        assert_eq!(session.description.as_deref(), Some("Fix login"));
+        assert_eq!(session.tags, ["old", "auth"]);
+        assert_eq!(session.ticket.as_deref(), Some("WEB-1"));
+        assert_eq!(session.notes, ["first"]);
+
+        Annotation {
+            description: Some(String::new()),
+            remove_tags: vec!["old".to_string()],
+            note: Some("second".to_string()),
+            ..Annotation::default()
+        }
+        .apply(&mut session);
+        assert_eq!(session.description, None);
+        assert_eq!(session.tags, ["auth"]);
+        assert_eq!(session.ticket.as_deref(), Some("WEB-1"));
+        assert_eq!(session.notes, ["first", "second"]);
+        assert_eq!(session.labels().as_deref(), Some("#auth  WEB-1"));
+        assert!(Annotation::default().is_empty());
+    }
+
+    #[test]
+    fn group_by_repo_keeps_first_appearance_order() {
+        let session = |name: &str, repo: Option<&str>| Session {
+            name: name.to_string(),
+            repo: repo.map(str::to_string),
+            ..sample_session()
+        };
+        let sessions = [
+            session("a", Some("/src/backend")),
+            session("b", Some("/src/frontend")),
+            session("c", None),
+            session("d", Some("/src/backend")),
+        ];
+        let groups = group_by_repo(&sessions);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].repo, Some("/src/backend"));
+        assert_eq!(groups[0].sessions, [0, 3]);
+        assert_eq!(groups[0].label(&sessions), "backend");
+        assert_eq!(groups[1].sessions, [1]);
+        assert_eq!(groups[2].repo, None);
+        assert_eq!(groups[2].label(&sessions), "(unknown repository)");
+        assert!(group_by_repo(&[]).is_empty());
+    }
+
+    #[test]
+    fn dead_since_roundtrip() {
+        let mut session = sample_session();
+        assert!(!session.is_dead());
+        assert!(!serde_json::to_string(&session).unwrap().contains("dead_since"));
+
+        session.dead_since = Some("2024-01-01T00:00:00Z".parse().unwrap());
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert!(restored.is_dead());
+        assert_eq!(restored.dead_since, session.dead_since);
+    }
+
     #[test]
     fn chrono_datetime_roundtrip() {
         let session = sample_session();
@@ -109,44 +343,46 @@ mod tests {
     }
 
     #[test]
-    fn find_by_pane_id_matches_watcher() {
-        let sessions = vec![sample_session()];
-        let found = Session::find_by_pane_id(&sessions, 2);
-        assert!(found.is_some());
-        assert_eq!(found.unwrap().name, "test");
+    fn pane_ids_claude_first() {
+        let ids: Vec<u64> = sample_session().pane_ids().collect();
+        assert_eq!(ids, vec![3, 4, 2]);
     }
 
     #[test]
-    fn find_by_pane_id_matches_claude() {
-        let sessions = vec![sample_session()];
-        let found = Session::find_by_pane_id(&sessions, 3);
-        assert!(found.is_some());
-        assert_eq!(found.unwrap().name, "test");
-    }
+    fn aux_panes_omitted_when_empty() {
+        let mut session = sample_session();
+        let json = serde_json::to_string(&session).unwrap();
+        assert!(!json.contains("aux_panes"));
 
-    #[test]
-    fn find_by_pane_id_matches_shell() {
-        let sessions = vec![sample_session()];
-        let found = Session::find_by_pane_id(&sessions, 4);
-        assert!(found.is_some());
-        assert_eq!(found.unwrap().name, "test");
+        session.aux_panes.insert("server".to_string(), 9);
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.aux_panes.get("server"), Some(&9));
     }
 
     #[test]
-    fn find_by_pane_id_matches_plans() {
+    fn pane_ids_include_aux_panes() {
         let mut session = sample_session();
-        session.plans_pane_id = Some(5);
+        session.aux_panes.insert("server".to_string(), 9);
+        let ids: Vec<u64> = session.pane_ids().collect();
+        assert_eq!(ids, vec![3, 4, 2, 9]);
         let sessions = vec![session];
-        let found = Session::find_by_pane_id(&sessions, 5);
+        assert!(Session::find_by_pane_id(&sessions, 9).is_some());
+    }
+
+    #[test]
+    fn find_by_pane_id_matches_claude() {
+        let sessions = vec![sample_session()];
+        let found = Session::find_by_pane_id(&sessions, 3);
         assert!(found.is_some());
         assert_eq!(found.unwrap().name, "test");
     }
 
     #[test]
-    fn find_by_pane_id_no_match_plans_none() {
-        let sessions = vec![sample_session()]; // plans_pane_id is None
-        let found = Session::find_by_pane_id(&sessions, 5);
-        assert!(found.is_none());
+    fn find_by_pane_id_matches_layout_pane() {
+        let sessions = vec![sample_session()];
+        assert_eq!(Session::find_by_pane_id(&sessions, 2).unwrap().name, "test");
+        assert_eq!(Session::find_by_pane_id(&sessions, 4).unwrap().name, "test");
     }
 
     #[test]
@@ -166,19 +402,19 @@ mod tests {
     fn find_by_pane_id_multiple_sessions() {
         let mut s1 = sample_session();
         s1.name = "first".to_string();
-        s1.watcher_pane_id = 10;
         s1.claude_pane_id = 11;
-        s1.shell_pane_id = 12;
+        s1.panes = BTreeMap::from([("watcher".to_string(), 10), ("shell".to_string(), 12)]);
 
         let mut s2 = sample_session();
         s2.name = "second".to_string();
-        s2.watcher_pane_id = 20;
         s2.claude_pane_id = 21;
-        s2.shell_pane_id = 22;
+        s2.panes = BTreeMap::from([("watcher".to_string(), 20), ("shell".to_string(), 22)]);
 
         let sessions = vec![s1, s2];
         let found = Session::find_by_pane_id(&sessions, 21);
         assert!(found.is_some());
         assert_eq!(found.unwrap().name, "second");
+        let found = Session::find_by_pane_id(&sessions, 12);
+        assert_eq!(found.unwrap().name, "first");
     }
 }
@@ -5,9 +5,14 @@ use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 
 use crate::error::CcmError;
+use crate::migrate::{self, SCHEMA_VERSION};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct State {
+    /// Layout of this file; see `migrate`. Not to be confused with `version`,
+    /// which counts writes.
+    #[serde(default)]
+    pub schema_version: u32,
     pub sessions: Vec<Session>,
     pub active_session: Option<String>,
     pub version: u64,
@@ -16,16 +21,6 @@ pub struct State {
 // Re-export for convenience
 use crate::session::Session;
 
-impl Default for State {
-    fn default() -> Self {
-        Self {
-            sessions: Vec::new(),
-            active_session: None,
-            version: 0,
-        }
-    }
-}
-
 /// Return the path to the state file (~/.local/state/ccm/state.json).
 pub fn state_path() -> Result<PathBuf, CcmError> {
     let base = dirs::state_dir()
@@ -34,24 +29,29 @@ pub fn state_path() -> Result<PathBuf, CcmError> {
     Ok(base.join("ccm").join("state.json"))
 }
 
-/// Read state from the given path. Returns default state if file doesn't exist.
-fn load_from(path: &Path) -> Result<State, CcmError> {
+/// Read state from the given path, migrated to the current schema. Returns
+/// default state if file doesn't exist. State written by a newer ccm is read
+/// as far as this build understands it.
+pub fn load_from(path: &Path) -> Result<State, CcmError> {
+    Ok(load_stored(path)?.0)
+}
+
+/// `load_from`, plus the schema version the file was stored with.
+fn load_stored(path: &Path) -> Result<(State, u32), CcmError> {
     if !path.exists() {
-        return Ok(State::default());
+        return Ok((State::default(), SCHEMA_VERSION));
     }
     let data = fs::read_to_string(path)
         .map_err(|e| CcmError::State(format!("failed to read {}: {e}", path.display())))?;
-    let state: State = serde_json::from_str(&data)?;
-    Ok(state)
-}
-
-/// Read the state from disk. Returns default state if file doesn't exist.
-pub fn load() -> Result<State, CcmError> {
-    load_from(&state_path()?)
+    let mut value: serde_json::Value = serde_json::from_str(&data)?;
+    let stored = migrate::version_of(&value);
+    migrate::run(&mut value)?;
+    let state: State = serde_json::from_value(value)?;
+    Ok((state, stored))
 }
 
 /// Atomically update state at the given path: load, apply function, save.
-fn update_at<F>(path: &Path, f: F) -> Result<State, CcmError>
+pub fn update_at<F>(path: &Path, f: F) -> Result<State, CcmError>
 where
     F: FnOnce(&mut State) -> Result<(), CcmError>,
 {
@@ -70,9 +70,21 @@ where
 
     flock_exclusive(&lock_file)?;
 
-    let mut state = load_from(path)?;
+    let (mut state, stored) = load_stored(path)?;
+    // Writing would drop whatever the newer schema added
+    if state.schema_version > SCHEMA_VERSION {
+        return Err(CcmError::SchemaTooNew {
+            found: state.schema_version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    // This write persists the migration, so it happens once
+    if stored < SCHEMA_VERSION {
+        migrate::backfill(&mut state.sessions, stored);
+    }
 
     f(&mut state)?;
+    state.schema_version = SCHEMA_VERSION;
     state.version += 1;
 
     let json = serde_json::to_string_pretty(&state)?;
@@ -85,7 +97,7 @@ where
     tmp_file
         .sync_all()
         .map_err(|e| CcmError::State(format!("failed to sync temp file: {e}")))?;
-    fs::rename(&tmp_path, &path)
+    fs::rename(&tmp_path, path)
         .map_err(|e| CcmError::State(format!("failed to rename temp file: {e}")))?;
 
     Ok(state)
@@ -99,6 +111,19 @@ where
     update_at(&state_path()?, f)
 }
 
+#[cfg(unix)]
+fn flock_exclusive(file: &fs::File) -> Result<(), CcmError> {
+    use std::os::unix::io::AsRawFd;
+    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) };
+    if ret != 0 {
+        return Err(CcmError::State(format!(
+            "flock failed: {}",
+            std::io::Error::last_os_error()
+        )));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -109,13 +134,20 @@ mod tests {
         Session {
             name: name.to_string(),
             tab_id: 1,
-            watcher_pane_id: 2,
             claude_pane_id: 3,
-            shell_pane_id: 4,
+            panes: [("watcher".to_string(), 2), ("shell".to_string(), 4)].into(),
+            aux_panes: Default::default(),
             cwd: "/tmp".to_string(),
+            base_ref: None,
             created_at: Utc::now(),
             claude_status: None,
-            plans_pane_id: None,
+            dead_since: None,
+            adopted: false,
+            description: None,
+            tags: Vec::new(),
+            ticket: None,
+            notes: Vec::new(),
+            repo: None,
         }
     }
 
@@ -150,21 +182,57 @@ mod tests {
     }
 
     #[test]
-    fn state_deserialize_with_sessions() {
+    fn load_from_migrates_legacy_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_state_path(&dir);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
         let json = r#"{
             "sessions": [
-                {"name":"s1","tab_id":1,"watcher_pane_id":2,"claude_pane_id":3,"shell_pane_id":4,"cwd":"/","created_at":"2024-01-01T00:00:00Z"},
-                {"name":"s2","tab_id":5,"watcher_pane_id":6,"claude_pane_id":7,"shell_pane_id":8,"cwd":"/tmp","created_at":"2024-01-02T00:00:00Z"}
+                {"name":"s1","tab_id":1,"watcher_pane_id":2,"claude_pane_id":3,"shell_pane_id":4,"cwd":"/nonexistent","created_at":"2024-01-01T00:00:00Z"},
+                {"name":"s2","tab_id":5,"watcher_pane_id":6,"claude_pane_id":7,"shell_pane_id":8,"cwd":"/nonexistent","created_at":"2024-01-02T00:00:00Z"}
             ],
             "active_session": "s1",
             "version": 3
         }"#;
-        let state: State = serde_json::from_str(json).unwrap();
+        fs::write(&path, json).unwrap();
+        let state = load_from(&path).unwrap();
+        assert_eq!(state.schema_version, SCHEMA_VERSION);
         assert_eq!(state.sessions.len(), 2);
         assert_eq!(state.sessions[0].name, "s1");
-        assert_eq!(state.sessions[1].name, "s2");
+        assert_eq!(state.sessions[0].panes.get("watcher"), Some(&2));
+        assert_eq!(state.sessions[1].panes.get("shell"), Some(&8));
         assert_eq!(state.active_session, Some("s1".to_string()));
         assert_eq!(state.version, 3);
+
+        // Reading leaves the file alone; the next write stores the new schema
+        assert!(fs::read_to_string(&path).unwrap().contains("watcher_pane_id"));
+        update_at(&path, |_| Ok(())).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(!written.contains("watcher_pane_id"));
+        assert!(written.contains(&format!(r#""schema_version": {SCHEMA_VERSION}"#)));
+    }
+
+    #[test]
+    fn update_at_refuses_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_state_path(&dir);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let json = format!(
+            r#"{{"schema_version":{},"sessions":[],"active_session":null,"version":4,"future":1}}"#,
+            SCHEMA_VERSION + 1
+        );
+        fs::write(&path, &json).unwrap();
+
+        // Still readable
+        assert_eq!(load_from(&path).unwrap().version, 4);
+
+        let err = update_at(&path, |_| Ok(())).unwrap_err();
+        assert!(matches!(
+            err,
+            CcmError::SchemaTooNew { found, supported }
+                if found == SCHEMA_VERSION + 1 && supported == SCHEMA_VERSION
+        ));
+        assert_eq!(fs::read_to_string(&path).unwrap(), json);
     }
 
     #[test]
@@ -220,6 +288,7 @@ mod tests {
         let path = temp_state_path(&dir);
         let state = update_at(&path, |_| Ok(())).unwrap();
         assert_eq!(state.version, 1);
+        assert_eq!(state.schema_version, SCHEMA_VERSION);
         assert!(path.exists());
     }
 
@@ -281,16 +350,3 @@ mod tests {
         assert!(!tmp_path.exists(), ".tmp file should not remain");
     }
 }
-
-#[cfg(unix)]
-fn flock_exclusive(file: &fs::File) -> Result<(), CcmError> {
-    use std::os::unix::io::AsRawFd;
-    let ret = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) };
-    if ret != 0 {
-        return Err(CcmError::State(format!(
-            "flock failed: {}",
-            std::io::Error::last_os_error()
-        )));
-    }
-    Ok(())
-}
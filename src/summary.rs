@@ -0,0 +1,125 @@
+use crate::error::CcmError;
+use crate::git;
+use crate::session::Session;
+
+/// What a session has produced so far, relative to its base.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitSummary {
+    /// Commits on the session branch that are not on the base.
+    pub ahead: u32,
+    /// Commits on the base that the session branch does not have.
+    pub behind: u32,
+    /// Modified, staged or untracked files in the worktree.
+    pub dirty: u32,
+    /// Lines added and removed since the merge-base, uncommitted edits included.
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+impl GitSummary {
+    pub fn is_empty(&self) -> bool {
+        self.ahead == 0 && self.dirty == 0 && self.insertions == 0 && self.deletions == 0
+    }
+}
+
+/// Compute the summary of `session`'s worktree against its base (see
+/// `git::session_base`).
+pub fn session_summary(session: &Session) -> Result<GitSummary, CcmError> {
+    let cwd = session.cwd.as_str();
+    let base = git::session_base(session)?;
+    let merge_base = git::run(cwd, &["merge-base", &base, "HEAD"])?;
+
+    let counts = git::run(
+        cwd,
+        &["rev-list", "--left-right", "--count", &format!("{base}...HEAD")],
+    )?;
+    let (behind, ahead) = parse_left_right(&counts)
+        .ok_or_else(|| CcmError::Git(format!("unexpected rev-list output: {counts}")))?;
+
+    let status = git::run(cwd, &["status", "--porcelain"])?;
+    let numstat = git::run(cwd, &["diff", "--numstat", &merge_base])?;
+    let (insertions, deletions) = parse_numstat(&numstat);
+
+    Ok(GitSummary {
+        ahead,
+        behind,
+        dirty: status.lines().filter(|l| !l.is_empty()).count() as u32,
+        insertions,
+        deletions,
+    })
+}
+
+/// Parse `git rev-list --left-right --count` output (`<left>\t<right>`).
+fn parse_left_right(output: &str) -> Option<(u32, u32)> {
+    let (left, right) = output.split_once(char::is_whitespace)?;
+    Some((left.trim().parse().ok()?, right.trim().parse().ok()?))
+}
+
+/// Sum the added and removed lines of `git diff --numstat`; binary files
+/// (`-\t-\t<path>`) count as zero.
+fn parse_numstat(output: &str) -> (u32, u32) {
+    output.lines().fold((0, 0), |(ins, del), line| {
+        let mut fields = line.split('\t');
+        let added = fields.next().and_then(|n| n.parse::<u32>().ok());
+        let removed = fields.next().and_then(|n| n.parse::<u32>().ok());
+        (ins + added.unwrap_or(0), del + removed.unwrap_or(0))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn parse_left_right_counts() {
+        assert_eq!(parse_left_right("3\t12"), Some((3, 12)));
+        assert_eq!(parse_left_right("0 0"), Some((0, 0)));
+        assert_eq!(parse_left_right("x"), None);
+    }
+
+    #[test]
+    fn parse_numstat_sums_lines() {
+        let out = "10\t2\tsrc/main.rs\n-\t-\tlogo.png\n3\t0\tREADME";
+        assert_eq!(parse_numstat(out), (13, 2));
+        assert_eq!(parse_numstat(""), (0, 0));
+    }
+
+    #[test]
+    fn session_summary_against_recorded_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let r = git::test_repo(dir.path());
+        let wt = dir.path().join("wt").to_string_lossy().into_owned();
+        git::run(&r, &["worktree", "add", "-q", "-b", "feat", &wt]).unwrap();
+        git::run(&r, &["config", "branch.feat.ccmBase", "main"]).unwrap();
+
+        let mut session: Session = serde_json::from_value(serde_json::json!({
+            "name": "feat",
+            "tab_id": 1,
+            "claude_pane_id": 1,
+            "cwd": wt,
+            "created_at": "2025-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        assert!(session_summary(&session).unwrap().is_empty());
+
+        std::fs::write(Path::new(&wt).join("a.txt"), "1\n2\n3\n").unwrap();
+        git::run(&wt, &["add", "a.txt"]).unwrap();
+        git::run(&wt, &["commit", "-q", "-m", "a"]).unwrap();
+        std::fs::write(Path::new(&wt).join("a.txt"), "1\n3\n").unwrap();
+        std::fs::write(Path::new(&wt).join("b.txt"), "new\n").unwrap();
+        git::run(&r, &["commit", "-q", "--allow-empty", "-m", "on main"]).unwrap();
+
+        session.base_ref = Some("main".to_string());
+        assert_eq!(
+            session_summary(&session).unwrap(),
+            GitSummary {
+                ahead: 1,
+                behind: 1,
+                dirty: 2,
+                insertions: 2,
+                deletions: 0,
+            }
+        );
+    }
+}
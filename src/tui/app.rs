@@ -1,16 +1,45 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
 
+use chrono::Utc;
+
+use crate::archive;
+use crate::checks::{self, CheckOutput};
 use crate::error::CcmError;
-use crate::gj;
-use crate::session::Session;
+use crate::journal::{self, Event, EventKind};
+use crate::layout;
+use crate::mux::Multiplexer;
+use crate::overlap;
+use crate::session::{self, Session};
 use crate::state::{self, State};
-use crate::wezterm;
+use crate::summary::{self, GitSummary};
+use crate::worktree::WorktreeProvider;
+
+use super::diff_view::DiffView;
 
 pub enum ConfirmAction {
     Close(String),
     CloseWithMerge(String),
 }
 
+/// Git information about all sessions, computed in a background thread.
+struct SessionScan {
+    overlaps: BTreeMap<String, Vec<String>>,
+    summaries: BTreeMap<String, GitSummary>,
+}
+
+/// Merge checks running in a background thread; the merge itself happens on
+/// the UI thread once they pass.
+struct PendingMerge {
+    name: String,
+    log: PathBuf,
+    /// Last line of the log as of the latest poll.
+    last_line: Option<String>,
+    result: Receiver<Result<(), CcmError>>,
+}
+
 pub struct App {
     pub sessions: Vec<Session>,
     pub active_session: Option<String>,
@@ -21,12 +50,35 @@ pub struct App {
     pub own_session: String,
     pub status_message: Option<String>,
     pub pane_titles: HashMap<u64, String>,
-    wezterm_binary: String,
+    /// For each session, the sessions whose changes touch the same files.
+    pub overlaps: BTreeMap<String, Vec<String>>,
+    /// Git status of each session against its base.
+    pub summaries: BTreeMap<String, GitSummary>,
+    pending_scan: Option<Receiver<SessionScan>>,
+    /// Diff review of the selected session (`d`), drawn instead of the list.
+    pub diff_view: Option<DiffView>,
+    /// Repositories whose sessions are hidden under their header (`z`).
+    pub collapsed: BTreeSet<String>,
+    mux: Box<dyn Multiplexer>,
+    worktrees: Box<dyn WorktreeProvider>,
+    state_path: PathBuf,
+    dead_session_grace_secs: u64,
+    keep_dead_with_worktree: bool,
+    merge_checks: Vec<String>,
+    pending_merge: Option<PendingMerge>,
     manual_navigation: bool,
 }
 
 impl App {
-    pub fn new(session_name: &str, wezterm_binary: &str) -> Self {
+    pub fn new(
+        session_name: &str,
+        mux: Box<dyn Multiplexer>,
+        worktrees: Box<dyn WorktreeProvider>,
+        state_path: PathBuf,
+        dead_session_grace_secs: u64,
+        keep_dead_with_worktree: bool,
+        merge_checks: Vec<String>,
+    ) -> Self {
         let mut app = Self {
             sessions: Vec::new(),
             active_session: None,
@@ -37,7 +89,18 @@ impl App {
             own_session: session_name.to_string(),
             status_message: None,
             pane_titles: HashMap::new(),
-            wezterm_binary: wezterm_binary.to_string(),
+            overlaps: BTreeMap::new(),
+            summaries: BTreeMap::new(),
+            pending_scan: None,
+            diff_view: None,
+            collapsed: BTreeSet::new(),
+            mux,
+            worktrees,
+            state_path,
+            dead_session_grace_secs,
+            keep_dead_with_worktree,
+            merge_checks,
+            pending_merge: None,
             manual_navigation: false,
         };
         app.refresh_state();
@@ -45,7 +108,7 @@ impl App {
     }
 
     pub fn refresh_state(&mut self) {
-        match state::load() {
+        match state::load_from(&self.state_path) {
             Ok(state) => {
                 self.apply_state(state);
             }
@@ -60,10 +123,10 @@ impl App {
             return;
         }
         self.last_version = state.version;
-        self.sessions = state.sessions;
+        self.sessions = grouped(state.sessions);
         self.active_session = state.active_session;
 
-        // Reflect claude_status from state into pane_titles (overrides WezTerm polling)
+        // Reflect claude_status from state into pane_titles (overrides pane title polling)
         for session in &self.sessions {
             if let Some(ref status) = session.claude_status {
                 self.pane_titles
@@ -80,6 +143,72 @@ impl App {
         if !self.sessions.is_empty() && self.selected_index >= self.sessions.len() {
             self.selected_index = self.sessions.len() - 1;
         }
+        // A session in a collapsed group is selected through its header
+        if !self.is_navigable(self.selected_index) {
+            self.selected_index = self.group_start(self.selected_index);
+        }
+    }
+
+    /// Whether the list shows repository headers: sessions of more than one
+    /// repository are open.
+    pub fn is_grouped(&self) -> bool {
+        self.sessions
+            .iter()
+            .any(|s| s.repo != self.sessions[0].repo)
+    }
+
+    /// Whether the group of `session` is collapsed.
+    pub fn is_collapsed(&self, session: &Session) -> bool {
+        self.is_grouped() && self.collapsed.contains(&group_key(session))
+    }
+
+    /// Index of the first session of the group of session `index`.
+    pub fn group_start(&self, index: usize) -> usize {
+        let Some(session) = self.sessions.get(index) else {
+            return index;
+        };
+        self.sessions
+            .iter()
+            .position(|s| s.repo == session.repo)
+            .unwrap_or(index)
+    }
+
+    /// Number of sessions in the group of session `index`.
+    pub fn group_len(&self, index: usize) -> usize {
+        let repo = &self.sessions[index].repo;
+        self.sessions.iter().filter(|s| &s.repo == repo).count()
+    }
+
+    /// Whether j/k can stop at session `index`. A collapsed group is one stop,
+    /// at its first session, which stands for the header.
+    fn is_navigable(&self, index: usize) -> bool {
+        match self.sessions.get(index) {
+            Some(session) => !self.is_collapsed(session) || self.group_start(index) == index,
+            None => true,
+        }
+    }
+
+    /// The selected session, unless it is hidden in a collapsed group.
+    fn selected_session(&self) -> Option<&Session> {
+        self.sessions
+            .get(self.selected_index)
+            .filter(|s| !self.is_collapsed(s))
+    }
+
+    /// Collapse or expand the repository group of the selected session.
+    pub fn toggle_group(&mut self) {
+        let Some(session) = self.sessions.get(self.selected_index) else {
+            return;
+        };
+        if !self.is_grouped() {
+            return;
+        }
+        let key = group_key(session);
+        if !self.collapsed.remove(&key) {
+            self.collapsed.insert(key);
+            self.selected_index = self.group_start(self.selected_index);
+        }
+        self.manual_navigation = true;
     }
 
     /// selected_index をアクティブセッションの位置に同期する。
@@ -105,10 +234,12 @@ impl App {
         }
     }
 
-    /// Reconcile state with live WezTerm panes.
-    /// Remove sessions whose panes no longer exist.
+    /// Reconcile state with live multiplexer panes.
+    /// Sessions whose panes no longer exist are marked dead; they are removed
+    /// once the grace period has passed (unless `keep_dead_with_worktree` holds
+    /// them while their worktree exists).
     pub fn reconcile(&mut self) {
-        let live_panes = match wezterm::list_panes(&self.wezterm_binary) {
+        let live_panes = match self.mux.list_panes() {
             Ok(p) => p,
             Err(e) => {
                 self.status_message = Some(format!("Reconcile error: {e}"));
@@ -129,7 +260,7 @@ impl App {
                 self.pane_titles
                     .insert(session.claude_pane_id, status.clone());
             } else if let Some(title) = pane_title_map.get(&session.claude_pane_id) {
-                // Fallback to WezTerm pane title
+                // Fallback to the multiplexer's pane title
                 if !title.is_empty() {
                     self.pane_titles
                         .insert(session.claude_pane_id, title.to_string());
@@ -139,71 +270,120 @@ impl App {
 
         let live_pane_ids: std::collections::HashSet<u64> =
             live_panes.iter().map(|p| p.pane_id).collect();
+        let is_alive = |s: &Session| s.pane_ids().any(|id| live_pane_ids.contains(&id));
+
+        let now = Utc::now();
+        let grace = chrono::Duration::seconds(self.dead_session_grace_secs as i64);
+        let keep = self.keep_dead_with_worktree;
+        let expired = |s: &Session| {
+            s.dead_since.is_some_and(|t| now - t >= grace)
+                && !(keep && Path::new(&s.cwd).is_dir())
+        };
 
-        let dead_sessions: Vec<String> = self
+        let changed = self
             .sessions
             .iter()
-            .filter(|s| {
-                // A session is dead if none of its panes exist
-                !live_pane_ids.contains(&s.claude_pane_id)
-                    && !live_pane_ids.contains(&s.shell_pane_id)
-                    && !live_pane_ids.contains(&s.watcher_pane_id)
-                    && !s.plans_pane_id.is_some_and(|id| live_pane_ids.contains(&id))
-            })
-            .map(|s| s.name.clone())
-            .collect();
-
-        if dead_sessions.is_empty() {
+            .any(|s| is_alive(s) == s.is_dead() || expired(s));
+        if !changed {
             return;
         }
 
-        match state::update(|state| {
-            state
-                .sessions
-                .retain(|s| !dead_sessions.contains(&s.name));
+        let mut events = Vec::new();
+        match state::update_at(&self.state_path, |state| {
+            for s in &mut state.sessions {
+                if is_alive(s) {
+                    if s.dead_since.take().is_some() {
+                        events.push(Event::new(&s.name, EventKind::Alive));
+                    }
+                } else if s.dead_since.is_none() {
+                    s.dead_since = Some(now);
+                    events.push(Event::new(&s.name, EventKind::Dead));
+                }
+            }
+            let mut purged = Vec::new();
+            state.sessions.retain(|s| {
+                let purge = expired(s);
+                if purge {
+                    purged.push(s.name.clone());
+                }
+                !purge
+            });
+            events.extend(purged.iter().map(|name| Event::new(name, EventKind::Purged)));
             if let Some(ref active) = state.active_session {
-                if dead_sessions.contains(active) {
+                if purged.contains(active) {
                     state.active_session = None;
                 }
             }
             Ok(())
         }) {
-            Ok(new_state) => self.apply_state(new_state),
+            Ok(new_state) => {
+                self.apply_state(new_state);
+                self.record(&events);
+            }
             Err(e) => {
                 self.status_message = Some(format!("Reconcile save error: {e}"));
             }
         }
     }
 
-    pub fn move_down(&mut self) {
-        if !self.sessions.is_empty() {
-            self.selected_index = (self.selected_index + 1) % self.sessions.len();
-            self.manual_navigation = true;
+    /// Append `events` to the journal, reporting failure in the status line.
+    fn record(&mut self, events: &[Event]) {
+        if let Err(e) = journal::append(&journal::path_for(&self.state_path), events) {
+            self.status_message = Some(format!("History error: {e}"));
         }
     }
 
+    pub fn move_down(&mut self) {
+        self.move_by(1);
+    }
+
     pub fn move_up(&mut self) {
-        if !self.sessions.is_empty() {
-            if self.selected_index == 0 {
-                self.selected_index = self.sessions.len() - 1;
-            } else {
-                self.selected_index -= 1;
+        self.move_by(self.sessions.len().saturating_sub(1));
+    }
+
+    /// Move the selection `step` places forward (wrapping), skipping the
+    /// sessions hidden in collapsed groups.
+    fn move_by(&mut self, step: usize) {
+        let len = self.sessions.len();
+        if len == 0 {
+            return;
+        }
+        let mut index = self.selected_index;
+        for _ in 0..len {
+            index = (index + step) % len;
+            if self.is_navigable(index) {
+                break;
             }
-            self.manual_navigation = true;
         }
+        self.selected_index = index;
+        self.manual_navigation = true;
     }
 
     pub fn switch_to_selected(&mut self) {
+        if self
+            .sessions
+            .get(self.selected_index)
+            .is_some_and(|s| self.is_collapsed(s))
+        {
+            self.toggle_group();
+            return;
+        }
         if let Some(session) = self.sessions.get(self.selected_index) {
             let name = session.name.clone();
+            if session.is_dead() {
+                self.status_message = Some(format!(
+                    "'{name}' has no panes; run `ccm restore {name}` to reopen it"
+                ));
+                return;
+            }
             let tab_id = session.tab_id;
 
-            if let Err(e) = wezterm::activate_tab(&self.wezterm_binary, tab_id) {
+            if let Err(e) = self.mux.activate_tab(tab_id) {
                 self.status_message = Some(format!("Switch error: {e}"));
                 return;
             }
 
-            match state::update(|state| {
+            match state::update_at(&self.state_path, |state| {
                 state.active_session = Some(name.clone());
                 Ok(())
             }) {
@@ -219,13 +399,36 @@ impl App {
     }
 
     pub fn request_close(&mut self) {
-        if let Some(session) = self.sessions.get(self.selected_index) {
+        if let Some(session) = self.selected_session() {
             self.confirm_action = Some(ConfirmAction::Close(session.name.clone()));
         }
     }
 
+    /// Open the diff review of the selected session against its base.
+    pub fn open_diff(&mut self) {
+        let Some(session) = self.selected_session() else {
+            return;
+        };
+        match DiffView::for_session(session) {
+            Ok(view) => self.diff_view = Some(view),
+            Err(e) => self.status_message = Some(format!("Diff error: {e}")),
+        }
+    }
+
     pub fn request_close_with_merge(&mut self) {
-        if let Some(session) = self.sessions.get(self.selected_index) {
+        if let Some(session) = self.selected_session() {
+            if session.adopted {
+                self.status_message = Some(format!(
+                    "'{}' is adopted: run `ccm close --merge --remove-worktree {}`",
+                    session.name, session.name
+                ));
+                return;
+            }
+            if let Some(pending) = &self.pending_merge {
+                self.status_message =
+                    Some(format!("Merge checks for '{}' are still running", pending.name));
+                return;
+            }
             self.confirm_action = Some(ConfirmAction::CloseWithMerge(session.name.clone()));
         }
     }
@@ -236,13 +439,134 @@ impl App {
                 ConfirmAction::Close(name) => (name, false),
                 ConfirmAction::CloseWithMerge(name) => (name, true),
             };
-            let is_own = name == self.own_session;
-            if let Err(e) = self.do_close_session(&name, merge) {
-                self.status_message = Some(format!("Close error: {e}"));
+            if merge && !self.merge_checks.is_empty() {
+                self.start_merge_checks(name);
+            } else {
+                self.finish_close(&name, merge);
+            }
+        }
+    }
+
+    fn finish_close(&mut self, name: &str, merge: bool) {
+        if let Err(e) = self.do_close_session(name, merge) {
+            self.status_message = Some(format!("Close error: {e}"));
+            return;
+        }
+        if name == self.own_session {
+            self.should_quit = true;
+        }
+    }
+
+    /// Run `[merge] checks` for `name` in a background thread so the sidebar
+    /// stays responsive; `poll_merge_checks` merges once they pass.
+    fn start_merge_checks(&mut self, name: String) {
+        let Some(session) = self.sessions.iter().find(|s| s.name == name) else {
+            self.status_message = Some(format!("Close error: session not found: {name}"));
+            return;
+        };
+        let cwd = session.cwd.clone();
+        let merge_checks = self.merge_checks.clone();
+        let log = checks::log_path(&self.state_path, &name);
+        let thread_log = log.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(checks::run(&merge_checks, &cwd, CheckOutput::Log(&thread_log)));
+        });
+        self.pending_merge = Some(PendingMerge {
+            name,
+            log,
+            last_line: None,
+            result: rx,
+        });
+    }
+
+    /// Start recomputing the git status and changed files of all sessions in
+    /// a background thread, unless a scan is still running. Git can be slow
+    /// on large worktrees, so rendering never waits for it; the result is
+    /// picked up by `poll_scan`.
+    pub fn scan_sessions(&mut self) {
+        if self.pending_scan.is_some() {
+            return;
+        }
+        let sessions = self.sessions.clone();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let summaries = sessions
+                .iter()
+                .filter_map(|s| Some((s.name.clone(), summary::session_summary(s).ok()?)))
+                .collect();
+            let (changes, _) = overlap::collect(&sessions);
+            let overlaps = overlap::overlapping_sessions(&overlap::find_overlaps(&changes));
+            let _ = tx.send(SessionScan {
+                overlaps,
+                summaries,
+            });
+        });
+        self.pending_scan = Some(rx);
+    }
+
+    /// Take the result of a finished scan, if any.
+    pub fn poll_scan(&mut self) {
+        let Some(rx) = &self.pending_scan else {
+            return;
+        };
+        match rx.try_recv() {
+            Ok(scan) => {
+                self.overlaps = scan.overlaps;
+                self.summaries = scan.summaries;
+            }
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {}
+        }
+        self.pending_scan = None;
+    }
+
+    /// Sessions that overlap with `name` (empty if none).
+    pub fn overlaps_of(&self, name: &str) -> &[String] {
+        self.overlaps.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Session, log and last log line so far of the running merge checks.
+    pub fn merge_output(&self) -> Option<(&str, &Path, Option<&str>)> {
+        self.pending_merge
+            .as_ref()
+            .map(|p| (p.name.as_str(), p.log.as_path(), p.last_line.as_deref()))
+    }
+
+    /// Merge the pending session if its checks passed, or report the failure
+    /// and keep the session. While they run, picks up their latest output.
+    pub fn poll_merge_checks(&mut self) {
+        let Some(pending) = &mut self.pending_merge else {
+            return;
+        };
+        let result = match pending.result.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => {
+                pending.last_line = checks::last_line(&pending.log);
                 return;
             }
-            if is_own {
-                self.should_quit = true;
+            Err(TryRecvError::Disconnected) => Err(CcmError::Check(
+                "check runner exited unexpectedly".to_string(),
+            )),
+        };
+        let Some(pending) = self.pending_merge.take() else {
+            return;
+        };
+        match result {
+            Ok(()) => {
+                self.status_message = Some(format!(
+                    "Merge checks for '{}' passed (log: {})",
+                    pending.name,
+                    pending.log.display()
+                ));
+                self.finish_close(&pending.name, true);
+            }
+            Err(e) => {
+                self.status_message = Some(format!(
+                    "Merge of '{}' aborted: {e} (log: {})",
+                    pending.name,
+                    pending.log.display()
+                ));
             }
         }
     }
@@ -254,19 +578,21 @@ impl App {
     fn do_close_session(&mut self, name: &str, merge: bool) -> Result<(), CcmError> {
         // If merging, attempt merge BEFORE destroying session state.
         // On merge failure the session remains intact for the user to investigate.
+        let mut capture = None;
         if merge {
-            let state = state::load()?;
+            let state = state::load_from(&self.state_path)?;
             let session = state
                 .sessions
                 .iter()
                 .find(|s| s.name == name)
                 .ok_or_else(|| CcmError::SessionNotFound(name.to_string()))?;
-            gj::exit_worktree(&session.cwd, true)?;
+            capture = Some(archive::Capture::of(session));
+            self.worktrees.exit_worktree(&session.cwd, true)?;
         }
 
         // Remove session from state atomically under lock
         let mut removed_session = None;
-        let new_state = state::update(|state| {
+        let new_state = state::update_at(&self.state_path, |state| {
             let idx = state
                 .sessions
                 .iter()
@@ -280,19 +606,21 @@ impl App {
         })?;
 
         let session = removed_session.expect("session was just removed in update closure");
-
-        // Kill panes (ignore errors for already-dead panes)
-        // Kill watcher pane last so that own-session close completes shell/claude kills first
-        let _ = wezterm::kill_pane(&self.wezterm_binary, session.shell_pane_id);
-        let _ = wezterm::kill_pane(&self.wezterm_binary, session.claude_pane_id);
-        if let Some(plans_pane_id) = session.plans_pane_id {
-            let _ = wezterm::kill_pane(&self.wezterm_binary, plans_pane_id);
+        self.record(&[Event::new(name, EventKind::Closed { merged: merge })]);
+        let capture = capture.unwrap_or_else(|| archive::Capture::of(&session));
+        let archive_dir = archive::dir_for(&self.state_path);
+        if let Err(e) = capture.save(&archive_dir, session.clone(), merge) {
+            self.status_message = Some(format!("Archive error: {e}"));
         }
-        let _ = wezterm::kill_pane(&self.wezterm_binary, session.watcher_pane_id);
 
-        // Clean up git worktree (best-effort for non-merge path)
-        if !merge {
-            let _ = gj::exit_worktree(&session.cwd, false);
+        // Kill panes (ignore errors for already-dead panes). Our own watcher
+        // pane goes last so that own-session close finishes the other kills first
+        layout::kill_session_panes(self.mux.as_ref(), &session);
+
+        // Clean up git worktree (best-effort for non-merge path). Adopted
+        // worktrees are left alone; `ccm close --remove-worktree` removes them
+        if !merge && !session.adopted {
+            let _ = self.worktrees.exit_worktree(&session.cwd, false);
         }
 
         self.apply_state(new_state);
@@ -300,20 +628,53 @@ impl App {
     }
 
     pub fn select_by_click(&mut self, row: u16, area_width: u16) {
-        use super::ui::{session_name_text, wrap_text};
+        use super::ui::{group_header_text, session_detail_lines, session_name_text, wrap_text};
 
         let mut current_row: u16 = 2; // header + separator
         let indent = 3u16;
         let box_width = (area_width.saturating_sub(indent)) as usize;
         let inner_width = box_width.saturating_sub(4); // "│ " + " │"
+        let grouped = self.is_grouped();
 
         for (i, session) in self.sessions.iter().enumerate() {
+            let collapsed = self.is_collapsed(session);
+            if grouped && self.group_start(i) == i {
+                let header_start = current_row;
+                let text = group_header_text(
+                    session.repo_label(),
+                    self.group_len(i),
+                    collapsed,
+                    collapsed && i == self.selected_index,
+                );
+                current_row += wrap_text(&text, area_width as usize).len() as u16;
+                if row >= header_start && row < current_row {
+                    self.selected_index = i;
+                    self.toggle_group();
+                    return;
+                }
+            }
+            if collapsed {
+                continue;
+            }
+
             let session_start = current_row;
             let is_selected = i == self.selected_index;
             let is_active = self.active_session.as_deref() == Some(&session.name);
             let is_own = session.name == self.own_session;
-            let text = session_name_text(&session.name, is_selected, is_active, is_own);
+            let text = session_name_text(
+                &session.name,
+                is_selected,
+                is_active,
+                is_own,
+                session.is_dead(),
+            );
             current_row += wrap_text(&text, area_width as usize).len() as u16;
+            let summary = self.summaries.get(&session.name);
+            for (detail, _) in
+                session_detail_lines(session, summary, self.overlaps_of(&session.name))
+            {
+                current_row += wrap_text(&detail, area_width as usize).len() as u16;
+            }
 
             if let Some(title) = self.pane_titles.get(&session.claude_pane_id) {
                 if !title.is_empty() && box_width > 4 {
@@ -332,13 +693,40 @@ impl App {
     }
 }
 
+/// Key of a session's repository group in `App::collapsed`.
+fn group_key(session: &Session) -> String {
+    session.repo.clone().unwrap_or_default()
+}
+
+/// Reorder `sessions` so that each repository's sessions are adjacent,
+/// keeping state order within a repository.
+fn grouped(mut sessions: Vec<Session>) -> Vec<Session> {
+    let order: Vec<usize> = session::group_by_repo(&sessions)
+        .into_iter()
+        .flat_map(|g| g.sessions)
+        .collect();
+    let mut slots: Vec<Option<Session>> = sessions.drain(..).map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
+    use crate::git;
+    use crate::mux::fake::FakeMux;
+    use crate::mux::SplitDirection;
+    use crate::worktree::fake::FakeWorktrees;
 
     impl App {
         fn new_for_test() -> Self {
+            Self::with_fakes(
+                FakeMux::new(),
+                FakeWorktrees::new(),
+                PathBuf::from("/nonexistent/ccm/state.json"),
+            )
+        }
+
+        fn with_fakes(mux: FakeMux, worktrees: FakeWorktrees, state_path: PathBuf) -> Self {
             Self {
                 sessions: Vec::new(),
                 active_session: None,
@@ -349,7 +737,18 @@ mod tests {
                 own_session: "test-watcher".to_string(),
                 status_message: None,
                 pane_titles: HashMap::new(),
-                wezterm_binary: "wezterm".to_string(),
+                overlaps: BTreeMap::new(),
+                summaries: BTreeMap::new(),
+                pending_scan: None,
+                diff_view: None,
+                collapsed: BTreeSet::new(),
+                mux: Box::new(mux),
+                worktrees: Box::new(worktrees),
+                state_path,
+                dead_session_grace_secs: 300,
+                keep_dead_with_worktree: true,
+                merge_checks: Vec::new(),
+                pending_merge: None,
                 manual_navigation: false,
             }
         }
@@ -359,13 +758,20 @@ mod tests {
         Session {
             name: name.to_string(),
             tab_id: 100,
-            watcher_pane_id: 200,
             claude_pane_id,
-            shell_pane_id: 400,
+            panes: [("watcher".to_string(), 200), ("shell".to_string(), 400)].into(),
+            aux_panes: Default::default(),
             cwd: "/tmp".to_string(),
+            base_ref: None,
             created_at: Utc::now(),
             claude_status: None,
-            plans_pane_id: None,
+            dead_since: None,
+            adopted: false,
+            description: None,
+            tags: Vec::new(),
+            ticket: None,
+            notes: Vec::new(),
+            repo: None,
         }
     }
 
@@ -379,6 +785,7 @@ mod tests {
             sessions,
             active_session: None,
             version: 1,
+            ..State::default()
         }
     }
 
@@ -445,6 +852,87 @@ mod tests {
         assert!(app.manual_navigation);
     }
 
+    // ---------------------------------------------------------------
+    // repository groups
+    // ---------------------------------------------------------------
+
+    fn state_with_repos(sessions: &[(&str, &str)]) -> State {
+        let mut state = state_with_sessions(&sessions.iter().map(|(n, _)| *n).collect::<Vec<_>>());
+        for (session, (_, repo)) in state.sessions.iter_mut().zip(sessions) {
+            session.repo = Some(repo.to_string());
+        }
+        state
+    }
+
+    #[test]
+    fn apply_state_groups_sessions_by_repo() {
+        let mut app = App::new_for_test();
+        app.apply_state(state_with_repos(&[
+            ("a", "/src/api"),
+            ("b", "/src/web"),
+            ("c", "/src/api"),
+        ]));
+        let names: Vec<&str> = app.sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["a", "c", "b"]);
+        assert!(app.is_grouped());
+        assert_eq!(app.group_start(1), 0);
+        assert_eq!(app.group_len(2), 1);
+    }
+
+    #[test]
+    fn single_repo_is_not_grouped() {
+        let mut app = App::new_for_test();
+        app.apply_state(state_with_repos(&[("a", "/src/api"), ("b", "/src/api")]));
+        assert!(!app.is_grouped());
+        app.toggle_group();
+        assert!(app.collapsed.is_empty());
+    }
+
+    #[test]
+    fn collapsed_group_is_one_stop() {
+        let mut app = App::new_for_test();
+        app.apply_state(state_with_repos(&[
+            ("a", "/src/api"),
+            ("b", "/src/api"),
+            ("c", "/src/web"),
+        ]));
+        app.selected_index = 1;
+        app.toggle_group();
+        assert_eq!(app.selected_index, 0);
+        assert!(app.selected_session().is_none());
+
+        app.move_down();
+        assert_eq!(app.selected_index, 2);
+        app.move_down();
+        assert_eq!(app.selected_index, 0);
+        app.move_up();
+        assert_eq!(app.selected_index, 2);
+
+        app.selected_index = 0;
+        app.toggle_group();
+        assert_eq!(app.selected_session().unwrap().name, "a");
+        app.move_down();
+        assert_eq!(app.selected_index, 1);
+    }
+
+    #[test]
+    fn request_close_ignores_collapsed_group() {
+        let mut app = App::new_for_test();
+        app.apply_state(state_with_repos(&[("a", "/src/api"), ("b", "/src/web")]));
+        app.toggle_group();
+        app.request_close();
+        assert!(app.confirm_action.is_none());
+    }
+
+    #[test]
+    fn switch_to_selected_expands_collapsed_group() {
+        let mut app = App::new_for_test();
+        app.apply_state(state_with_repos(&[("a", "/src/api"), ("b", "/src/web")]));
+        app.toggle_group();
+        app.switch_to_selected();
+        assert!(app.collapsed.is_empty());
+    }
+
     // ---------------------------------------------------------------
     // request_close / confirm
     // ---------------------------------------------------------------
@@ -575,4 +1063,417 @@ mod tests {
         // should stay at 2 because manual_navigation is true
         assert_eq!(app.selected_index, 2);
     }
+
+    // ---------------------------------------------------------------
+    // reconcile / close (fake multiplexer)
+    // ---------------------------------------------------------------
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        mux: FakeMux,
+        worktrees: FakeWorktrees,
+        app: App,
+    }
+
+    /// Create one live session per name (claude, watcher and shell panes in
+    /// their own tab) and an App watching them.
+    fn fixture(names: &[&str]) -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("state.json");
+        let mux = FakeMux::new();
+        let worktrees = FakeWorktrees::new();
+
+        let mut sessions = Vec::new();
+        for name in names {
+            let wt = worktrees.new_worktree("/repo", Some(name), None).unwrap();
+            let claude = mux.spawn_tab(&wt.worktree_path).unwrap();
+            let watcher = mux
+                .split_pane(claude, SplitDirection::Left, 20, None)
+                .unwrap();
+            let shell = mux
+                .split_pane(claude, SplitDirection::Bottom, 25, None)
+                .unwrap();
+            let tab_id = mux.pane(claude).unwrap().tab_id;
+            sessions.push(Session {
+                name: name.to_string(),
+                tab_id,
+                claude_pane_id: claude,
+                panes: [("watcher".to_string(), watcher), ("shell".to_string(), shell)].into(),
+                aux_panes: Default::default(),
+                cwd: wt.worktree_path,
+                base_ref: None,
+                created_at: Utc::now(),
+                claude_status: None,
+                dead_since: None,
+                adopted: false,
+                description: None,
+                tags: Vec::new(),
+                ticket: None,
+                notes: Vec::new(),
+                repo: None,
+            });
+        }
+        state::update_at(&state_path, |state| {
+            state.sessions = sessions;
+            state.active_session = names.first().map(|n| n.to_string());
+            Ok(())
+        })
+        .unwrap();
+
+        let mut app = App::with_fakes(mux.clone(), worktrees.clone(), state_path);
+        app.refresh_state();
+        Fixture {
+            _dir: dir,
+            mux,
+            worktrees,
+            app,
+        }
+    }
+
+    #[test]
+    fn reconcile_keeps_live_sessions() {
+        let mut f = fixture(&["a", "b"]);
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 2);
+        assert!(f.app.status_message.is_none());
+    }
+
+    #[test]
+    fn reconcile_keeps_session_with_any_live_pane() {
+        let mut f = fixture(&["a"]);
+        let s = f.app.sessions[0].clone();
+        f.mux.close_externally(s.claude_pane_id);
+        f.mux.close_externally(s.panes["shell"]);
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 1);
+    }
+
+    fn close_tab(f: &Fixture, index: usize) {
+        let tab_id = f.app.sessions[index].tab_id;
+        for pane in f.mux.panes_in_tab(tab_id) {
+            f.mux.close_externally(pane.pane_id);
+        }
+    }
+
+    #[test]
+    fn reconcile_marks_dead_sessions() {
+        let mut f = fixture(&["a", "b"]);
+        close_tab(&f, 0);
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 2);
+        assert!(f.app.sessions[0].is_dead());
+        assert!(!f.app.sessions[1].is_dead());
+        assert_eq!(f.app.active_session.as_deref(), Some("a"));
+
+        // Marking is idempotent and does not touch the state file again
+        let version = f.app.last_version;
+        f.app.reconcile();
+        assert_eq!(f.app.last_version, version);
+
+        let events = journal::read(
+            &journal::path_for(&f.app.state_path),
+            &journal::Filter::default(),
+        )
+        .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].session.as_str(), &events[0].kind), ("a", &EventKind::Dead));
+    }
+
+    #[test]
+    fn reconcile_clears_dead_marker_when_panes_return() {
+        let mut f = fixture(&["a"]);
+        state::update_at(&f.app.state_path, |state| {
+            state.sessions[0].dead_since = Some(Utc::now());
+            Ok(())
+        })
+        .unwrap();
+        f.app.refresh_state();
+        f.app.reconcile();
+        assert!(!f.app.sessions[0].is_dead());
+    }
+
+    #[test]
+    fn reconcile_purges_after_grace_period() {
+        let mut f = fixture(&["a", "b"]);
+        close_tab(&f, 0);
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 2);
+
+        // Pretend the session died before the grace period
+        state::update_at(&f.app.state_path, |state| {
+            state.sessions[0].dead_since = Some(Utc::now() - chrono::Duration::seconds(301));
+            Ok(())
+        })
+        .unwrap();
+        f.app.refresh_state();
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 1);
+        assert_eq!(f.app.sessions[0].name, "b");
+        // "a" was active, so the active session is cleared
+        assert_eq!(f.app.active_session, None);
+    }
+
+    #[test]
+    fn reconcile_zero_grace_removes_immediately() {
+        let mut f = fixture(&["a", "b"]);
+        f.app.dead_session_grace_secs = 0;
+        close_tab(&f, 0);
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 1);
+        assert_eq!(f.app.sessions[0].name, "b");
+    }
+
+    #[test]
+    fn switch_to_dead_session_shows_hint() {
+        let mut f = fixture(&["a", "b"]);
+        close_tab(&f, 1);
+        f.app.reconcile();
+        f.app.selected_index = 1;
+        f.app.switch_to_selected();
+        assert!(f.app.status_message.as_deref().unwrap().contains("ccm restore b"));
+        assert_eq!(f.mux.calls("activate_tab"), 0);
+    }
+
+    fn give_worktree(f: &mut Fixture) {
+        let worktree = f._dir.path().join("a");
+        std::fs::create_dir(&worktree).unwrap();
+        state::update_at(&f.app.state_path, |state| {
+            state.sessions[0].cwd = worktree.to_string_lossy().to_string();
+            Ok(())
+        })
+        .unwrap();
+        f.app.refresh_state();
+    }
+
+    #[test]
+    fn reconcile_purges_dead_session_with_worktree_if_configured() {
+        let mut f = fixture(&["a"]);
+        give_worktree(&mut f);
+        f.app.dead_session_grace_secs = 0;
+        f.app.keep_dead_with_worktree = false;
+        f.mux.restart();
+        f.app.reconcile();
+        assert!(f.app.sessions.is_empty());
+    }
+
+    #[test]
+    fn reconcile_keeps_dead_session_with_worktree() {
+        let mut f = fixture(&["a"]);
+        give_worktree(&mut f);
+        f.app.dead_session_grace_secs = 0;
+        f.mux.restart();
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 1);
+        assert!(f.app.sessions[0].is_dead());
+    }
+
+    #[test]
+    fn reconcile_list_failure_keeps_state() {
+        let mut f = fixture(&["a"]);
+        f.mux.set_list_unavailable(true);
+        f.app.reconcile();
+        assert_eq!(f.app.sessions.len(), 1);
+        assert!(f
+            .app
+            .status_message
+            .as_deref()
+            .unwrap()
+            .starts_with("Reconcile error"));
+    }
+
+    #[test]
+    fn reconcile_uses_pane_title_fallback() {
+        let mut f = fixture(&["a"]);
+        let claude = f.app.sessions[0].claude_pane_id;
+        f.mux.set_title(claude, "✳ Working");
+        f.app.reconcile();
+        assert_eq!(f.app.pane_titles.get(&claude).unwrap(), "✳ Working");
+    }
+
+    #[test]
+    fn confirm_close_kills_panes_and_discards_worktree() {
+        let mut f = fixture(&["a", "b"]);
+        let tab_a = f.app.sessions[0].tab_id;
+        f.app.selected_index = 0;
+        f.app.request_close();
+        f.app.confirm_action_yes();
+        assert!(f.app.status_message.is_none());
+        assert_eq!(f.app.sessions.len(), 1);
+        assert!(f.mux.panes_in_tab(tab_a).is_empty());
+        assert_eq!(f.worktrees.discarded(), vec!["/repo-a"]);
+        assert!(!f.app.should_quit);
+
+        let archived = archive::list(&archive::dir_for(&f.app.state_path)).unwrap();
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived[0].session.name, "a");
+        assert!(!archived[0].merged);
+    }
+
+    #[test]
+    fn confirm_close_with_merge_failure_keeps_session() {
+        let mut f = fixture(&["a"]);
+        f.worktrees.fail_merge();
+        f.app.request_close_with_merge();
+        f.app.confirm_action_yes();
+        assert!(f
+            .app
+            .status_message
+            .as_deref()
+            .unwrap()
+            .starts_with("Close error"));
+        assert_eq!(f.app.sessions.len(), 1);
+        assert_eq!(f.mux.live_pane_ids().len(), 3);
+    }
+
+    #[test]
+    fn close_adopted_session_keeps_worktree() {
+        let mut f = fixture(&["a"]);
+        f.app.sessions[0].adopted = true;
+        f.app.request_close_with_merge();
+        assert!(f.app.confirm_action.is_none());
+        assert!(f.app.status_message.as_deref().unwrap().contains("--remove-worktree"));
+
+        state::update_at(&f.app.state_path, |state| {
+            state.sessions[0].adopted = true;
+            Ok(())
+        })
+        .unwrap();
+        f.app.request_close();
+        f.app.confirm_action_yes();
+        assert!(f.app.sessions.is_empty());
+        assert!(f.worktrees.discarded().is_empty());
+        assert_eq!(f.worktrees.worktrees().len(), 1);
+    }
+
+    /// Poll until the background merge checks have finished.
+    fn wait_for_merge_checks(app: &mut App) {
+        for _ in 0..500 {
+            app.poll_merge_checks();
+            if app.merge_output().is_none() {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        panic!("merge checks did not finish");
+    }
+
+    #[test]
+    fn confirm_merge_runs_checks_before_merging() {
+        let mut f = fixture(&["a"]);
+        f.app.merge_checks = vec!["true".to_string()];
+        f.app.sessions[0].cwd = f._dir.path().to_string_lossy().into_owned();
+        f.app.request_close_with_merge();
+        f.app.confirm_action_yes();
+        assert_eq!(f.app.merge_output().map(|(name, ..)| name), Some("a"));
+        assert!(f.worktrees.merged().is_empty());
+
+        wait_for_merge_checks(&mut f.app);
+        assert_eq!(f.worktrees.merged(), vec!["/repo-a"]);
+        assert!(f.app.sessions.is_empty());
+        let msg = f.app.status_message.as_deref().unwrap();
+        assert!(msg.starts_with("Merge checks for 'a' passed (log: "), "{msg}");
+    }
+
+    #[test]
+    fn running_merge_checks_show_latest_output() {
+        let mut f = fixture(&["a"]);
+        f.app.merge_checks =
+            vec!["echo step-one; while [ ! -e go ]; do sleep 0.01; done".to_string()];
+        f.app.sessions[0].cwd = f._dir.path().to_string_lossy().into_owned();
+        f.app.request_close_with_merge();
+        f.app.confirm_action_yes();
+
+        for _ in 0..500 {
+            f.app.poll_merge_checks();
+            if matches!(f.app.merge_output(), Some((_, _, Some(_)))) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let (name, log, last_line) = f.app.merge_output().unwrap();
+        assert_eq!((name, last_line), ("a", Some("step-one")));
+        assert_eq!(log, checks::log_path(&f.app.state_path, "a"));
+
+        std::fs::write(f._dir.path().join("go"), "").unwrap();
+        wait_for_merge_checks(&mut f.app);
+        assert!(f.app.merge_output().is_none());
+        assert!(f.app.sessions.is_empty());
+    }
+
+    #[test]
+    fn failed_merge_checks_keep_session() {
+        let mut f = fixture(&["a"]);
+        f.app.merge_checks = vec!["exit 2".to_string()];
+        f.app.sessions[0].cwd = f._dir.path().to_string_lossy().into_owned();
+        f.app.request_close_with_merge();
+        f.app.confirm_action_yes();
+
+        f.app.request_close_with_merge();
+        assert!(f.app.confirm_action.is_none());
+        assert!(f.app.status_message.as_deref().unwrap().contains("still running"));
+
+        wait_for_merge_checks(&mut f.app);
+        let msg = f.app.status_message.as_deref().unwrap();
+        assert!(msg.starts_with("Merge of 'a' aborted"), "{msg}");
+        assert!(msg.contains("exited with status 2"));
+        assert!(f.worktrees.merged().is_empty());
+        assert_eq!(f.app.sessions.len(), 1);
+        assert_eq!(f.mux.live_pane_ids().len(), 3);
+    }
+
+    #[test]
+    fn scan_sessions_flags_overlaps_and_summarizes() {
+        let mut f = fixture(&["a", "b"]);
+        let r = git::test_repo(f._dir.path());
+        for session in &mut f.app.sessions {
+            let wt = f._dir.path().join(&session.name).to_string_lossy().into_owned();
+            git::run(&r, &["worktree", "add", "-q", "-b", &session.name, &wt]).unwrap();
+            std::fs::write(Path::new(&wt).join("lib.rs"), &session.name).unwrap();
+            session.cwd = wt;
+        }
+
+        f.app.scan_sessions();
+        for _ in 0..500 {
+            f.app.poll_scan();
+            if f.app.pending_scan.is_none() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(f.app.overlaps_of("a"), ["b"]);
+        assert_eq!(f.app.overlaps_of("b"), ["a"]);
+        assert!(f.app.overlaps_of("c").is_empty());
+        assert_eq!(f.app.summaries["a"].dirty, 1);
+        assert_eq!(f.app.summaries["b"].ahead, 0);
+    }
+
+    #[test]
+    fn open_diff_of_selected_session() {
+        let mut f = fixture(&["a"]);
+        f.app.open_diff();
+        assert!(f.app.diff_view.is_none());
+        assert!(f.app.status_message.as_deref().unwrap().starts_with("Diff error"));
+
+        f.app.sessions[0].cwd = git::test_repo(f._dir.path());
+        f.app.open_diff();
+        assert!(f.app.diff_view.is_some());
+    }
+
+    #[test]
+    fn confirm_close_own_session_quits() {
+        let mut f = fixture(&["test-watcher"]);
+        f.app.request_close();
+        f.app.confirm_action_yes();
+        assert!(f.app.should_quit);
+        assert!(f.mux.live_pane_ids().is_empty());
+    }
+
+    #[test]
+    fn switch_to_selected_activates_tab() {
+        let mut f = fixture(&["a", "b"]);
+        f.app.move_down();
+        f.app.switch_to_selected();
+        assert_eq!(f.mux.active_tab(), Some(f.app.sessions[1].tab_id));
+        assert_eq!(f.app.active_session.as_deref(), Some("b"));
+    }
 }
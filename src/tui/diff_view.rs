@@ -0,0 +1,341 @@
+use std::cell::Cell;
+
+use crossterm::event::KeyCode;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+
+use crate::diff::{self, FileDiff};
+use crate::error::CcmError;
+use crate::git;
+use crate::session::Session;
+
+/// Review view of a session's diff against its base: a file list and the
+/// colored hunks of the selected file. Used by `ccm diff` and by the `d` key
+/// of the tab-watcher.
+pub struct DiffView {
+    title: String,
+    files: Vec<FileDiff>,
+    selected: usize,
+    scroll: usize,
+    /// Height of the diff pane at the last draw, for page scrolling.
+    page: Cell<usize>,
+}
+
+impl DiffView {
+    pub fn new(title: String, files: Vec<FileDiff>) -> Self {
+        Self {
+            title,
+            files,
+            selected: 0,
+            scroll: 0,
+            page: Cell::new(20),
+        }
+    }
+
+    /// Diff of `session`'s worktree against its base.
+    pub fn for_session(session: &Session) -> Result<Self, CcmError> {
+        let base = git::session_base(session)?;
+        let files = diff::session_diff(session)?;
+        Ok(Self::new(format!("{} vs {base}", session.name), files))
+    }
+
+    /// Handle a key press. Returns false when the view should be closed.
+    pub fn handle_key(&mut self, code: KeyCode) -> bool {
+        let page = self.page.get().max(1) as isize;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return false,
+            KeyCode::Char('j') | KeyCode::Down => self.scroll_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.scroll_by(-1),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll_by(page),
+            KeyCode::PageUp => self.scroll_by(-page),
+            KeyCode::Char('g') | KeyCode::Home => self.scroll = 0,
+            KeyCode::Char('G') | KeyCode::End => self.scroll = self.max_scroll(),
+            KeyCode::Char('n') => self.next_hunk(),
+            KeyCode::Char('N') | KeyCode::Char('p') => self.prev_hunk(),
+            KeyCode::Tab | KeyCode::Char('J') | KeyCode::Char(']') => self.select_file(1),
+            KeyCode::BackTab | KeyCode::Char('K') | KeyCode::Char('[') => self.select_file(-1),
+            _ => {}
+        }
+        true
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.scroll = self
+            .scroll
+            .saturating_add_signed(delta)
+            .min(self.max_scroll());
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.file_lines().len().saturating_sub(1)
+    }
+
+    /// Select the next (`1`) or previous (`-1`) file, wrapping around.
+    fn select_file(&mut self, delta: isize) {
+        if self.files.is_empty() {
+            return;
+        }
+        let len = self.files.len() as isize;
+        self.selected = (self.selected as isize + delta).rem_euclid(len) as usize;
+        self.scroll = 0;
+    }
+
+    /// Scroll to the next hunk, continuing with the next file's first hunk.
+    fn next_hunk(&mut self) {
+        if let Some(&offset) = self.hunk_offsets().iter().find(|&&o| o > self.scroll) {
+            self.scroll = offset;
+            return;
+        }
+        if self.selected + 1 < self.files.len() {
+            self.selected += 1;
+            self.scroll = self.hunk_offsets().first().copied().unwrap_or(0);
+        }
+    }
+
+    /// Scroll to the previous hunk, continuing with the previous file's last hunk.
+    fn prev_hunk(&mut self) {
+        if let Some(&offset) = self.hunk_offsets().iter().rev().find(|&&o| o < self.scroll) {
+            self.scroll = offset;
+            return;
+        }
+        if self.selected > 0 {
+            self.selected -= 1;
+            self.scroll = self.hunk_offsets().last().copied().unwrap_or(0);
+        }
+    }
+
+    /// Line index of each hunk header in `file_lines`.
+    fn hunk_offsets(&self) -> Vec<usize> {
+        let Some(file) = self.files.get(self.selected) else {
+            return Vec::new();
+        };
+        let mut offset = file.header.len();
+        file.hunks
+            .iter()
+            .map(|hunk| {
+                let start = offset;
+                offset += 1 + hunk.lines.len();
+                start
+            })
+            .collect()
+    }
+
+    /// Rendered lines of the selected file.
+    fn file_lines(&self) -> Vec<Line<'static>> {
+        let Some(file) = self.files.get(self.selected) else {
+            return vec![Line::from(Span::styled(
+                "(no changes)",
+                Style::default().fg(Color::DarkGray),
+            ))];
+        };
+        let mut lines: Vec<Line> = file
+            .header
+            .iter()
+            .map(|h| Line::from(Span::styled(h.clone(), Style::default().fg(Color::DarkGray))))
+            .collect();
+        for hunk in &file.hunks {
+            lines.push(Line::from(Span::styled(
+                hunk.header.clone(),
+                Style::default().fg(Color::Cyan),
+            )));
+            for line in &hunk.lines {
+                let color = match line.as_bytes().first() {
+                    Some(b'+') => Color::Green,
+                    Some(b'-') => Color::Red,
+                    Some(b'\\') => Color::DarkGray,
+                    _ => Color::Reset,
+                };
+                lines.push(Line::from(Span::styled(
+                    line.clone(),
+                    Style::default().fg(color),
+                )));
+            }
+        }
+        lines
+    }
+
+    pub fn draw(&self, f: &mut Frame, area: Rect) {
+        let [body, help_area] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(area);
+
+        // Side by side when there is room, stacked in the narrow sidebar
+        let [list_area, diff_area] = if body.width >= 80 {
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Min(1)]).areas(body)
+        } else {
+            let list_height = (self.files.len() as u16 + 2)
+                .min(body.height / 3 + 1)
+                .max(3);
+            Layout::vertical([Constraint::Length(list_height), Constraint::Min(1)]).areas(body)
+        };
+
+        let file_items: Vec<Line> = self
+            .files
+            .iter()
+            .enumerate()
+            .map(|(i, file)| {
+                let (ins, del) = file.stat();
+                let marker = if i == self.selected { "> " } else { "  " };
+                let style = if i == self.selected {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{marker}{}", file.path), style),
+                    Span::styled(format!(" +{ins}"), Style::default().fg(Color::Green)),
+                    Span::styled(format!(" -{del}"), Style::default().fg(Color::Red)),
+                ])
+            })
+            .collect();
+        // Keep the selected file visible in a short list
+        let list_rows = list_area.height.saturating_sub(2) as usize;
+        let list_scroll = (self.selected + 1).saturating_sub(list_rows.max(1));
+        let list = Paragraph::new(file_items)
+            .scroll((list_scroll as u16, 0))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" {} ({} files) ", self.title, self.files.len())),
+            );
+        f.render_widget(list, list_area);
+
+        self.page.set(diff_area.height.saturating_sub(2) as usize);
+        let path = self
+            .files
+            .get(self.selected)
+            .map(|file| file.path.as_str())
+            .unwrap_or_default();
+        let diff = Paragraph::new(self.file_lines())
+            .scroll((self.scroll as u16, 0))
+            .block(Block::default().borders(Borders::ALL).title(format!(" {path} ")));
+        f.render_widget(diff, diff_area);
+
+        let help = Line::from(vec![
+            Span::styled(" j/k", Style::default().fg(Color::Yellow)),
+            Span::styled(" scroll ", Style::default().fg(Color::DarkGray)),
+            Span::styled("n/p", Style::default().fg(Color::Yellow)),
+            Span::styled(" hunk ", Style::default().fg(Color::DarkGray)),
+            Span::styled("Tab", Style::default().fg(Color::Yellow)),
+            Span::styled(" file ", Style::default().fg(Color::DarkGray)),
+            Span::styled("q", Style::default().fg(Color::Yellow)),
+            Span::styled(" back", Style::default().fg(Color::DarkGray)),
+        ]);
+        f.render_widget(Paragraph::new(help), help_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::Hunk;
+
+    fn file(path: &str, hunks: &[usize]) -> FileDiff {
+        FileDiff {
+            path: path.to_string(),
+            header: vec!["new file mode 100644".to_string()],
+            hunks: hunks
+                .iter()
+                .map(|&n| Hunk {
+                    header: "@@ -1 +1 @@".to_string(),
+                    lines: vec!["+x".to_string(); n],
+                })
+                .collect(),
+        }
+    }
+
+    fn view() -> DiffView {
+        DiffView::new(
+            "s vs main".to_string(),
+            vec![file("a.rs", &[2, 3]), file("b.rs", &[1])],
+        )
+    }
+
+    #[test]
+    fn hunk_navigation_crosses_files() {
+        let mut v = view();
+        assert_eq!(v.hunk_offsets(), [1, 4]);
+
+        v.handle_key(KeyCode::Char('n'));
+        assert_eq!((v.selected, v.scroll), (0, 1));
+        v.handle_key(KeyCode::Char('n'));
+        assert_eq!((v.selected, v.scroll), (0, 4));
+        v.handle_key(KeyCode::Char('n'));
+        assert_eq!((v.selected, v.scroll), (1, 1));
+        v.handle_key(KeyCode::Char('n'));
+        assert_eq!((v.selected, v.scroll), (1, 1));
+
+        v.handle_key(KeyCode::Char('p'));
+        assert_eq!((v.selected, v.scroll), (0, 4));
+        v.handle_key(KeyCode::Char('p'));
+        assert_eq!((v.selected, v.scroll), (0, 1));
+        v.handle_key(KeyCode::Char('p'));
+        assert_eq!((v.selected, v.scroll), (0, 1));
+    }
+
+    #[test]
+    fn file_selection_wraps_and_resets_scroll() {
+        let mut v = view();
+        v.handle_key(KeyCode::Char('G'));
+        assert_eq!(v.scroll, 7);
+        v.handle_key(KeyCode::Tab);
+        assert_eq!((v.selected, v.scroll), (1, 0));
+        v.handle_key(KeyCode::Tab);
+        assert_eq!(v.selected, 0);
+        v.handle_key(KeyCode::BackTab);
+        assert_eq!(v.selected, 1);
+    }
+
+    #[test]
+    fn scrolling_is_clamped() {
+        let mut v = view();
+        v.handle_key(KeyCode::Char('k'));
+        assert_eq!(v.scroll, 0);
+        for _ in 0..20 {
+            v.handle_key(KeyCode::Char('j'));
+        }
+        assert_eq!(v.scroll, v.max_scroll());
+        assert!(!v.handle_key(KeyCode::Char('q')));
+    }
+
+    #[test]
+    fn file_lines_color_changes() {
+        let mut v = view();
+        v.files[0].hunks[0].lines = vec!["+add".into(), "-del".into(), " ctx".into()];
+        let lines = v.file_lines();
+        let color = |i: usize| lines[i].spans[0].style.fg;
+        assert_eq!(color(0), Some(Color::DarkGray));
+        assert_eq!(color(1), Some(Color::Cyan));
+        assert_eq!(color(2), Some(Color::Green));
+        assert_eq!(color(3), Some(Color::Red));
+        assert_eq!(color(4), Some(Color::Reset));
+
+        let empty = DiffView::new("s".to_string(), Vec::new());
+        assert_eq!(empty.file_lines().len(), 1);
+        assert_eq!(empty.max_scroll(), 0);
+    }
+
+    #[test]
+    fn draw_fits_narrow_and_wide_areas() {
+        use ratatui::backend::TestBackend;
+        use ratatui::Terminal;
+
+        for (width, height) in [(24, 4), (30, 12), (120, 40)] {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            let v = view();
+            terminal.draw(|f| v.draw(f, f.area())).unwrap();
+            let text: String = terminal
+                .backend()
+                .buffer()
+                .content()
+                .iter()
+                .map(|c| c.symbol())
+                .collect();
+            assert!(text.contains("a.rs"), "{width}x{height}: {text}");
+        }
+    }
+}
@@ -1,4 +1,5 @@
 mod app;
+mod diff_view;
 mod event;
 mod ui;
 
@@ -6,27 +7,51 @@ use std::io;
 use std::time::Duration;
 
 use crossterm::event::{
-    DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEventKind, MouseEventKind,
+    self as crossterm_event, DisableMouseCapture, EnableMouseCapture, Event as CrosstermEvent,
+    KeyCode, KeyEventKind, MouseEventKind,
 };
 use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
 use ratatui::backend::CrosstermBackend;
 use ratatui::Terminal;
 
 use crate::config::Config;
+use crate::mux;
+use crate::state;
+use crate::worktree;
 
 use self::app::App;
+pub use self::diff_view::DiffView;
 use self::event::{Event, EventHandler};
 
 pub fn run(session_name: &str, config: &Config) -> anyhow::Result<()> {
+    with_terminal(|terminal| run_event_loop(terminal, session_name, config))
+}
+
+/// Show `view` full-screen until it is closed (`ccm diff`).
+pub fn run_diff(mut view: DiffView) -> anyhow::Result<()> {
+    with_terminal(|terminal| loop {
+        terminal.draw(|f| view.draw(f, f.area()))?;
+        if let CrosstermEvent::Key(key) = crossterm_event::read()? {
+            if key.kind == KeyEventKind::Press && !view.handle_key(key.code) {
+                return Ok(());
+            }
+        }
+    })
+}
+
+/// Run `f` on the alternate screen in raw mode, restoring the terminal
+/// afterwards, even on panic.
+fn with_terminal(
+    f: impl FnOnce(&mut Terminal<CrosstermBackend<io::Stdout>>) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
     terminal::enable_raw_mode()?;
     let mut stdout = io::stdout();
     crossterm::execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
-        run_event_loop(&mut terminal, session_name, config)
-    }));
+    let result =
+        std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(&mut terminal)));
 
     // Cleanup always runs, even on panic
     let _ = terminal::disable_raw_mode();
@@ -49,7 +74,16 @@ fn run_event_loop(
     config: &Config,
 ) -> anyhow::Result<()> {
     let events = EventHandler::new(Duration::from_secs(config.tui.tick_interval_secs))?;
-    let mut app = App::new(session_name, &config.wezterm.binary);
+    let mut app = App::new(
+        session_name,
+        mux::from_config(config),
+        worktree::from_config(config),
+        state::state_path()?,
+        config.tui.dead_session_grace_secs,
+        config.tui.keep_dead_with_worktree,
+        config.merge.checks.clone(),
+    );
+    app.scan_sessions();
     let mut last_area_width: u16 = 0;
 
     loop {
@@ -64,6 +98,14 @@ fn run_event_loop(
                     continue;
                 }
 
+                // The diff view takes all keys until it is closed
+                if let Some(view) = app.diff_view.as_mut() {
+                    if !view.handle_key(key.code) {
+                        app.diff_view = None;
+                    }
+                    continue;
+                }
+
                 // Clear status from previous key press
                 app.status_message = None;
 
@@ -85,6 +127,8 @@ fn run_event_loop(
                     KeyCode::Enter => app.switch_to_selected(),
                     KeyCode::Char('c') => app.request_close(),
                     KeyCode::Char('m') => app.request_close_with_merge(),
+                    KeyCode::Char('d') => app.open_diff(),
+                    KeyCode::Char('z') => app.toggle_group(),
                     KeyCode::Char('r') => {
                         app.reconcile();
                         app.refresh_state();
@@ -93,7 +137,7 @@ fn run_event_loop(
                 }
             }
             Event::Mouse(mouse) => {
-                if let MouseEventKind::Down(_) = mouse.kind {
+                if let (MouseEventKind::Down(_), None) = (mouse.kind, &app.diff_view) {
                     app.select_by_click(mouse.row, last_area_width);
                 }
             }
@@ -102,7 +146,10 @@ fn run_event_loop(
                 app.refresh_state();
             }
             Event::Tick => {
+                app.poll_merge_checks();
+                app.poll_scan();
                 app.reconcile();
+                app.scan_sessions();
             }
         }
 
@@ -6,6 +6,8 @@ use ratatui::Frame;
 use unicode_width::UnicodeWidthStr;
 
 use super::app::{App, ConfirmAction};
+use crate::session::Session;
+use crate::summary::GitSummary;
 
 /// Wrap text to fit within the given display width (in terminal columns).
 /// Handles multi-byte UTF-8 and wide characters (CJK, emoji) correctly.
@@ -67,7 +69,14 @@ pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
 
 /// Build the display text for a session name line.
 /// Returns something like `" > my-session  ●"` or `"   my-session"`.
-pub fn session_name_text(name: &str, is_selected: bool, is_active: bool, is_own: bool) -> String {
+/// Dead sessions (no live panes) get a trailing `✗`.
+pub fn session_name_text(
+    name: &str,
+    is_selected: bool,
+    is_active: bool,
+    is_own: bool,
+    is_dead: bool,
+) -> String {
     // INVARIANT: Both prefixes must have the same display width (3 columns).
     // select_by_click() depends on this for accurate row counting.
     let prefix = if is_selected { " > " } else { "   " };
@@ -77,7 +86,71 @@ pub fn session_name_text(name: &str, is_selected: bool, is_active: bool, is_own:
         (false, true) => "  ◆",
         (false, false) => "",
     };
-    format!("{prefix}{name}{suffix}")
+    let dead = if is_dead { "  ✗" } else { "" };
+    format!("{prefix}{name}{suffix}{dead}")
+}
+
+/// Build the header line of a repository group, e.g. `" ▾ backend (2)"`.
+/// A collapsed group (`▸`) is selected through its header and then gets the
+/// same 3-column selection prefix as a session name.
+pub fn group_header_text(label: &str, count: usize, collapsed: bool, is_selected: bool) -> String {
+    let prefix = if is_selected { " > " } else { " " };
+    let arrow = if collapsed { "▸" } else { "▾" };
+    format!("{prefix}{arrow} {label} ({count})")
+}
+
+/// Build the detail lines shown under a session name, with their color:
+/// the description, tags and ticket, the base ref (`"     from release/1.2"`),
+/// the git summary and the sessions whose changes touch the same files.
+pub fn session_detail_lines(
+    session: &Session,
+    summary: Option<&GitSummary>,
+    overlaps: &[String],
+) -> Vec<(String, Color)> {
+    let mut lines = Vec::new();
+    if let Some(description) = session.description.as_deref() {
+        lines.push((format!("     {description}"), Color::White));
+    }
+    if let Some(labels) = session.labels() {
+        lines.push((format!("     {labels}"), Color::Magenta));
+    }
+    if let Some(base) = session.base_ref.as_deref() {
+        lines.push((format!("     from {base}"), Color::DarkGray));
+    }
+    if let Some(summary) = summary {
+        let color = if summary.is_empty() {
+            Color::DarkGray
+        } else {
+            Color::Green
+        };
+        lines.push((format!("     {}", summary_text(summary)), color));
+    }
+    if !overlaps.is_empty() {
+        lines.push((
+            format!("     ! overlaps {}", overlaps.join(", ")),
+            Color::Red,
+        ));
+    }
+    lines
+}
+
+/// Compact git summary, e.g. `"↑2 ↓1  3 dirty  +120 -4"` or `"no changes"`.
+pub fn summary_text(summary: &GitSummary) -> String {
+    let mut parts = Vec::new();
+    if summary.ahead > 0 || summary.behind > 0 {
+        parts.push(format!("↑{} ↓{}", summary.ahead, summary.behind));
+    }
+    if summary.dirty > 0 {
+        parts.push(format!("{} dirty", summary.dirty));
+    }
+    if summary.insertions > 0 || summary.deletions > 0 {
+        parts.push(format!("+{} -{}", summary.insertions, summary.deletions));
+    }
+    if parts.is_empty() {
+        "no changes".to_string()
+    } else {
+        parts.join("  ")
+    }
 }
 
 /// Render a title inside a Unicode box, appending Lines to the given vec.
@@ -118,6 +191,180 @@ fn render_title_box(lines: &mut Vec<Line>, title: &str, indent: usize, box_width
     lines.push(Line::from(Span::styled(bottom, style)));
 }
 
+pub fn draw(f: &mut Frame, app: &App) {
+    let area = f.area();
+
+    if let Some(ref view) = app.diff_view {
+        view.draw(f, area);
+        return;
+    }
+
+    let chunks = Layout::vertical([
+        Constraint::Min(1),    // session list
+        Constraint::Length(1), // help line
+    ])
+    .split(area);
+
+    // Session list
+    let mut lines: Vec<Line> = Vec::new();
+
+    lines.push(Line::from(Span::styled(
+        " CCM Sessions",
+        Style::default()
+            .fg(Color::Cyan)
+            .add_modifier(Modifier::BOLD),
+    )));
+    lines.push(Line::from(Span::styled(
+        " ────────────",
+        Style::default().fg(Color::DarkGray),
+    )));
+
+    let indent: usize = 3;
+    let box_width = (area.width as usize).saturating_sub(indent);
+
+    if app.sessions.is_empty() {
+        lines.push(Line::from(Span::styled(
+            " (no sessions)",
+            Style::default().fg(Color::DarkGray),
+        )));
+    } else {
+        let grouped = app.is_grouped();
+        for (i, session) in app.sessions.iter().enumerate() {
+            let collapsed = app.is_collapsed(session);
+            if grouped && app.group_start(i) == i {
+                let is_selected = collapsed && i == app.selected_index;
+                let text =
+                    group_header_text(session.repo_label(), app.group_len(i), collapsed, is_selected);
+                let style = if is_selected {
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)
+                };
+                for wrapped_line in wrap_text(&text, area.width as usize) {
+                    lines.push(Line::from(Span::styled(wrapped_line, style)));
+                }
+            }
+            if collapsed {
+                continue;
+            }
+
+            let is_selected = i == app.selected_index;
+            let is_active = app.active_session.as_deref() == Some(&session.name);
+            let is_own = session.name == app.own_session;
+            let is_dead = session.is_dead();
+
+            let style = if is_selected {
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else if is_dead {
+                Style::default().fg(Color::DarkGray)
+            } else if is_own {
+                Style::default().fg(Color::Cyan)
+            } else if is_active {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::White)
+            };
+
+            let text =
+                session_name_text(&session.name, is_selected, is_active, is_own, is_dead);
+            for wrapped_line in wrap_text(&text, area.width as usize) {
+                lines.push(Line::from(Span::styled(wrapped_line, style)));
+            }
+
+            let summary = app.summaries.get(&session.name);
+            for (detail, color) in
+                session_detail_lines(session, summary, app.overlaps_of(&session.name))
+            {
+                for wrapped_line in wrap_text(&detail, area.width as usize) {
+                    lines.push(Line::from(Span::styled(
+                        wrapped_line,
+                        Style::default().fg(color),
+                    )));
+                }
+            }
+
+            // Render pane title box if available
+            if let Some(title) = app.pane_titles.get(&session.claude_pane_id) {
+                if !title.is_empty() && box_width > 4 {
+                    render_title_box(&mut lines, title, indent, box_width);
+                }
+            }
+        }
+    }
+
+    if let Some((name, log, last_line)) = app.merge_output() {
+        lines.push(Line::from(""));
+        let msg = format!(" Running merge checks for '{name}'...");
+        for wrapped_line in wrap_text(&msg, area.width as usize) {
+            lines.push(Line::from(Span::styled(
+                wrapped_line,
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        let output = last_line.map(|l| format!("   {l}"));
+        let log = format!("   log: {}", log.display());
+        for msg in output.iter().chain([&log]) {
+            for wrapped_line in wrap_text(msg, area.width as usize) {
+                lines.push(Line::from(Span::styled(
+                    wrapped_line,
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+        }
+    }
+
+    // Confirm action overlay
+    if let Some(ref action) = app.confirm_action {
+        let msg = match action {
+            ConfirmAction::Close(name) => format!(" Close '{name}'? [y/n]"),
+            ConfirmAction::CloseWithMerge(name) => {
+                format!(" Close '{name}' with merge? [y/n]")
+            }
+        };
+        let confirm_style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
+        lines.push(Line::from(""));
+        for wrapped_line in wrap_text(&msg, area.width as usize) {
+            lines.push(Line::from(Span::styled(wrapped_line, confirm_style)));
+        }
+    }
+
+    // Status message
+    if let Some(ref msg) = app.status_message {
+        let status_style = Style::default().fg(Color::Red);
+        lines.push(Line::from(""));
+        for wrapped_line in wrap_text(&format!(" {msg}"), area.width as usize) {
+            lines.push(Line::from(Span::styled(wrapped_line, status_style)));
+        }
+    }
+
+    let session_widget = Paragraph::new(lines).block(Block::default().borders(Borders::NONE));
+    f.render_widget(session_widget, chunks[0]);
+
+    // Help line
+    let help = Line::from(vec![
+        Span::styled(" j/k", Style::default().fg(Color::Yellow)),
+        Span::styled(" nav ", Style::default().fg(Color::DarkGray)),
+        Span::styled("Enter", Style::default().fg(Color::Yellow)),
+        Span::styled(" switch ", Style::default().fg(Color::DarkGray)),
+        Span::styled("c", Style::default().fg(Color::Yellow)),
+        Span::styled(" close ", Style::default().fg(Color::DarkGray)),
+        Span::styled("m", Style::default().fg(Color::Yellow)),
+        Span::styled(" merge ", Style::default().fg(Color::DarkGray)),
+        Span::styled("d", Style::default().fg(Color::Yellow)),
+        Span::styled(" diff ", Style::default().fg(Color::DarkGray)),
+        Span::styled("z", Style::default().fg(Color::Yellow)),
+        Span::styled(" fold ", Style::default().fg(Color::DarkGray)),
+        Span::styled("q", Style::default().fg(Color::Yellow)),
+        Span::styled(" quit", Style::default().fg(Color::DarkGray)),
+    ]);
+    let help_widget = Paragraph::new(help);
+    f.render_widget(help_widget, chunks[1]);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -194,153 +441,133 @@ mod tests {
 
     #[test]
     fn session_name_text_selected_active_own() {
-        let text = session_name_text("my-session", true, true, true);
+        let text = session_name_text("my-session", true, true, true, false);
         assert_eq!(text, " > my-session  ● ◆");
     }
 
     #[test]
     fn session_name_text_unselected_inactive() {
-        let text = session_name_text("foo", false, false, false);
+        let text = session_name_text("foo", false, false, false, false);
         assert_eq!(text, "   foo");
     }
 
     #[test]
     fn session_name_text_selected_not_active() {
-        let text = session_name_text("bar", true, false, false);
+        let text = session_name_text("bar", true, false, false, false);
         assert_eq!(text, " > bar");
     }
 
     #[test]
     fn session_name_text_own_not_active() {
-        let text = session_name_text("baz", false, false, true);
+        let text = session_name_text("baz", false, false, true, false);
         assert_eq!(text, "   baz  ◆");
     }
 
+    #[test]
+    fn session_name_text_dead() {
+        assert_eq!(session_name_text("gone", false, false, false, true), "   gone  ✗");
+        assert_eq!(
+            session_name_text("gone", true, true, false, true),
+            " > gone  ●  ✗"
+        );
+    }
+
     #[test]
     fn session_name_wraps_when_long() {
         // " > " (3) + "a"*10 + "  ●" (3) = 16 columns total
         let name = "a".repeat(10);
-        let text = session_name_text(&name, true, true, false);
+        let text = session_name_text(&name, true, true, false, false);
         let wrapped = wrap_text(&text, 10);
         assert!(wrapped.len() > 1, "long session name should wrap");
     }
 
+    #[test]
+    fn session_name_wraps_by_display_width() {
+        let text = session_name_text("gj/main-認証機能の実装", true, false, false, false);
+        let lines = wrap_text(&text, 12);
+        assert!(lines.len() > 1);
+        for line in &lines {
+            assert!(UnicodeWidthStr::width(line.as_str()) <= 12, "{line}");
+        }
+        assert_eq!(lines.concat().replace(' ', ""), text.replace(' ', ""));
+    }
+
+    #[test]
+    fn group_header_text_shows_fold_state() {
+        assert_eq!(group_header_text("api", 2, false, false), " ▾ api (2)");
+        assert_eq!(group_header_text("api", 2, true, false), " ▸ api (2)");
+        assert_eq!(group_header_text("api", 2, true, true), " > ▸ api (2)");
+    }
+
     #[test]
     fn session_name_prefix_same_width() {
         // " > " and "   " have the same display width (3 columns)
-        let selected = session_name_text("test", true, false, false);
-        let unselected = session_name_text("test", false, false, false);
+        let selected = session_name_text("test", true, false, false, false);
+        let unselected = session_name_text("test", false, false, false, false);
         assert_eq!(
             UnicodeWidthStr::width(selected.as_str()),
             UnicodeWidthStr::width(unselected.as_str()),
             "selected and unselected prefixes should have equal display width"
         );
     }
-}
-
-pub fn draw(f: &mut Frame, app: &App) {
-    let area = f.area();
-
-    let chunks = Layout::vertical([
-        Constraint::Min(1),    // session list
-        Constraint::Length(1), // help line
-    ])
-    .split(area);
-
-    // Session list
-    let mut lines: Vec<Line> = Vec::new();
-
-    lines.push(Line::from(Span::styled(
-        " CCM Sessions",
-        Style::default()
-            .fg(Color::Cyan)
-            .add_modifier(Modifier::BOLD),
-    )));
-    lines.push(Line::from(Span::styled(
-        " ────────────",
-        Style::default().fg(Color::DarkGray),
-    )));
-
-    let indent: usize = 3;
-    let box_width = (area.width as usize).saturating_sub(indent);
-
-    if app.sessions.is_empty() {
-        lines.push(Line::from(Span::styled(
-            " (no sessions)",
-            Style::default().fg(Color::DarkGray),
-        )));
-    } else {
-        for (i, session) in app.sessions.iter().enumerate() {
-            let is_selected = i == app.selected_index;
-            let is_active = app.active_session.as_deref() == Some(&session.name);
-            let is_own = session.name == app.own_session;
-
-            let style = if is_selected {
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD)
-            } else if is_own {
-                Style::default().fg(Color::Cyan)
-            } else if is_active {
-                Style::default().fg(Color::Green)
-            } else {
-                Style::default().fg(Color::White)
-            };
 
-            let text = session_name_text(&session.name, is_selected, is_active, is_own);
-            for wrapped_line in wrap_text(&text, area.width as usize) {
-                lines.push(Line::from(Span::styled(wrapped_line, style)));
-            }
-
-            // Render pane title box if available
-            if let Some(title) = app.pane_titles.get(&session.claude_pane_id) {
-                if !title.is_empty() && box_width > 4 {
-                    render_title_box(&mut lines, title, indent, box_width);
-                }
-            }
-        }
+    #[test]
+    fn session_detail_lines_show_base_ref_and_overlaps() {
+        let mut session: Session = serde_json::from_str(
+            r#"{"name":"s","tab_id":1,"claude_pane_id":2,"cwd":"/tmp","created_at":"2025-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        assert!(session_detail_lines(&session, None, &[]).is_empty());
+        session.base_ref = Some("release/1.2".to_string());
+        assert_eq!(
+            session_detail_lines(&session, None, &[]),
+            [("     from release/1.2".to_string(), Color::DarkGray)]
+        );
+        let overlaps = ["a".to_string(), "b".to_string()];
+        assert_eq!(
+            session_detail_lines(&session, None, &overlaps)[1],
+            ("     ! overlaps a, b".to_string(), Color::Red)
+        );
     }
 
-    // Confirm action overlay
-    if let Some(ref action) = app.confirm_action {
-        let msg = match action {
-            ConfirmAction::Close(name) => format!(" Close '{name}'? [y/n]"),
-            ConfirmAction::CloseWithMerge(name) => {
-                format!(" Close '{name}' with merge? [y/n]")
-            }
-        };
-        let confirm_style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD);
-        lines.push(Line::from(""));
-        for wrapped_line in wrap_text(&msg, area.width as usize) {
-            lines.push(Line::from(Span::styled(wrapped_line, confirm_style)));
-        }
+    #[test]
+    fn session_detail_lines_show_metadata() {
+        let mut session: Session = serde_json::from_str(
+            r#"{"name":"s","tab_id":1,"claude_pane_id":2,"cwd":"/tmp","created_at":"2025-01-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+        session.description = Some("Fix login redirect".to_string());
+        session.tags = vec!["auth".to_string()];
+        session.ticket = Some("WEB-481".to_string());
+        session.base_ref = Some("main".to_string());
+        let lines = session_detail_lines(&session, None, &[]);
+        assert_eq!(
+            lines[..2],
+            [
+                ("     Fix login redirect".to_string(), Color::White),
+                ("     #auth  WEB-481".to_string(), Color::Magenta),
+            ]
+        );
+        assert_eq!(lines[2].0, "     from main");
     }
 
-    // Status message
-    if let Some(ref msg) = app.status_message {
-        let status_style = Style::default().fg(Color::Red);
-        lines.push(Line::from(""));
-        for wrapped_line in wrap_text(&format!(" {msg}"), area.width as usize) {
-            lines.push(Line::from(Span::styled(wrapped_line, status_style)));
-        }
+    #[test]
+    fn summary_text_compacts_counts() {
+        assert_eq!(summary_text(&GitSummary::default()), "no changes");
+        let summary = GitSummary {
+            ahead: 2,
+            behind: 1,
+            dirty: 3,
+            insertions: 120,
+            deletions: 4,
+        };
+        assert_eq!(summary_text(&summary), "↑2 ↓1  3 dirty  +120 -4");
+        assert_eq!(
+            summary_text(&GitSummary {
+                dirty: 1,
+                ..GitSummary::default()
+            }),
+            "1 dirty"
+        );
     }
-
-    let session_widget = Paragraph::new(lines).block(Block::default().borders(Borders::NONE));
-    f.render_widget(session_widget, chunks[0]);
-
-    // Help line
-    let help = Line::from(vec![
-        Span::styled(" j/k", Style::default().fg(Color::Yellow)),
-        Span::styled(" nav ", Style::default().fg(Color::DarkGray)),
-        Span::styled("Enter", Style::default().fg(Color::Yellow)),
-        Span::styled(" switch ", Style::default().fg(Color::DarkGray)),
-        Span::styled("c", Style::default().fg(Color::Yellow)),
-        Span::styled(" close ", Style::default().fg(Color::DarkGray)),
-        Span::styled("m", Style::default().fg(Color::Yellow)),
-        Span::styled(" merge ", Style::default().fg(Color::DarkGray)),
-        Span::styled("q", Style::default().fg(Color::Yellow)),
-        Span::styled(" quit", Style::default().fg(Color::DarkGray)),
-    ]);
-    let help_widget = Paragraph::new(help);
-    f.render_widget(help_widget, chunks[1]);
 }
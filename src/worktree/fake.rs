@@ -0,0 +1,127 @@
+//! In-memory worktree provider for command tests.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use super::{NewOutput, WorktreeProvider};
+use crate::error::CcmError;
+
+/// Simulated worktree provider. Clones share state, so a test can keep a
+/// handle for inspection after passing a boxed copy to the code under test.
+#[derive(Clone, Default)]
+pub struct FakeWorktrees {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    /// worktree path -> branch
+    worktrees: BTreeMap<String, String>,
+    merged: Vec<String>,
+    discarded: Vec<String>,
+    next_suffix: u32,
+    fail_new: bool,
+    fail_merge: bool,
+    empty_branch: bool,
+}
+
+impl FakeWorktrees {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the next `new_worktree` calls fail.
+    pub fn fail_new(&self) {
+        self.inner.borrow_mut().fail_new = true;
+    }
+
+    /// Make merges fail (the worktree is left in place, like a merge conflict).
+    pub fn fail_merge(&self) {
+        self.inner.borrow_mut().fail_merge = true;
+    }
+
+    /// Make `new_worktree` report an empty branch name.
+    pub fn return_empty_branch(&self) {
+        self.inner.borrow_mut().empty_branch = true;
+    }
+
+    /// Worktrees that currently exist, as (path, branch).
+    pub fn worktrees(&self) -> Vec<(String, String)> {
+        self.inner
+            .borrow()
+            .worktrees
+            .iter()
+            .map(|(p, b)| (p.clone(), b.clone()))
+            .collect()
+    }
+
+    /// Paths of worktrees removed with a merge.
+    pub fn merged(&self) -> Vec<String> {
+        self.inner.borrow().merged.clone()
+    }
+
+    /// Paths of worktrees removed without merging.
+    pub fn discarded(&self) -> Vec<String> {
+        self.inner.borrow().discarded.clone()
+    }
+}
+
+impl WorktreeProvider for FakeWorktrees {
+    fn new_worktree(
+        &self,
+        cwd: &str,
+        branch_suffix: Option<&str>,
+        base: Option<&str>,
+    ) -> Result<NewOutput, CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.fail_new {
+            return Err(CcmError::Gj("injected new_worktree failure".to_string()));
+        }
+        inner.next_suffix += 1;
+        let suffix = match branch_suffix {
+            Some(s) => s.to_string(),
+            None => format!("rand{}", inner.next_suffix),
+        };
+        let worktree_path = format!("{}-{suffix}", cwd.trim_end_matches('/'));
+        let base = base.unwrap_or("main");
+        let branch = if inner.empty_branch {
+            String::new()
+        } else {
+            format!("gj/{base}-{suffix}")
+        };
+        inner
+            .worktrees
+            .insert(worktree_path.clone(), branch.clone());
+        Ok(NewOutput {
+            worktree_path,
+            branch,
+            base: Some(base.to_string()),
+        })
+    }
+
+    fn exit_worktree(&self, worktree_path: &str, merge: bool) -> Result<(), CcmError> {
+        let mut inner = self.inner.borrow_mut();
+        if !inner.worktrees.contains_key(worktree_path) {
+            return Err(CcmError::Gj(format!("no worktree at {worktree_path}")));
+        }
+        if merge && inner.fail_merge {
+            return Err(CcmError::Gj("injected merge conflict".to_string()));
+        }
+        inner.worktrees.remove(worktree_path);
+        if merge {
+            inner.merged.push(worktree_path.to_string());
+        } else {
+            inner.discarded.push(worktree_path.to_string());
+        }
+        Ok(())
+    }
+
+    fn branch_prefix(&self) -> Option<&str> {
+        Some("gj/")
+    }
+
+    fn branch_name(&self, _cwd: &str, suffix: &str, base: Option<&str>) -> Option<String> {
+        Some(format!("gj/{}-{suffix}", base.unwrap_or("main")))
+    }
+}
@@ -0,0 +1,328 @@
+use std::path::Path;
+
+use super::{expand_worktree_path, prefixed_branch, random_suffix, NewOutput, WorktreeProvider};
+use crate::config::GitConfig;
+use crate::error::CcmError;
+use crate::git;
+
+/// Built-in worktree provider using plain `git worktree`, for setups without gj.
+/// Branches are named `<prefix><base>-<suffix>` and a base branch is recorded
+/// in `branch.<branch>.ccmBase` so that `exit_worktree` knows where to merge.
+/// Sessions started from a tag or commit have no merge target.
+pub struct Git {
+    branch_prefix: String,
+    worktree_path: String,
+}
+
+impl Git {
+    pub fn new(config: &GitConfig) -> Self {
+        Self {
+            branch_prefix: config.branch_prefix.clone(),
+            worktree_path: config.worktree_path.clone(),
+        }
+    }
+
+    fn base_config_key(branch: &str) -> String {
+        format!("branch.{branch}.ccmBase")
+    }
+
+    /// Merge `branch` into `base` in the main worktree. Requires the session
+    /// worktree to be clean and `base` to be checked out in the main worktree.
+    fn merge_into_base(
+        &self,
+        worktree_path: &str,
+        main: &str,
+        branch: &str,
+    ) -> Result<(), CcmError> {
+        let base = git::run_optional(
+            worktree_path,
+            &["config", "--get", &Self::base_config_key(branch)],
+        )?
+        .ok_or_else(|| {
+            CcmError::Git(format!(
+                "cannot merge {branch}: it was not created from a local branch; merge it by hand"
+            ))
+        })?;
+
+        // Untracked files (plans, build output of merge checks) are not merged
+        // and do not block it
+        let status = git::run(
+            worktree_path,
+            &["status", "--porcelain", "--untracked-files=no"],
+        )?;
+        if !status.is_empty() {
+            return Err(CcmError::Git(format!(
+                "{worktree_path} has uncommitted changes; commit or discard them before merging"
+            )));
+        }
+
+        let checked_out = git::current_branch(main)?;
+        if checked_out != base {
+            return Err(CcmError::Git(format!(
+                "cannot merge {branch}: main worktree is on {checked_out}, not {base}"
+            )));
+        }
+
+        if let Err(e) = git::run(main, &["merge", "--no-edit", branch]) {
+            let _ = git::run(main, &["merge", "--abort"]);
+            return Err(e);
+        }
+        Ok(())
+    }
+}
+
+impl WorktreeProvider for Git {
+    /// Run `git worktree add -b <branch> <path> <base>`, where base defaults
+    /// to the branch checked out in `cwd`.
+    fn new_worktree(
+        &self,
+        cwd: &str,
+        branch_suffix: Option<&str>,
+        base: Option<&str>,
+    ) -> Result<NewOutput, CcmError> {
+        let main = git::main_worktree(cwd)?;
+        let base = match base {
+            Some(b) => {
+                git::run_optional(
+                    cwd,
+                    &[
+                        "rev-parse",
+                        "--verify",
+                        "--quiet",
+                        &format!("{b}^{{commit}}"),
+                    ],
+                )?
+                .ok_or_else(|| CcmError::Git(format!("unknown base ref '{b}'")))?;
+                b.to_string()
+            }
+            None => git::current_branch(cwd)?,
+        };
+        let suffix = match branch_suffix {
+            Some(s) => s.to_string(),
+            None => random_suffix(),
+        };
+        let branch = prefixed_branch(&self.branch_prefix, &base, &suffix);
+        let path = expand_worktree_path(&self.worktree_path, Path::new(&main), &base, &suffix);
+        if path.exists() {
+            return Err(CcmError::Git(format!(
+                "worktree path already exists: {}",
+                path.display()
+            )));
+        }
+        let worktree_path = path.to_string_lossy().into_owned();
+
+        git::run(
+            cwd,
+            &["worktree", "add", "-b", &branch, &worktree_path, &base],
+        )?;
+        if git::branch_exists(cwd, &base)? {
+            git::run(cwd, &["config", &Self::base_config_key(&branch), &base])?;
+        }
+
+        Ok(NewOutput {
+            worktree_path,
+            branch,
+            base: Some(base),
+        })
+    }
+
+    /// If `merge` is true, merge the branch into its base and remove the
+    /// worktree and branch, leaving both untouched if the merge fails.
+    /// Otherwise remove them with `--force` / `-D`.
+    fn exit_worktree(&self, worktree_path: &str, merge: bool) -> Result<(), CcmError> {
+        let branch = git::current_branch(worktree_path)?;
+        let main = git::main_worktree(worktree_path)?;
+        if Path::new(&main) == Path::new(&git::toplevel(worktree_path)?) {
+            return Err(CcmError::Git(format!(
+                "refusing to remove the main worktree {main}"
+            )));
+        }
+
+        if merge {
+            self.merge_into_base(worktree_path, &main, &branch)?;
+            git::run(&main, &["worktree", "remove", "--force", worktree_path])?;
+            git::run(&main, &["branch", "-d", &branch])?;
+        } else {
+            git::run(&main, &["worktree", "remove", "--force", worktree_path])?;
+            git::run(&main, &["branch", "-D", &branch])?;
+        }
+        Ok(())
+    }
+
+    fn branch_prefix(&self) -> Option<&str> {
+        Some(&self.branch_prefix)
+    }
+
+    fn branch_name(&self, cwd: &str, suffix: &str, base: Option<&str>) -> Option<String> {
+        let base = match base {
+            Some(b) => b.to_string(),
+            None => git::current_branch(cwd).ok()?,
+        };
+        Some(prefixed_branch(&self.branch_prefix, &base, suffix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repository with a README committed on `main`, inside its own temp dir so that
+    /// sibling worktrees are cleaned up too.
+    fn init_repo() -> (tempfile::TempDir, String) {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = git::test_repo(dir.path());
+        commit_file(&repo, "README");
+        (dir, repo)
+    }
+
+    fn provider() -> Git {
+        Git::new(&GitConfig::default())
+    }
+
+    fn commit_file(dir: &str, name: &str) {
+        std::fs::write(Path::new(dir).join(name), "change\n").unwrap();
+        git::run(dir, &["add", name]).unwrap();
+        git::run(dir, &["commit", "-q", "-m", name]).unwrap();
+    }
+
+    #[test]
+    fn new_worktree_creates_branch_from_current() {
+        let (dir, repo) = init_repo();
+        let out = provider().new_worktree(&repo, Some("feat"), None).unwrap();
+
+        assert_eq!(out.branch, "ccm/main-feat");
+        assert_eq!(out.base.as_deref(), Some("main"));
+        let expected = dir.path().join("repo-feat");
+        assert_eq!(out.worktree_path, expected.to_string_lossy());
+        assert!(expected.join("README").exists());
+        assert_eq!(
+            git::current_branch(&out.worktree_path).unwrap(),
+            "ccm/main-feat"
+        );
+        assert_eq!(
+            git::run(&repo, &["config", "--get", "branch.ccm/main-feat.ccmBase"]).unwrap(),
+            "main"
+        );
+    }
+
+    #[test]
+    fn new_worktree_from_explicit_ref() {
+        let (_dir, repo) = init_repo();
+        git::run(&repo, &["tag", "v1.0"]).unwrap();
+        commit_file(&repo, "later.txt");
+
+        let out = provider()
+            .new_worktree(&repo, Some("hotfix"), Some("v1.0"))
+            .unwrap();
+        assert_eq!(out.branch, "ccm/v1.0-hotfix");
+        assert_eq!(out.base.as_deref(), Some("v1.0"));
+        assert!(!Path::new(&out.worktree_path).join("later.txt").exists());
+
+        let err = provider()
+            .new_worktree(&repo, Some("x"), Some("no-such-ref"))
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown base ref 'no-such-ref'"));
+    }
+
+    #[test]
+    fn new_worktree_rejects_existing_path() {
+        let (dir, repo) = init_repo();
+        std::fs::create_dir(dir.path().join("repo-taken")).unwrap();
+        let err = provider()
+            .new_worktree(&repo, Some("taken"), None)
+            .unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+    }
+
+    #[test]
+    fn exit_with_merge_lands_commits_on_base() {
+        let (_dir, repo) = init_repo();
+        let out = provider().new_worktree(&repo, None, None).unwrap();
+        commit_file(&out.worktree_path, "feature.txt");
+        let plans = Path::new(&out.worktree_path).join(".ccm/plans");
+        std::fs::create_dir_all(&plans).unwrap();
+        std::fs::write(plans.join("init.md"), "plan\n").unwrap();
+
+        provider().exit_worktree(&out.worktree_path, true).unwrap();
+
+        assert!(Path::new(&repo).join("feature.txt").exists());
+        assert!(!Path::new(&out.worktree_path).exists());
+        assert_eq!(
+            git::run(&repo, &["branch", "--list", &out.branch]).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn exit_with_merge_refuses_tag_base() {
+        let (_dir, repo) = init_repo();
+        git::run(&repo, &["tag", "v1.0"]).unwrap();
+        let out = provider()
+            .new_worktree(&repo, Some("hotfix"), Some("v1.0"))
+            .unwrap();
+        commit_file(&out.worktree_path, "fix.txt");
+
+        let err = provider()
+            .exit_worktree(&out.worktree_path, true)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "git failed: cannot merge ccm/v1.0-hotfix: it was not created from a local branch; merge it by hand"
+        );
+        assert!(Path::new(&out.worktree_path).exists());
+        assert!(!Path::new(&repo).join("fix.txt").exists());
+    }
+
+    #[test]
+    fn exit_with_merge_refuses_dirty_worktree() {
+        let (_dir, repo) = init_repo();
+        let out = provider().new_worktree(&repo, Some("dirty"), None).unwrap();
+        std::fs::write(Path::new(&out.worktree_path).join("README"), "wip\n").unwrap();
+
+        let err = provider()
+            .exit_worktree(&out.worktree_path, true)
+            .unwrap_err();
+        assert!(err.to_string().contains("uncommitted changes"));
+        assert!(Path::new(&out.worktree_path).exists());
+    }
+
+    #[test]
+    fn exit_with_merge_aborts_on_conflict() {
+        let (_dir, repo) = init_repo();
+        let out = provider()
+            .new_worktree(&repo, Some("conflict"), None)
+            .unwrap();
+        std::fs::write(Path::new(&out.worktree_path).join("README"), "theirs\n").unwrap();
+        git::run(&out.worktree_path, &["commit", "-q", "-am", "theirs"]).unwrap();
+        std::fs::write(Path::new(&repo).join("README"), "ours\n").unwrap();
+        git::run(&repo, &["commit", "-q", "-am", "ours"]).unwrap();
+
+        assert!(provider().exit_worktree(&out.worktree_path, true).is_err());
+        assert_eq!(git::run(&repo, &["status", "--porcelain"]).unwrap(), "");
+        assert!(Path::new(&out.worktree_path).exists());
+    }
+
+    #[test]
+    fn exit_without_merge_discards_everything() {
+        let (_dir, repo) = init_repo();
+        let out = provider().new_worktree(&repo, Some("drop"), None).unwrap();
+        commit_file(&out.worktree_path, "unmerged.txt");
+        std::fs::write(Path::new(&out.worktree_path).join("wip.txt"), "wip\n").unwrap();
+
+        provider().exit_worktree(&out.worktree_path, false).unwrap();
+
+        assert!(!Path::new(&out.worktree_path).exists());
+        assert!(!Path::new(&repo).join("unmerged.txt").exists());
+        assert_eq!(
+            git::run(&repo, &["branch", "--list", &out.branch]).unwrap(),
+            ""
+        );
+    }
+
+    #[test]
+    fn exit_refuses_main_worktree() {
+        let (_dir, repo) = init_repo();
+        let err = provider().exit_worktree(&repo, false).unwrap_err();
+        assert!(err.to_string().contains("main worktree"));
+    }
+}
@@ -0,0 +1,129 @@
+use std::process::Command;
+
+use super::{prefixed_branch, NewOutput, WorktreeProvider};
+use crate::error::CcmError;
+use crate::git;
+
+/// Worktree provider backed by the `gj` CLI.
+pub struct Gj;
+
+impl WorktreeProvider for Gj {
+    /// Run `gj new [branch-suffix] --output=json` in the given directory.
+    /// If branch_suffix is None, uses `--random-suffix` instead.
+    /// Returns the parsed worktree information, with the branch checked out in
+    /// `cwd` as its base. gj always branches from that branch, and ccm does not
+    /// switch the user's checkout, so an explicit `base` is rejected.
+    fn new_worktree(
+        &self,
+        cwd: &str,
+        branch_suffix: Option<&str>,
+        base: Option<&str>,
+    ) -> Result<NewOutput, CcmError> {
+        if let Some(base) = base {
+            return Err(CcmError::Gj(format!(
+                "gj new cannot branch from '{base}'; check it out in --cwd or set worktree.provider = \"git\""
+            )));
+        }
+        let current = git::current_branch(cwd).ok();
+
+        let mut output = gj_new(cwd, branch_suffix)?;
+        // gj does not report the base; it is whatever was checked out
+        if output.base.is_none() {
+            output.base = current;
+        }
+        Ok(output)
+    }
+
+    /// Run `gj exit` in the given worktree directory.
+    /// If `merge` is true, uses `--merge`; otherwise uses `--force`.
+    fn exit_worktree(&self, worktree_path: &str, merge: bool) -> Result<(), CcmError> {
+        let flag = if merge { "--merge" } else { "--force" };
+        let output = Command::new("gj")
+            .args(["exit", flag])
+            .current_dir(worktree_path)
+            .output()
+            .map_err(|e| CcmError::Gj(format!("failed to run gj exit: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::Gj(format!("gj exit {flag} failed: {stderr}")));
+        }
+        Ok(())
+    }
+
+    /// gj names its branches `gj/<base>-<suffix>`.
+    fn branch_prefix(&self) -> Option<&str> {
+        Some("gj/")
+    }
+
+    fn branch_name(&self, cwd: &str, suffix: &str, base: Option<&str>) -> Option<String> {
+        let base = match base {
+            Some(b) => b.to_string(),
+            None => git::current_branch(cwd).ok()?,
+        };
+        Some(prefixed_branch("gj/", &base, suffix))
+    }
+}
+
+fn gj_new(cwd: &str, branch_suffix: Option<&str>) -> Result<NewOutput, CcmError> {
+    let mut cmd = Command::new("gj");
+    cmd.arg("new");
+
+    if let Some(suffix) = branch_suffix {
+        cmd.arg(suffix);
+    } else {
+        cmd.arg("--random-suffix");
+    }
+
+    cmd.arg("--output=json");
+    cmd.current_dir(cwd);
+
+    let output = cmd
+        .output()
+        .map_err(|e| CcmError::Gj(format!("failed to run gj new: {e}")))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(CcmError::Gj(format!("gj new failed: {stderr}")));
+    }
+
+    parse_new_output(&output.stdout)
+}
+
+fn parse_new_output(bytes: &[u8]) -> Result<NewOutput, CcmError> {
+    serde_json::from_slice(bytes)
+        .map_err(|e| CcmError::Gj(format!("failed to parse gj new output: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_gj_new_output() {
+        let json = r#"{"worktree_path":"/tmp/repo-abc123","branch":"gj/main-abc123"}"#;
+        let result = parse_new_output(json.as_bytes()).unwrap();
+        assert_eq!(result.worktree_path, "/tmp/repo-abc123");
+        assert_eq!(result.branch, "gj/main-abc123");
+        assert_eq!(result.base, None);
+    }
+
+    #[test]
+    fn new_worktree_rejects_base() {
+        let err = Gj.new_worktree("/", None, Some("v1.0")).unwrap_err();
+        assert!(err.to_string().contains("cannot branch from 'v1.0'"));
+    }
+
+    #[test]
+    fn parse_new_output_missing_field() {
+        let json = r#"{"worktree_path":"/tmp/repo"}"#;
+        let result = parse_new_output(json.as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_new_output_invalid_json() {
+        let result = parse_new_output(b"not json");
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,358 @@
+use std::path::Path;
+use std::process::Command;
+
+use super::{expand_worktree_path, random_suffix, NewOutput, WorktreeProvider};
+use crate::config::{JjConfig, JjMergeStrategy};
+use crate::error::CcmError;
+
+/// Base ref of the workspace, relative to the workspace root.
+const BASE_FILE: &str = ".jj/ccm-base";
+
+/// Worktree provider backed by jj workspaces (works with colocated repos).
+/// Each session gets a workspace named after its directory, starting on top of
+/// trunk (or an explicit base), and a bookmark `<prefix><suffix>` that serves
+/// as the session branch. An explicit base is recorded in the workspace's
+/// `.jj/ccm-base` so that merges go back onto it instead of trunk.
+pub struct Jj {
+    binary: String,
+    trunk: String,
+    bookmark_prefix: String,
+    workspace_path: String,
+    merge: JjMergeStrategy,
+}
+
+impl Jj {
+    pub fn new(config: &JjConfig) -> Self {
+        Self {
+            binary: config.binary.clone(),
+            trunk: config.trunk.clone(),
+            bookmark_prefix: config.bookmark_prefix.clone(),
+            workspace_path: config.workspace_path.clone(),
+            merge: config.merge,
+        }
+    }
+
+    /// Run `jj <args>` in `dir` and return its trimmed stdout.
+    fn run(&self, dir: &str, args: &[&str]) -> Result<String, CcmError> {
+        let subcommand = args.first().copied().unwrap_or_default();
+        let output = Command::new(&self.binary)
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .map_err(|e| CcmError::Jj(format!("failed to run jj {subcommand}: {e}")))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(CcmError::Jj(format!(
+                "jj {subcommand} failed: {}",
+                stderr.trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// Run a `jj log` template over `revset` without the graph.
+    fn log(&self, dir: &str, revset: &str, template: &str) -> Result<String, CcmError> {
+        self.run(dir, &["log", "--no-graph", "-r", revset, "-T", template])
+    }
+
+    /// Where the session in `dir` merges to: its recorded base, else trunk.
+    fn merge_target(&self, dir: &str) -> String {
+        std::fs::read_to_string(Path::new(dir).join(BASE_FILE))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| self.trunk.clone())
+    }
+
+    /// Session bookmarks between the merge target and the working copy.
+    fn session_bookmarks(&self, dir: &str, target: &str) -> Result<Vec<String>, CcmError> {
+        let out = self.run(
+            dir,
+            &[
+                "bookmark",
+                "list",
+                "-r",
+                &session_revset(target),
+                "-T",
+                "name ++ \"\\n\"",
+            ],
+        )?;
+        Ok(parse_bookmarks(&out, &self.bookmark_prefix))
+    }
+
+    /// Rebase the session onto `target` (optionally squashing it) and, if
+    /// `target` is a bookmark, move it to the session's tip. A revset or
+    /// commit ID base has no bookmark to move. On conflicts the operation is
+    /// restored, so the repository is left exactly as it was.
+    fn merge_into(&self, dir: &str, target: &str) -> Result<(), CcmError> {
+        let op = self.run(dir, &["op", "log", "--no-graph", "-n", "1", "-T", "id"])?;
+        let result = self.rebase_onto(dir, target);
+        if result.is_err() {
+            let _ = self.run(dir, &["op", "restore", &op]);
+        }
+        result
+    }
+
+    fn rebase_onto(&self, dir: &str, target: &str) -> Result<(), CcmError> {
+        // Make the working-copy changes a regular commit so that @- is the tip
+        if self.log(dir, "@", "empty")? != "true" {
+            self.run(dir, &["new"])?;
+        }
+        let tip = "@-";
+        if self
+            .log(dir, &format!("{target}..{tip}"), "change_id")?
+            .is_empty()
+        {
+            return Ok(());
+        }
+
+        self.run(dir, &["rebase", "-b", "@", "-d", target])?;
+
+        let conflicts = self.log(
+            dir,
+            &format!("({}) & conflicts()", session_revset(target)),
+            "change_id.short() ++ \"\\n\"",
+        )?;
+        if !conflicts.is_empty() {
+            return Err(CcmError::Jj(format!(
+                "rebasing onto {target} produced conflicts in: {}",
+                conflicts.lines().collect::<Vec<_>>().join(", ")
+            )));
+        }
+
+        if self.merge == JjMergeStrategy::Squash {
+            let range = format!("{target}..{tip}");
+            let root = format!("roots({range})");
+            if !self
+                .log(dir, &format!("({range}) ~ {root}"), "change_id")?
+                .is_empty()
+            {
+                self.run(
+                    dir,
+                    &[
+                        "squash",
+                        "--from",
+                        &format!("({range}) ~ {root}"),
+                        "--into",
+                        &root,
+                        "--use-destination-message",
+                    ],
+                )?;
+            }
+        }
+
+        if self.is_bookmark(dir, target)? {
+            self.run(dir, &["bookmark", "set", target, "-r", tip])?;
+        }
+        Ok(())
+    }
+
+    /// Whether `name` is a local bookmark (and not a revset or commit ID).
+    fn is_bookmark(&self, dir: &str, name: &str) -> Result<bool, CcmError> {
+        Ok(!self.log(dir, &bookmark_revset(name), "change_id")?.is_empty())
+    }
+}
+
+impl WorktreeProvider for Jj {
+    /// Run `jj workspace add -r <base>` (trunk by default) and create the
+    /// session bookmark on the new working-copy commit.
+    fn new_worktree(
+        &self,
+        cwd: &str,
+        branch_suffix: Option<&str>,
+        base: Option<&str>,
+    ) -> Result<NewOutput, CcmError> {
+        let root = self.run(cwd, &["root"])?;
+        let revision = base.unwrap_or(&self.trunk);
+        let suffix = match branch_suffix {
+            Some(s) => s.to_string(),
+            None => random_suffix(),
+        };
+        let bookmark = format!("{}{suffix}", self.bookmark_prefix);
+        let path = expand_worktree_path(&self.workspace_path, Path::new(&root), revision, &suffix);
+        if path.exists() {
+            return Err(CcmError::Jj(format!(
+                "workspace path already exists: {}",
+                path.display()
+            )));
+        }
+        let worktree_path = path.to_string_lossy().into_owned();
+        let name = workspace_name(&worktree_path)?;
+
+        self.run(
+            cwd,
+            &[
+                "workspace",
+                "add",
+                "--name",
+                &name,
+                "-r",
+                revision,
+                &worktree_path,
+            ],
+        )?;
+        let created = self
+            .run(
+                &worktree_path,
+                &["bookmark", "create", &bookmark, "-r", "@"],
+            )
+            .and_then(|_| match base {
+                Some(base) => std::fs::write(Path::new(&worktree_path).join(BASE_FILE), base)
+                    .map_err(|e| CcmError::Jj(format!("failed to record base ref: {e}"))),
+                None => Ok(()),
+            });
+        if let Err(e) = created {
+            let _ = self.run(cwd, &["workspace", "forget", &name]);
+            let _ = std::fs::remove_dir_all(&worktree_path);
+            return Err(e);
+        }
+
+        Ok(NewOutput {
+            worktree_path,
+            branch: bookmark,
+            base: Some(revision.to_string()),
+        })
+    }
+
+    /// Forget the workspace, delete its bookmark and remove the directory.
+    /// If `merge` is true, the session is first rebased (or squashed) onto
+    /// its base and nothing is removed if that fails; otherwise its commits
+    /// are abandoned.
+    fn exit_worktree(&self, worktree_path: &str, merge: bool) -> Result<(), CcmError> {
+        let name = workspace_name(worktree_path)?;
+        let workspaces = self.run(worktree_path, &["workspace", "list"])?;
+        if name == "default" || !parse_workspace_names(&workspaces).contains(&name) {
+            return Err(CcmError::Jj(format!(
+                "{worktree_path} is not a ccm workspace (expected a workspace named {name})"
+            )));
+        }
+        let target = self.merge_target(worktree_path);
+        let bookmarks = self.session_bookmarks(worktree_path, &target)?;
+
+        if merge {
+            self.merge_into(worktree_path, &target)?;
+        } else {
+            self.run(worktree_path, &["abandon", &session_revset(&target)])?;
+        }
+
+        for bookmark in &bookmarks {
+            self.run(worktree_path, &["bookmark", "delete", bookmark])?;
+        }
+        self.run(worktree_path, &["workspace", "forget", &name])?;
+        std::fs::remove_dir_all(worktree_path)
+            .map_err(|e| CcmError::Jj(format!("failed to remove {worktree_path}: {e}")))?;
+        Ok(())
+    }
+
+    /// jj workspaces are not git worktrees.
+    fn branch_prefix(&self) -> Option<&str> {
+        None
+    }
+
+    /// The session's bookmark, which does not depend on the base.
+    fn branch_name(&self, _cwd: &str, suffix: &str, _base: Option<&str>) -> Option<String> {
+        Some(format!("{}{suffix}", self.bookmark_prefix))
+    }
+}
+
+/// Commits of the session that are not on `target` yet.
+fn session_revset(target: &str) -> String {
+    format!("{target}..@")
+}
+
+/// The commit of the local bookmark named exactly `name`; empty if there is
+/// no such bookmark.
+fn bookmark_revset(name: &str) -> String {
+    let quoted = name.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("bookmarks(exact:\"{quoted}\")")
+}
+
+/// Workspaces are named after their directory, as `jj workspace add` does by
+/// default, so that the name can be recovered from the path on exit.
+fn workspace_name(worktree_path: &str) -> Result<String, CcmError> {
+    Path::new(worktree_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| CcmError::Jj(format!("invalid workspace path: {worktree_path}")))
+}
+
+/// Workspace names from `jj workspace list` (`<name>: <commit summary>`).
+fn parse_workspace_names(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once(": "))
+        .map(|(name, _)| name.trim().to_string())
+        .collect()
+}
+
+fn parse_bookmarks(output: &str, prefix: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|name| !name.is_empty() && name.starts_with(prefix))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn workspace_name_from_path() {
+        assert_eq!(workspace_name("/src/repo-abc123").unwrap(), "repo-abc123");
+        assert!(workspace_name("/").is_err());
+    }
+
+    #[test]
+    fn parse_workspace_names_from_list() {
+        let out = "default: qpvuntsm 230dd059 (empty) (no description set)\nrepo-abc123: rlvkpnrz 1b2c3d4e ccm/abc123 | fix parser\n";
+        assert_eq!(parse_workspace_names(out), ["default", "repo-abc123"]);
+        assert!(parse_workspace_names("").is_empty());
+    }
+
+    #[test]
+    fn parse_bookmarks_keeps_session_prefix() {
+        let out = "ccm/abc123\nfeature-x\n\nccm/abc123-fix\n";
+        assert_eq!(
+            parse_bookmarks(out, "ccm/"),
+            ["ccm/abc123", "ccm/abc123-fix"]
+        );
+        assert!(parse_bookmarks("", "ccm/").is_empty());
+    }
+
+    #[test]
+    fn merge_target_prefers_recorded_base() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().to_string_lossy().into_owned();
+        let jj = Jj::new(&JjConfig::default());
+        assert_eq!(jj.merge_target(&path), "main");
+        assert_eq!(session_revset(&jj.merge_target(&path)), "main..@");
+
+        std::fs::create_dir(dir.path().join(".jj")).unwrap();
+        std::fs::write(dir.path().join(BASE_FILE), "release-1.2\n").unwrap();
+        assert_eq!(jj.merge_target(&path), "release-1.2");
+    }
+
+    #[test]
+    fn bookmark_revset_matches_exact_name() {
+        assert_eq!(bookmark_revset("main"), r#"bookmarks(exact:"main")"#);
+        assert_eq!(
+            bookmark_revset(r#"main-" | @"#),
+            r#"bookmarks(exact:"main-\" | @")"#
+        );
+    }
+
+    #[test]
+    fn missing_binary_reports_jj_error() {
+        let jj = Jj::new(&JjConfig {
+            binary: "ccm-test-no-such-jj".to_string(),
+            ..JjConfig::default()
+        });
+        let err = jj.new_worktree("/", None, None).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("jj failed: failed to run jj root"));
+    }
+}
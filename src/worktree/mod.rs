@@ -0,0 +1,216 @@
+mod git;
+mod gj;
+mod jj;
+
+#[cfg(test)]
+pub mod fake;
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::config::{Config, WorktreeBackend};
+use crate::error::CcmError;
+
+pub use self::git::Git;
+pub use self::gj::Gj;
+pub use self::jj::Jj;
+
+const SUFFIX_LEN: usize = 6;
+
+/// A freshly created worktree.
+#[derive(Debug, Deserialize)]
+pub struct NewOutput {
+    pub worktree_path: String,
+    pub branch: String,
+    /// Ref the branch was created from, if the provider reports it.
+    #[serde(default)]
+    pub base: Option<String>,
+}
+
+/// Creates and tears down the git worktree (or jj workspace) backing a session.
+pub trait WorktreeProvider {
+    /// Create a worktree for the repository at `cwd`.
+    /// If `branch_suffix` is None, a random suffix is used. If `base` is None,
+    /// the provider's default base is used (e.g. the branch checked out in `cwd`).
+    fn new_worktree(
+        &self,
+        cwd: &str,
+        branch_suffix: Option<&str>,
+        base: Option<&str>,
+    ) -> Result<NewOutput, CcmError>;
+
+    /// Remove the worktree. If `merge` is true, its branch is merged first and
+    /// the worktree is only removed if the merge succeeds; otherwise the worktree
+    /// is discarded forcibly.
+    fn exit_worktree(&self, worktree_path: &str, merge: bool) -> Result<(), CcmError>;
+
+    /// Prefix of the git branches this provider creates, which tells its
+    /// worktrees apart from hand-made ones (`ccm gc`). None if sessions are
+    /// not git worktrees.
+    fn branch_prefix(&self) -> Option<&str>;
+
+    /// Branch `new_worktree(cwd, Some(suffix), base)` would create, so that a
+    /// new session can be named without colliding. None if it cannot be told
+    /// in advance.
+    fn branch_name(&self, cwd: &str, suffix: &str, base: Option<&str>) -> Option<String>;
+}
+
+/// Branch of a provider that names them `<prefix><base>-<suffix>`
+/// (`gj/main-feat`).
+pub fn prefixed_branch(prefix: &str, base: &str, suffix: &str) -> String {
+    format!("{prefix}{}-{suffix}", ref_slug(base))
+}
+
+/// Whether the session or branch `existing` is called `name`, or is the branch
+/// a provider with branch prefix `prefix` created for `name` on `base`
+/// (`gj/main-feat` for `feat`, but not `gj/main-fix-feat`).
+pub fn is_named(existing: &str, name: &str, prefix: Option<&str>, base: Option<&str>) -> bool {
+    existing == name
+        || matches!((prefix, base), (Some(p), Some(b)) if existing == prefixed_branch(p, b, name))
+}
+
+/// Build the worktree provider selected by `worktree.provider`.
+pub fn from_config(config: &Config) -> Box<dyn WorktreeProvider> {
+    match config.worktree.provider {
+        WorktreeBackend::Gj => Box::new(Gj),
+        WorktreeBackend::Git => Box::new(Git::new(&config.git)),
+        WorktreeBackend::Jj => Box::new(Jj::new(&config.jj)),
+    }
+}
+
+/// Where a worktree for an existing `branch` goes when ccm has to create one
+/// (e.g. `ccm adopt <branch>`): `git.worktree_path` with the branch as base
+/// and suffix.
+pub fn branch_worktree_path(config: &Config, main: &Path, branch: &str) -> PathBuf {
+    expand_worktree_path(&config.git.worktree_path, main, branch, &ref_slug(branch))
+}
+
+/// Substitute `{repo}`, `{base}` and `{suffix}` in the configured worktree path
+/// and resolve it against the main worktree. `..` is folded so that the path
+/// stored in state.json matches what the multiplexer reports as pane cwd.
+fn expand_worktree_path(template: &str, main: &Path, base: &str, suffix: &str) -> PathBuf {
+    let repo = main
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let expanded = template
+        .replace("{repo}", &repo)
+        .replace("{base}", &ref_slug(base))
+        .replace("{suffix}", &ref_slug(suffix));
+
+    let mut path = PathBuf::new();
+    for component in main.join(expanded).components() {
+        match component {
+            Component::ParentDir => {
+                path.pop();
+            }
+            Component::CurDir => {}
+            other => path.push(other),
+        }
+    }
+    path
+}
+
+/// Make a ref (`release/1.2`, `v1.0^`, `HEAD~2`) usable inside a branch name
+/// or path component.
+fn ref_slug(reference: &str) -> String {
+    reference
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches(|c| c == '-' || c == '.')
+        .to_string()
+}
+
+/// Random lowercase alphanumeric suffix, like `gj new --random-suffix`.
+pub fn random_suffix() -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    let mut value = hasher.finish();
+    (0..SUFFIX_LEN)
+        .map(|_| {
+            let c = ALPHABET[(value % ALPHABET.len() as u64) as usize] as char;
+            value /= ALPHABET.len() as u64;
+            c
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_named_matches_exact_branch_only() {
+        assert!(is_named("login", "login", None, None));
+        assert!(is_named("gj/main-login", "login", Some("gj/"), Some("main")));
+        assert!(!is_named("gj/main-fix-login", "login", Some("gj/"), Some("main")));
+        assert!(!is_named("gj/main-login-2", "login", Some("gj/"), Some("main")));
+        assert!(!is_named("gj/main-login", "login", Some("gj/"), None));
+        assert!(!is_named("gj/dev-login", "login", Some("gj/"), Some("main")));
+    }
+
+    #[test]
+    fn prefixed_branch_slugs_base() {
+        assert_eq!(prefixed_branch("gj/", "main", "fix"), "gj/main-fix");
+        assert_eq!(
+            prefixed_branch("gj/", "release/1.2", "fix"),
+            "gj/release-1.2-fix"
+        );
+    }
+
+    #[test]
+    fn expand_worktree_path_resolves_relative() {
+        let main = Path::new("/src/repo");
+        assert_eq!(
+            expand_worktree_path("../{repo}-{suffix}", main, "main", "abc"),
+            PathBuf::from("/src/repo-abc")
+        );
+        assert_eq!(
+            expand_worktree_path(".worktrees/{base}-{suffix}", main, "feat/x", "abc"),
+            PathBuf::from("/src/repo/.worktrees/feat-x-abc")
+        );
+        assert_eq!(
+            expand_worktree_path("/tmp/wt/{suffix}", main, "main", "abc"),
+            PathBuf::from("/tmp/wt/abc")
+        );
+        // Names from naming.template may contain slashes
+        assert_eq!(
+            expand_worktree_path("../{repo}-{suffix}", main, "main", "alice/PROJ-1-x"),
+            PathBuf::from("/src/repo-alice-PROJ-1-x")
+        );
+    }
+
+    #[test]
+    fn ref_slug_replaces_special_characters() {
+        assert_eq!(ref_slug("main"), "main");
+        assert_eq!(ref_slug("release/1.2"), "release-1.2");
+        assert_eq!(ref_slug("HEAD~2"), "HEAD-2");
+        assert_eq!(ref_slug("refs/tags/v1.0^"), "refs-tags-v1.0");
+        assert_eq!(ref_slug("gj/認証-実装"), "gj-認証-実装");
+    }
+
+    #[test]
+    fn random_suffix_is_alphanumeric() {
+        let suffix = random_suffix();
+        assert_eq!(suffix.len(), SUFFIX_LEN);
+        assert!(suffix
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+    }
+}